@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS password_history_user_id_index;
+DROP TABLE IF EXISTS password_history;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS user_invites_user_id_index;
+DROP TABLE IF EXISTS user_invites;
\ No newline at end of file
@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS user_roles_role_id_index;
+DROP TABLE IF EXISTS user_roles;
+DROP TABLE IF EXISTS role_permissions;
+DROP TABLE IF EXISTS permissions;
+DROP TABLE IF EXISTS roles;
@@ -0,0 +1,55 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS roles (
+    id UUID PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS permissions (
+    id UUID PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT
+);
+
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role_id UUID NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    permission_id UUID NOT NULL REFERENCES permissions(id) ON DELETE CASCADE,
+    PRIMARY KEY (role_id, permission_id)
+);
+
+CREATE TABLE IF NOT EXISTS user_roles (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role_id UUID NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (user_id, role_id)
+);
+
+CREATE INDEX IF NOT EXISTS user_roles_role_id_index ON user_roles(role_id);
+
+INSERT INTO permissions (id, name, description) VALUES
+    (gen_random_uuid(), 'users:read', 'List users and view any user'),
+    (gen_random_uuid(), 'users:update', 'Update any user'),
+    (gen_random_uuid(), 'users:delete', 'Delete any user'),
+    (gen_random_uuid(), 'users:import', 'Bulk import users'),
+    (gen_random_uuid(), 'users:export', 'Bulk export users'),
+    (gen_random_uuid(), 'sessions:revoke', 'Revoke the sessions of any user'),
+    (gen_random_uuid(), 'roles:read', 'List roles and permissions'),
+    (gen_random_uuid(), 'roles:manage', 'Create, update and delete roles')
+ON CONFLICT (name) DO NOTHING;
+
+-- The built-in admin role holds every permission and replaces the is_admin flag for authorization
+INSERT INTO roles (id, name, description, created_at, updated_at)
+VALUES (gen_random_uuid(), 'admin', 'Full administrative access', NOW(), NOW())
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id FROM roles CROSS JOIN permissions
+WHERE roles.name = 'admin'
+ON CONFLICT DO NOTHING;
+
+INSERT INTO user_roles (user_id, role_id, created_at)
+SELECT users.id, roles.id, NOW() FROM users CROSS JOIN roles
+WHERE users.is_admin AND roles.name = 'admin'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,26 @@
+-- Add down migration script here
+-- Users of organizations are kept as global users, which is only possible while no two
+-- users share a username or email; otherwise they must be dealt with by hand first
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM users GROUP BY username HAVING COUNT(*) > 1)
+        OR EXISTS (SELECT 1 FROM users GROUP BY email HAVING COUNT(*) > 1) THEN
+        RAISE EXCEPTION 'Users of different organizations share a username or email';
+    END IF;
+END $$;
+
+DELETE FROM roles WHERE name = 'org_admin';
+DELETE FROM permissions WHERE name IN ('orgs:read', 'orgs:manage', 'members:manage');
+
+DROP INDEX IF EXISTS users_org_id_index;
+DROP INDEX IF EXISTS users_global_email_unique;
+DROP INDEX IF EXISTS users_global_username_unique;
+DROP INDEX IF EXISTS users_org_email_unique;
+DROP INDEX IF EXISTS users_org_username_unique;
+ALTER TABLE users ADD CONSTRAINT users_username_key UNIQUE (username);
+ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email);
+ALTER TABLE users DROP COLUMN IF EXISTS org_id;
+
+DROP INDEX IF EXISTS memberships_user_id_index;
+DROP TABLE IF EXISTS memberships;
+DROP TABLE IF EXISTS organizations;
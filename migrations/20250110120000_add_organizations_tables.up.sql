@@ -0,0 +1,58 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS organizations (
+    id UUID PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS memberships (
+    org_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role_id UUID REFERENCES roles(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (org_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS memberships_user_id_index ON memberships(user_id);
+
+-- Users created inside an organization belong to it; usernames and emails are unique per
+-- organization, and among users outside any organization. Uniqueness across organizations,
+-- when configured, is enforced by the application. Organizations cannot be deleted from
+-- under their users, which the application deletes first so that their deletion is recorded.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS org_id UUID REFERENCES organizations(id) ON DELETE RESTRICT;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_username_key;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+
+CREATE UNIQUE INDEX IF NOT EXISTS users_org_username_unique ON users(org_id, username);
+CREATE UNIQUE INDEX IF NOT EXISTS users_org_email_unique ON users(org_id, email);
+CREATE UNIQUE INDEX IF NOT EXISTS users_global_username_unique
+    ON users(username) WHERE org_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS users_global_email_unique
+    ON users(email) WHERE org_id IS NULL;
+CREATE INDEX IF NOT EXISTS users_org_id_index ON users(org_id);
+
+INSERT INTO permissions (id, name, description) VALUES
+    (gen_random_uuid(), 'orgs:read', 'List organizations and their members'),
+    (gen_random_uuid(), 'orgs:manage', 'Create and delete organizations and manage any membership'),
+    (gen_random_uuid(), 'members:manage', 'Manage the memberships of the current organization')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id FROM roles CROSS JOIN permissions
+WHERE roles.name = 'admin' AND permissions.name IN ('orgs:read', 'orgs:manage', 'members:manage')
+ON CONFLICT DO NOTHING;
+
+-- Membership role for organization administrators
+INSERT INTO roles (id, name, description, created_at, updated_at)
+VALUES (gen_random_uuid(), 'org_admin', 'Manages the users of an organization', NOW(), NOW())
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id FROM roles CROSS JOIN permissions
+WHERE roles.name = 'org_admin' AND permissions.name IN (
+    'users:read', 'users:update', 'users:delete', 'users:import', 'users:export',
+    'sessions:revoke', 'members:manage'
+)
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DELETE FROM permissions WHERE name = 'users:impersonate';
+DROP TABLE IF EXISTS impersonations;
@@ -0,0 +1,21 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS impersonations (
+    id UUID PRIMARY KEY NOT NULL,
+    actor_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason VARCHAR(255),
+    started_at TIMESTAMPTZ NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    ended_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS impersonations_started_at_index ON impersonations(started_at);
+
+INSERT INTO permissions (id, name, description)
+VALUES (gen_random_uuid(), 'users:impersonate', 'Log in as any user with fewer permissions')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id FROM roles CROSS JOIN permissions
+WHERE roles.name = 'admin' AND permissions.name = 'users:impersonate'
+ON CONFLICT DO NOTHING;
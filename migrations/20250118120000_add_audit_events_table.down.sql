@@ -0,0 +1,3 @@
+-- Add down migration script here
+DELETE FROM permissions WHERE name = 'audit:read';
+DROP TABLE IF EXISTS audit_events;
@@ -0,0 +1,26 @@
+-- Add up migration script here
+-- Actors and targets are not foreign keys so that events outlive deleted users
+CREATE TABLE IF NOT EXISTS audit_events (
+    id UUID PRIMARY KEY NOT NULL,
+    event_type VARCHAR(64) NOT NULL,
+    actor_id UUID,
+    target_id UUID,
+    ip TEXT,
+    user_agent TEXT,
+    details JSONB,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_events_created_at_index ON audit_events(created_at);
+CREATE INDEX IF NOT EXISTS audit_events_actor_id_index ON audit_events(actor_id);
+CREATE INDEX IF NOT EXISTS audit_events_target_id_index ON audit_events(target_id);
+CREATE INDEX IF NOT EXISTS audit_events_event_type_index ON audit_events(event_type);
+
+INSERT INTO permissions (id, name, description)
+VALUES (gen_random_uuid(), 'audit:read', 'Browse the audit log')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id FROM roles CROSS JOIN permissions
+WHERE roles.name = 'admin' AND permissions.name = 'audit:read'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS audit_events_seq_index;
+ALTER TABLE audit_events DROP COLUMN IF EXISTS hash;
+ALTER TABLE audit_events DROP COLUMN IF EXISTS prev_hash;
+ALTER TABLE audit_events DROP COLUMN IF EXISTS seq;
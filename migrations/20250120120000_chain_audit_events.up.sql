@@ -0,0 +1,7 @@
+-- Add up migration script here
+-- Events recorded before chaining keep a NULL hash and are reported as unchained
+ALTER TABLE audit_events ADD COLUMN IF NOT EXISTS seq BIGSERIAL;
+ALTER TABLE audit_events ADD COLUMN IF NOT EXISTS prev_hash TEXT;
+ALTER TABLE audit_events ADD COLUMN IF NOT EXISTS hash TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS audit_events_seq_index ON audit_events(seq);
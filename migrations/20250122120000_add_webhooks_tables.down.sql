@@ -0,0 +1,5 @@
+-- Add down migration script here
+DELETE FROM permissions WHERE name = 'webhooks:manage';
+DROP TABLE IF EXISTS webhook_deliveries;
+DROP TABLE IF EXISTS webhook_subscriptions;
+DROP TABLE IF EXISTS outbox_events;
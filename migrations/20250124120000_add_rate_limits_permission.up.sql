@@ -0,0 +1,9 @@
+-- Add up migration script here
+INSERT INTO permissions (id, name, description)
+VALUES (gen_random_uuid(), 'rate_limits:read', 'View the usage of every rate limit policy')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id FROM roles CROSS JOIN permissions
+WHERE roles.name = 'admin' AND permissions.name = 'rate_limits:read'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE sessions DROP COLUMN IF EXISTS last_used_at;
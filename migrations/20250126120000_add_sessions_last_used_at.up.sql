@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- Existing sessions are considered last used when they were last updated
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS last_used_at TIMESTAMPTZ;
+UPDATE sessions SET last_used_at = updated_at WHERE last_used_at IS NULL;
+ALTER TABLE sessions ALTER COLUMN last_used_at SET NOT NULL;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- Persistent sessions are opened with "remember me" and outlive the browser session
+ALTER TABLE sessions ADD COLUMN is_persistent BOOLEAN NOT NULL DEFAULT FALSE;
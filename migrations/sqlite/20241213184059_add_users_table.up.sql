@@ -0,0 +1,23 @@
+-- Add up migration script here
+-- Mirrors the Postgres users table, including the columns added by later migrations.
+-- UUIDs are stored as blobs and timestamps as RFC 3339 text.
+CREATE TABLE IF NOT EXISTS users (
+    id BLOB PRIMARY KEY NOT NULL,
+    github_id INTEGER UNIQUE,
+    username TEXT NOT NULL,
+    email TEXT NOT NULL,
+    is_admin BOOLEAN NOT NULL,
+    password_hash TEXT NOT NULL,
+    avatar_url TEXT,
+    org_id BLOB,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS users_org_username_unique
+    ON users (COALESCE(org_id, X''), username);
+CREATE UNIQUE INDEX IF NOT EXISTS users_org_email_unique
+    ON users (COALESCE(org_id, X''), email);
+CREATE INDEX IF NOT EXISTS users_email_index ON users(email);
+CREATE INDEX IF NOT EXISTS users_username_index ON users(username);
+CREATE INDEX IF NOT EXISTS users_github_id_index ON users(github_id);
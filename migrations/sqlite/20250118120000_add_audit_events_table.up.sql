@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- Actors and targets are not foreign keys so that events outlive deleted users.
+-- Events are not chained, so the hash columns of the Postgres table are left out.
+CREATE TABLE IF NOT EXISTS audit_events (
+    id BLOB PRIMARY KEY NOT NULL,
+    event_type TEXT NOT NULL,
+    actor_id BLOB,
+    target_id BLOB,
+    ip TEXT,
+    user_agent TEXT,
+    details TEXT,
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_events_created_at_index ON audit_events(created_at);
+CREATE INDEX IF NOT EXISTS audit_events_actor_id_index ON audit_events(actor_id);
+CREATE INDEX IF NOT EXISTS audit_events_target_id_index ON audit_events(target_id);
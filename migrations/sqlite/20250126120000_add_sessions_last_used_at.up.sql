@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- SQLite cannot add a NOT NULL column without a constant default, so existing sessions are
+-- backfilled instead; new sessions always set it
+ALTER TABLE sessions ADD COLUMN last_used_at DATETIME;
+UPDATE sessions SET last_used_at = updated_at WHERE last_used_at IS NULL;
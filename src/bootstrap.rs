@@ -1,24 +1,43 @@
-use std::{net::SocketAddr, time::Duration};
+use std::{future::IntoFuture, net::SocketAddr, sync::Arc, time::Duration};
 
 use anyhow::Context;
 use axum::{
-    error_handling::HandleErrorLayer,
     extract::FromRef,
     http::{HeaderValue, Method, StatusCode},
-    routing::{delete, get, patch, post},
-    serve, BoxError, Router,
+    routing::{delete, get, patch, post, put},
+    serve, Router,
 };
 use axum_extra::extract::cookie::Key;
 use getset::Getters;
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use metrics_exporter_prometheus::PrometheusHandle;
+use opentelemetry::{global, trace::TracerProvider};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    trace::{Sampler, SdkTracerProvider},
+    Resource,
+};
+use sqlx::{postgres::PgPoolOptions, sqlite::SqlitePoolOptions, PgPool};
 use tokio::{net::TcpListener, signal};
-use tower::{buffer::BufferLayer, limit::RateLimitLayer, ServiceBuilder};
+use tokio_util::sync::CancellationToken;
 use tower_http::{cors::CorsLayer, timeout::TimeoutLayer, trace::TraceLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use crate::{
     controllers::*,
-    utils::{AppConfig, AppResult, DatabaseConfig},
+    middlewares::{
+        metrics::RequestMetricsLayer,
+        rate_limit::{RateLimitKey, RateLimitLayer},
+        scope::RequireScopeLayer,
+        trace::{make_request_span, record_response},
+    },
+    models::Scope,
+    services,
+    stores::{AuditStore, MemoryStore, SessionStore, SqliteStore, Stores, UserStore},
+    utils::{
+        install_metrics, AppConfig, AppError, AppResult, BreachedPasswords, DatabaseBackend,
+        DatabaseConfig, RateLimitPolicy, RateLimiter, TelemetryConfig,
+    },
 };
 
 //----------------------------------------------------------------------
@@ -31,6 +50,13 @@ use crate::{
 /// - `db_pool`: The database connection pool.
 /// - `config`: The application configuration.
 /// - `key`: A secret key used for cookies.
+/// - `breached_passwords`: The breached password corpus, if the check is enabled.
+/// - `rate_limiter`: The counters of every rate limit policy.
+/// - `user_store`: Where users are kept.
+/// - `session_store`: Where sessions are kept.
+/// - `audit_store`: Where the audit log is kept.
+/// - `metrics`: The Prometheus recorder, if metrics are enabled.
+/// - `in_postgres`: Whether the stores keep users in the database of `db_pool`.
 #[derive(Debug, Clone, Getters)]
 pub struct AppState {
     #[getset(get = "pub with_prefix")]
@@ -39,6 +65,19 @@ pub struct AppState {
     config: AppConfig,
     #[getset(get = "pub with_prefix")]
     key: Key,
+    #[getset(get = "pub with_prefix")]
+    breached_passwords: Option<Arc<BreachedPasswords>>,
+    #[getset(get = "pub with_prefix")]
+    rate_limiter: Arc<RateLimiter>,
+    #[getset(get = "pub with_prefix")]
+    user_store: Arc<dyn UserStore>,
+    #[getset(get = "pub with_prefix")]
+    session_store: Arc<dyn SessionStore>,
+    #[getset(get = "pub with_prefix")]
+    audit_store: Arc<dyn AuditStore>,
+    #[getset(get = "pub with_prefix")]
+    metrics: Option<PrometheusHandle>,
+    in_postgres: bool,
 }
 
 //----------------------------------------------------------------------
@@ -51,6 +90,23 @@ impl FromRef<AppState> for Key {
     }
 }
 
+impl AppState {
+    /// Returns the database connection pool for a feature that has no store.
+    ///
+    /// Such features, e.g. roles, organizations, API keys and invites, refer to users by
+    /// their rows in Postgres, so they are refused with `501 Not Implemented` while users are
+    /// kept in SQLite or in memory.
+    pub fn require_db_pool(&self) -> Result<&PgPool, AppError> {
+        if !self.in_postgres {
+            return Err(AppError::new(
+                StatusCode::NOT_IMPLEMENTED,
+                "Unavailable unless users are kept in Postgres",
+            ));
+        }
+        Ok(&self.db_pool)
+    }
+}
+
 //----------------------------------------------------------------------
 // Methods
 //----------------------------------------------------------------------
@@ -63,11 +119,64 @@ impl FromRef<AppState> for Key {
 /// ## Returns
 /// - `AppResult<()>`: Indicates success or failure of server execution.
 pub async fn run_application(config: AppConfig) -> AppResult<()> {
-    init_tracing()?;
+    let tracer_provider = init_tracing(config.get_telemetry())?;
+
+    let (db_pool, stores) = connect_stores(config.get_database()).await?;
+
+    if config.get_database().get_backend() == DatabaseBackend::Postgres {
+        if *config.get_database().get_auto_migrate() {
+            services::run_migrations(&db_pool).await?;
+        }
+        services::bootstrap_admin(&db_pool, &config).await?;
 
-    let db_pool = create_connection_pool(config.get_database()).await?;
+        let audit = config.get_audit();
+        if !audit.get_checkpoint_path().is_empty() {
+            tokio::spawn(services::run_audit_checkpoints(
+                db_pool.clone(),
+                audit.get_checkpoint_path().clone(),
+                Duration::from_secs(*audit.get_checkpoint_interval_secs()),
+            ));
+        }
 
-    let app = create_router(db_pool, config.clone());
+        tokio::spawn(services::run_webhook_dispatcher(
+            db_pool.clone(),
+            config.get_webhooks().clone(),
+        ));
+    } else {
+        tracing::info!(
+            "Keeping users and sessions in SQLite; roles, organizations, API keys, invites, imports, exports and webhooks are refused"
+        );
+    }
+
+    let shutdown = CancellationToken::new();
+    let reaper = config.get_session_reaper().get_enabled().then(|| {
+        let pool = (config.get_database().get_backend() == DatabaseBackend::Postgres)
+            .then(|| db_pool.clone());
+        tokio::spawn(services::run_session_reaper(
+            stores.sessions.clone(),
+            pool,
+            config.get_session_reaper().clone(),
+            chrono::Duration::seconds(*config.get_session().get_idle_timeout_secs()),
+            shutdown.clone(),
+        ))
+    });
+
+    let state = create_app_state(db_pool, config.clone(), stores)?;
+    let app = create_router_with_state(state.clone());
+
+    let metrics = config.get_metrics();
+    let admin = if *metrics.get_enabled() && *metrics.get_port() != 0 {
+        let address = SocketAddr::new(metrics.get_host().parse()?, *metrics.get_port());
+        let listener = TcpListener::bind(address).await?;
+        tracing::info!("Serving metrics on {}:{}", address.ip(), address.port());
+        Some(tokio::spawn(
+            serve(listener, create_metrics_router(state).into_make_service())
+                .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+                .into_future(),
+        ))
+    } else {
+        None
+    };
 
     let address = SocketAddr::new(
         config.get_server().get_host().parse()?,
@@ -77,17 +186,49 @@ pub async fn run_application(config: AppConfig) -> AppResult<()> {
     let listener = TcpListener::bind(address).await?;
 
     tracing::info!("Listening on {}:{}", address.ip(), address.port());
-    serve(listener, app.into_make_service())
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .context("Failed to start server")
+    serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown_signal().await;
+            shutdown.cancel();
+        }
+    })
+    .await
+    .context("Failed to start server")?;
+
+    if let Some(reaper) = reaper {
+        reaper.await.context("Session reaper panicked")?;
+    }
+    if let Some(admin) = admin {
+        admin
+            .await
+            .context("Metrics listener panicked")?
+            .context("Failed to serve metrics")?;
+    }
+    // Spans still batched are exported before exiting
+    if let Some(provider) = tracer_provider {
+        tokio::task::spawn_blocking(move || provider.shutdown())
+            .await
+            .context("Trace exporter panicked")?
+            .context("Failed to export traces")?;
+    }
+    Ok(())
 }
 
-/// Initializes tracing for logging and diagnostics.
+/// Initializes tracing for logging and diagnostics, exporting spans to an OTLP collector when
+/// `telemetry.otlp_endpoint` is set.
+///
+/// ## Parameters
+/// - `config`: Telemetry configuration.
 ///
 /// ## Returns
-/// - `AppResult<()>`: Indicates success or failure of tracing initialization.
-fn init_tracing() -> AppResult<()> {
+/// - `AppResult<Option<SdkTracerProvider>>`: The provider exporting spans, if any, to shut
+///   down on exit so that batched spans are not lost.
+pub fn init_tracing(config: &TelemetryConfig) -> AppResult<Option<SdkTracerProvider>> {
     let env_filter = tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
         format!(
             "{}=info,tower_http=info,axum=debug",
@@ -100,11 +241,55 @@ fn init_tracing() -> AppResult<()> {
         .with_target(false)
         .with_timer(tracing_subscriber::fmt::time::UtcTime::rfc_3339());
 
+    let tracer_provider = create_tracer_provider(config)?;
+    let otel_layer = tracer_provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_CRATE_NAME")))
+    });
+
     tracing_subscriber::registry()
         .with(env_filter)
         .with(fmt_layer)
+        .with(otel_layer)
         .try_init()
-        .context("Failed to initialize tracing")
+        .context("Failed to initialize tracing")?;
+    Ok(tracer_provider)
+}
+
+/// Creates the provider exporting spans to the OTLP/HTTP collector at
+/// `telemetry.otlp_endpoint`, and installs the W3C trace context propagator.
+///
+/// ## Parameters
+/// - `config`: Telemetry configuration.
+///
+/// ## Returns
+/// - `AppResult<Option<SdkTracerProvider>>`: The provider, or `None` when no collector is set.
+fn create_tracer_provider(config: &TelemetryConfig) -> AppResult<Option<SdkTracerProvider>> {
+    let endpoint = config.get_otlp_endpoint().trim_end_matches('/');
+    if endpoint.is_empty() {
+        return Ok(None);
+    }
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint))
+        .build()
+        .context("Failed to create trace exporter")?;
+    // Traces continued from a caller follow its sampling decision
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+        *config.get_sample_ratio(),
+    )));
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(sampler)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.get_service_name().clone())
+                .build(),
+        )
+        .build();
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    Ok(Some(provider))
 }
 
 /// Creates a database connection pool.
@@ -124,21 +309,182 @@ pub async fn create_connection_pool(config: &DatabaseConfig) -> AppResult<PgPool
         .context("Failed to create database connection pool")
 }
 
-/// Creates the application router with all routes and middleware configured.
+/// Creates a connection pool that only connects once used, for features that require
+/// Postgres when users and sessions are kept elsewhere.
+///
+/// ## Parameters
+/// - `config`: Database configuration.
+///
+/// ## Returns
+/// - `PgPool`: A connection pool that fails while the database is unreachable.
+pub fn create_lazy_connection_pool(config: &DatabaseConfig) -> PgPool {
+    PgPoolOptions::new()
+        .acquire_timeout(Duration::from_secs(*config.get_acquire_timeout_secs()))
+        .connect_lazy_with(config.to_pg_connect_options())
+}
+
+/// Opens the SQLite database at `database.url` and brings it up to date with the migrations
+/// under `migrations/sqlite`.
+///
+/// ## Parameters
+/// - `config`: Database configuration.
+///
+/// ## Returns
+/// - `AppResult<SqliteStore>`: Stores keeping users and sessions in the database.
+pub async fn create_sqlite_store(config: &DatabaseConfig) -> AppResult<SqliteStore> {
+    // An in-memory database lives as long as one of its connections, so one is always kept
+    let pool = SqlitePoolOptions::new()
+        .max_connections(*config.get_max_connections())
+        .min_connections((*config.get_min_connections()).max(1))
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .acquire_timeout(Duration::from_secs(*config.get_acquire_timeout_secs()))
+        .connect_with(config.to_sqlite_connect_options()?)
+        .await
+        .context("Failed to open SQLite database")?;
+    sqlx::migrate!("./migrations/sqlite")
+        .run(&pool)
+        .await
+        .context("Failed to migrate SQLite database")?;
+    Ok(SqliteStore::new(pool))
+}
+
+/// Connects to the database selected by `database.url`: SQLite for a `sqlite:` URL, otherwise
+/// Postgres.
+///
+/// With SQLite, the returned pool connects to Postgres lazily and is never used: the features
+/// that have no SQLite store are refused, see [`AppState::require_db_pool`].
+///
+/// ## Parameters
+/// - `config`: Database configuration.
+///
+/// ## Returns
+/// - `AppResult<(PgPool, Stores)>`: The Postgres pool and the stores to build the router with.
+pub async fn connect_stores(config: &DatabaseConfig) -> AppResult<(PgPool, Stores)> {
+    match config.get_backend() {
+        DatabaseBackend::Sqlite => {
+            let store = create_sqlite_store(config).await?;
+            Ok((
+                create_lazy_connection_pool(config),
+                Stores::new(Arc::new(store)),
+            ))
+        }
+        DatabaseBackend::Postgres => {
+            let db_pool = create_connection_pool(config).await?;
+            let stores = Stores::postgres(db_pool.clone());
+            Ok((db_pool, stores))
+        }
+    }
+}
+
+/// Creates the application router with all routes and middleware configured, keeping
+/// everything in Postgres.
 ///
 /// ## Parameters
 /// - `db_pool`: The database connection pool.
 /// - `config`: The application configuration.
 ///
 /// ## Returns
-/// - `Router`: The configured router.
-pub fn create_router(db_pool: PgPool, config: AppConfig) -> Router {
+/// - `AppResult<Router>`: The configured router.
+pub fn create_router(db_pool: PgPool, config: AppConfig) -> AppResult<Router> {
+    let stores = Stores::postgres(db_pool.clone());
+    create_router_with_stores(db_pool, config, stores)
+}
+
+/// Creates the application router keeping users, sessions and the audit log in memory, so
+/// that it runs without a database.
+///
+/// Features that have no in-memory store, such as roles, organizations and webhooks, are
+/// refused, see [`AppState::require_db_pool`].
+///
+/// ## Parameters
+/// - `config`: The application configuration.
+///
+/// ## Returns
+/// - `AppResult<Router>`: The configured router.
+pub fn create_memory_router(config: AppConfig) -> AppResult<Router> {
+    let db_pool = create_lazy_connection_pool(config.get_database());
+    let stores = Stores::new(Arc::new(MemoryStore::new()));
+    create_router_with_stores(db_pool, config, stores)
+}
+
+/// Creates the application router with all routes and middleware configured.
+///
+/// ## Parameters
+/// - `db_pool`: The database connection pool, for features without a store.
+/// - `config`: The application configuration.
+/// - `stores`: Where users, sessions and the audit log are kept.
+///
+/// ## Returns
+/// - `AppResult<Router>`: The configured router.
+pub fn create_router_with_stores(
+    db_pool: PgPool,
+    config: AppConfig,
+    stores: Stores,
+) -> AppResult<Router> {
+    Ok(create_router_with_state(create_app_state(
+        db_pool, config, stores,
+    )?))
+}
+
+/// Creates the state shared by the routers of the application.
+///
+/// ## Parameters
+/// - `db_pool`: The database connection pool, for features without a store.
+/// - `config`: The application configuration.
+/// - `stores`: Where users, sessions and the audit log are kept.
+///
+/// ## Returns
+/// - `AppResult<AppState>`: The application state.
+pub fn create_app_state(db_pool: PgPool, config: AppConfig, stores: Stores) -> AppResult<AppState> {
     let key = Key::from(config.get_server().get_cookie_secret().as_bytes());
+    let breached_passwords =
+        BreachedPasswords::from_config(config.get_breached_passwords())?.map(Arc::new);
+    let rate_limiter = Arc::new(RateLimiter::from_config(
+        config.get_rate_limit(),
+        config.get_redis(),
+    )?);
+    let metrics = if *config.get_metrics().get_enabled() {
+        Some(install_metrics()?)
+    } else {
+        None
+    };
     let state = AppState {
         db_pool,
         config,
         key,
+        breached_passwords,
+        rate_limiter,
+        user_store: stores.users,
+        session_store: stores.sessions,
+        audit_store: stores.audit,
+        metrics,
+        in_postgres: stores.in_postgres,
     };
+    Ok(state)
+}
+
+/// Creates the router serving `/metrics` on its own admin port.
+///
+/// ## Parameters
+/// - `state`: The application state.
+///
+/// ## Returns
+/// - `Router`: The metrics router.
+pub fn create_metrics_router(state: AppState) -> Router {
+    Router::new()
+        .route("/metrics", get(get_metrics))
+        .with_state(state)
+}
+
+/// Creates the application router with all routes and middleware configured.
+///
+/// ## Parameters
+/// - `state`: The application state.
+///
+/// ## Returns
+/// - `Router`: The configured router.
+pub fn create_router_with_state(state: AppState) -> Router {
     let timeout = Duration::from_secs(*state.config.get_server().get_timeout_in_secs());
     let origins: Vec<HeaderValue> = state
         .config
@@ -160,53 +506,183 @@ pub fn create_router(db_pool: PgPool, config: AppConfig) -> Router {
         ])
         .allow_credentials(true);
 
-    let trace_layer = TraceLayer::new_for_http();
+    let trace_layer = TraceLayer::new_for_http()
+        .make_span_with(make_request_span)
+        .on_response(record_response);
     let timeout_layer = TimeoutLayer::new(timeout);
 
-    // See https://github.com/tokio-rs/axum/discussions/987
-    let rate_limit_layer = ServiceBuilder::new()
-        .layer(HandleErrorLayer::new(|err: BoxError| async move {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Unhandled error: {}", err),
-            )
-        }))
-        .layer(BufferLayer::new(1024))
-        .layer(RateLimitLayer::new(
-            *state.config.get_server().get_rate_limit_burst(),
-            Duration::from_secs(*state.config.get_server().get_rate_limit_per_secs()),
-        ));
+    // Reads get a generous budget, anything else the default one
+    let limits = state.config.get_rate_limit();
+    let rate_limit_layer =
+        RateLimitLayer::new(state.clone(), RateLimitPolicy::from_config(limits)).reads(
+            RateLimitPolicy::from_policy_config("read", limits.get_read()),
+        );
+
+    // Endpoints taking credentials also get a strict budget per IP address, shared between
+    // them, and one per account or token they are tried against
+    let auth_ip = RateLimitLayer::new(
+        state.clone(),
+        RateLimitPolicy::from_policy_config("auth", limits.get_auth()),
+    )
+    .keyed_by(RateLimitKey::Ip);
+    let auth_identifier = |fields| {
+        RateLimitLayer::new(
+            state.clone(),
+            RateLimitPolicy::from_policy_config("identifier", limits.get_identifier()),
+        )
+        .keyed_by(RateLimitKey::Identifier(fields))
+    };
+
+    // Every authenticated route requires a scope, except ending one's own session
+    let scope = |scope| RequireScopeLayer::new(state.clone(), scope);
 
     let users_router = Router::new()
-        .route("/register", post(register))
-        .route("/", get(get_all_users))
-        .route("/me", get(get_me))
-        .route("/:id", get(get_user))
-        .route("/:id", patch(update_user))
-        .route("/me", patch(update_me))
-        .route("/:id", delete(delete_user))
-        .route("/me", delete(delete_me));
+        .route(
+            "/register",
+            post(register)
+                .layer(auth_identifier(&["username", "email"]))
+                .layer(auth_ip.clone()),
+        )
+        .route("/import", post(import_users).layer(scope(Scope::ADMIN)))
+        .route("/export", get(export_users).layer(scope(Scope::ADMIN)))
+        .route(
+            "/invites/accept",
+            post(accept_invite)
+                .layer(auth_identifier(&["token"]))
+                .layer(auth_ip.clone()),
+        )
+        .route("/", get(get_all_users).layer(scope(Scope::ADMIN)))
+        .route("/me", get(get_me).layer(scope(Scope::PROFILE_READ)))
+        .route("/:id", get(get_user).layer(scope(Scope::ADMIN)))
+        .route("/:id", patch(update_user).layer(scope(Scope::ADMIN)))
+        .route("/me", patch(update_me).layer(scope(Scope::PROFILE_WRITE)))
+        .route("/:id", delete(delete_user).layer(scope(Scope::ADMIN)))
+        .route("/:id/roles", get(get_user_roles).layer(scope(Scope::ADMIN)))
+        .route(
+            "/:id/roles/:role_id",
+            put(grant_user_role).layer(scope(Scope::ADMIN)),
+        )
+        .route(
+            "/:id/roles/:role_id",
+            delete(revoke_user_role).layer(scope(Scope::ADMIN)),
+        )
+        .route("/me", delete(delete_me).layer(scope(Scope::ACCOUNT_DELETE)))
+        .route(
+            "/:id/impersonate",
+            post(start_impersonation).layer(scope(Scope::ADMIN)),
+        )
+        .route(
+            "/me/api-keys",
+            post(create_api_key).layer(scope(Scope::API_KEYS)),
+        )
+        .route(
+            "/me/api-keys",
+            get(get_my_api_keys).layer(scope(Scope::API_KEYS)),
+        )
+        .route(
+            "/me/api-keys/:id",
+            delete(revoke_api_key).layer(scope(Scope::API_KEYS)),
+        );
 
     let auth_router = Router::new()
-        .route("/login", post(login))
+        .route(
+            "/login",
+            post(login)
+                .layer(auth_identifier(&["username", "email"]))
+                .layer(auth_ip.clone()),
+        )
         .route("/logout", post(logout));
 
     let session_router = Router::new()
-        .route("/refresh-cookie", post(refresh_session_by_cookie))
-        .route("/refresh", post(refresh_session_by_body))
+        .route(
+            "/refresh-cookie",
+            post(refresh_session_by_cookie)
+                .layer(auth_identifier(&["refresh_token"]))
+                .layer(auth_ip.clone()),
+        )
+        .route(
+            "/refresh",
+            post(refresh_session_by_body)
+                .layer(auth_identifier(&["refresh_token"]))
+                .layer(auth_ip.clone()),
+        )
         .route("/current", patch(revoke_my_session))
-        .route("/:id", patch(revoke_user_session))
-        .route("/", patch(revoke_all_sessions));
+        .route(
+            "/:id",
+            patch(revoke_user_session).layer(scope(Scope::ADMIN)),
+        )
+        .route("/", patch(revoke_all_sessions).layer(scope(Scope::ADMIN)));
+
+    let roles_router = Router::new()
+        .route("/", get(get_all_roles))
+        .route("/", post(create_role))
+        .route("/permissions", get(get_all_permissions))
+        .route("/:id", get(get_role))
+        .route("/:id", patch(update_role))
+        .route("/:id", delete(delete_role))
+        .route_layer(scope(Scope::ADMIN));
+
+    let organizations_router = Router::new()
+        .route("/", get(get_all_organizations))
+        .route("/", post(create_organization))
+        .route("/:id", get(get_organization))
+        .route("/:id", delete(delete_organization))
+        .route("/:id/members", get(get_members))
+        .route("/:id/members/:user_id", put(put_member))
+        .route("/:id/members/:user_id", delete(delete_member))
+        .route_layer(scope(Scope::ADMIN));
+
+    let impersonations_router = Router::new()
+        .route("/", get(get_all_impersonations))
+        .route("/:id", delete(stop_impersonation))
+        .route_layer(scope(Scope::ADMIN));
+
+    let audit_router = Router::new()
+        .route("/", get(get_all_audit_events))
+        .route("/verify", get(verify_audit_log))
+        .route_layer(scope(Scope::ADMIN));
+
+    let webhooks_router = Router::new()
+        .route("/", post(create_webhook_subscription))
+        .route("/", get(get_all_webhook_subscriptions))
+        .route("/dead-letters", get(get_dead_webhook_deliveries))
+        .route("/dead-letters/:id/retry", post(retry_webhook_delivery))
+        .route("/:id", get(get_webhook_subscription))
+        .route("/:id", patch(update_webhook_subscription))
+        .route("/:id", delete(delete_webhook_subscription))
+        .route_layer(scope(Scope::ADMIN));
+
+    let rate_limits_router = Router::new()
+        .route("/", get(get_rate_limits))
+        .route_layer(scope(Scope::ADMIN));
+
+    // Metrics are served alongside the API, to admins only, unless they have their own port
+    let metrics = state.config.get_metrics();
+    let metrics_router = if *metrics.get_enabled() && *metrics.get_port() == 0 {
+        Router::new()
+            .route("/", get(get_api_metrics))
+            .route_layer(scope(Scope::ADMIN))
+    } else {
+        Router::new()
+    };
 
     Router::new()
         .route("/", get(health_check))
         .nest("/users", users_router)
         .nest("/auth", auth_router)
         .nest("/sessions", session_router)
+        .nest("/roles", roles_router)
+        .nest("/organizations", organizations_router)
+        .nest("/impersonations", impersonations_router)
+        .nest("/audit-events", audit_router)
+        .nest("/webhooks", webhooks_router)
+        .nest("/rate-limits", rate_limits_router)
+        .nest("/metrics", metrics_router)
         .layer(trace_layer)
         .layer(cors_layer)
         .layer(timeout_layer)
         .layer(rate_limit_layer)
+        .layer(RequestMetricsLayer)
         .with_state(state)
 }
 
@@ -0,0 +1,647 @@
+#![deny(missing_docs)]
+//! Command-line interface for running the server and administrative tasks.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Context};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Duration;
+use clap::{Parser, Subcommand, ValueEnum};
+use futures_util::TryStreamExt;
+use serde_json::json;
+use sqlx::PgPool;
+use tokio::{
+    fs::File,
+    io::{self, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    bootstrap::{create_connection_pool, run_application},
+    dto::{BulkFormat, ImportUsersQueryDto, UserReqDto},
+    models::{AuditEvent, Role, User},
+    services,
+    stores::{PgStore, UserStore},
+    utils::{hash_password, AppConfig, AppResult, BreachedPasswords, DatabaseBackend},
+};
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Auth service command-line arguments.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// The command to run; starts the server when omitted.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Top-level commands.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server.
+    Serve,
+    /// Manage users.
+    User {
+        /// The user command to run.
+        #[command(subcommand)]
+        command: UserCommand,
+    },
+    /// Check and pin the audit log.
+    Audit {
+        /// The audit command to run.
+        #[command(subcommand)]
+        command: AuditCommand,
+    },
+    /// Apply, revert and list the embedded database migrations.
+    Migrate {
+        /// The migration command to run.
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+    /// Manage sessions.
+    Session {
+        /// The session command to run.
+        #[command(subcommand)]
+        command: SessionCommand,
+    },
+    /// Manage the secrets tokens and cookies are signed with.
+    Keys {
+        /// The key command to run.
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
+    /// Inspect the configuration.
+    Config {
+        /// The configuration command to run.
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Delete the sessions that have expired, were revoked or went idle.
+    PurgeExpiredSessions,
+}
+
+/// User management commands.
+#[derive(Debug, Subcommand)]
+pub enum UserCommand {
+    /// Create a user and print it.
+    Create {
+        /// The username of the user.
+        #[arg(long)]
+        username: String,
+        /// The email address of the user.
+        #[arg(long)]
+        email: String,
+        /// The password; read from standard input when omitted, to keep it out of the shell
+        /// history.
+        #[arg(long)]
+        password: Option<String>,
+        /// Grant the admin role to the user.
+        #[arg(long)]
+        admin: bool,
+        /// Slug of the organization to create the user in.
+        #[arg(long)]
+        org: Option<String>,
+    },
+    /// Set the password of a user, subject to the password policy.
+    SetPassword {
+        /// The ID, username or email address of the user.
+        user: String,
+        /// The password; read from standard input when omitted.
+        #[arg(long)]
+        password: Option<String>,
+        /// Slug of the organization the user belongs to.
+        #[arg(long)]
+        org: Option<String>,
+    },
+    /// List users, latest first.
+    List {
+        /// Slug of the organization to list the users of.
+        #[arg(long)]
+        org: Option<String>,
+        /// The number of users to list.
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+        /// The number of users to skip.
+        #[arg(long, default_value_t = 0)]
+        offset: i64,
+    },
+    /// Import users from a CSV or JSON Lines file and print a report.
+    Import {
+        /// The file to import.
+        path: PathBuf,
+        /// The file format; inferred from the extension when omitted.
+        #[arg(long, value_enum)]
+        format: Option<BulkFormat>,
+        /// Validate every row without creating any user.
+        #[arg(long)]
+        dry_run: bool,
+        /// Import rows without a password hash and issue invite tokens for them.
+        #[arg(long)]
+        invite: bool,
+        /// Slug of the organization to import the users into.
+        #[arg(long)]
+        org: Option<String>,
+    },
+    /// Export all users, without password hashes.
+    Export {
+        /// The output format.
+        #[arg(long, value_enum, default_value = "csv")]
+        format: BulkFormat,
+        /// The file to write; defaults to standard output.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Slug of the organization to export the users of.
+        #[arg(long)]
+        org: Option<String>,
+    },
+}
+
+/// Audit log commands.
+#[derive(Debug, Subcommand)]
+pub enum AuditCommand {
+    /// Walk the audit chain, print a report and fail on the first broken link.
+    Verify {
+        /// A checkpoint file to check the chain against as well.
+        #[arg(long)]
+        checkpoints: Option<PathBuf>,
+    },
+    /// Append a checkpoint of the latest audit event to a file.
+    Checkpoint {
+        /// The checkpoint file; defaults to the configured checkpoint path.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Database migration commands.
+#[derive(Debug, Subcommand)]
+pub enum MigrateCommand {
+    /// Apply every pending migration.
+    Up,
+    /// Revert the latest applied migration.
+    Down {
+        /// Revert every applied migration newer than this version instead.
+        #[arg(long)]
+        target: Option<i64>,
+    },
+    /// List the migrations and whether they are applied.
+    Status,
+}
+
+/// Session management commands.
+#[derive(Debug, Subcommand)]
+pub enum SessionCommand {
+    /// Revoke the session of a user, who has to log in again.
+    Revoke {
+        /// The ID, username or email address of the user.
+        #[arg(long)]
+        user: String,
+        /// Slug of the organization the user belongs to.
+        #[arg(long)]
+        org: Option<String>,
+    },
+}
+
+/// Signing key commands.
+#[derive(Debug, Subcommand)]
+pub enum KeysCommand {
+    /// Generate new secrets and print them as environment variables.
+    ///
+    /// Deploying them invalidates every token or cookie signed with the previous secrets.
+    Rotate {
+        /// The secrets to generate; all of them when omitted.
+        #[arg(value_enum)]
+        keys: Vec<KeyKind>,
+    },
+}
+
+/// A secret that `keys rotate` can generate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum KeyKind {
+    /// The secret access and refresh tokens are signed with.
+    Jwt,
+    /// The secret cookies are signed and encrypted with.
+    Cookie,
+}
+
+/// Configuration commands.
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Check the configuration and the services it points to, and print a report.
+    Check,
+}
+
+//----------------------------------------------------------------------
+// Methods
+//----------------------------------------------------------------------
+
+/// Runs the selected command with the provided configuration.
+///
+/// ## Parameters
+/// - `cli`: The parsed command-line arguments.
+/// - `config`: The application configuration.
+///
+/// ## Returns
+/// - `AppResult<()>`: Indicates success or failure of the command.
+pub async fn run(cli: Cli, config: AppConfig) -> AppResult<()> {
+    match cli.command {
+        None | Some(Command::Serve) => run_application(config).await,
+        Some(Command::User { command }) => run_user_command(command, config).await,
+        Some(Command::Audit { command }) => run_audit_command(command, config).await,
+        Some(Command::Migrate { command }) => run_migrate_command(command, config).await,
+        Some(Command::Session { command }) => run_session_command(command, config).await,
+        Some(Command::Keys { command }) => run_keys_command(command),
+        Some(Command::Config { command }) => run_config_command(command, config).await,
+        Some(Command::PurgeExpiredSessions) => {
+            let db_pool = create_connection_pool(config.get_database()).await?;
+            let batch_size = *config.get_session_reaper().get_batch_size();
+            let idle_timeout = Duration::seconds(*config.get_session().get_idle_timeout_secs());
+            let purged =
+                services::purge_expired_sessions(&db_pool, idle_timeout, batch_size).await?;
+            println!("Purged {} expired, revoked or idle sessions", purged);
+            Ok(())
+        }
+    }
+}
+
+async fn run_user_command(command: UserCommand, config: AppConfig) -> AppResult<()> {
+    let db_pool = create_connection_pool(config.get_database()).await?;
+
+    match command {
+        UserCommand::Create {
+            username,
+            email,
+            password,
+            admin,
+            org,
+        } => {
+            let org_id = resolve_org(&db_pool, org.as_deref()).await?;
+            let (username, email) = (username.to_lowercase(), email.to_lowercase());
+            let password = read_password(password).await?;
+            UserReqDto {
+                username: Some(username.clone()),
+                email: Some(email.clone()),
+                password: password.clone(),
+                avatar_url: None,
+                github_id: None,
+            }
+            .validate()?;
+
+            let users = PgStore::new(db_pool.clone());
+            if users
+                .find_user_by_username_or_email(&config, org_id, &username, &email)
+                .await?
+                .is_some()
+            {
+                bail!("User {} already exists", username);
+            }
+            enforce_password_policy(&users, &config, &password, &[&username, &email], None).await?;
+
+            let password_hash = hash_password(&password, config.get_password_hashing())?;
+            let user = User::new(None, email, password_hash, username, None).with_org(org_id);
+            let user = services::create_user(&db_pool, &user).await?;
+            services::record_password_history(
+                &db_pool,
+                config.get_password_policy(),
+                user.id,
+                &user.password_hash,
+            )
+            .await?;
+            if admin {
+                let role = services::get_role_by_name(&db_pool, Role::ADMIN)
+                    .await?
+                    .ok_or_else(|| anyhow!("The built-in admin role is missing"))?;
+                services::assign_user_role(&db_pool, user.id, &role).await?;
+            }
+            let event = AuditEvent::new(AuditEvent::USER_CREATED)
+                .target(user.id)
+                .details(json!({ "source": "cli", "admin": admin }));
+            services::record_audit_event(&db_pool, config.get_audit().get_hmac_key(), event)
+                .await?;
+
+            let user = services::get_user_by_id(&db_pool, user.id)
+                .await?
+                .unwrap_or(user);
+            println!("{}", serde_json::to_string_pretty(&user)?);
+            Ok(())
+        }
+        UserCommand::SetPassword {
+            user,
+            password,
+            org,
+        } => {
+            let org_id = resolve_org(&db_pool, org.as_deref()).await?;
+            let user = resolve_user(&db_pool, &config, org_id, &user).await?;
+            let password = read_password(password).await?;
+
+            let users = PgStore::new(db_pool.clone());
+            enforce_password_policy(
+                &users,
+                &config,
+                &password,
+                &[&user.username, &user.email],
+                Some(user.id),
+            )
+            .await?;
+
+            let password_hash = hash_password(&password, config.get_password_hashing())?;
+            services::update_user_password_hash(&db_pool, user.id, &password_hash).await?;
+            services::record_password_history(
+                &db_pool,
+                config.get_password_policy(),
+                user.id,
+                &password_hash,
+            )
+            .await?;
+            let event = AuditEvent::new(AuditEvent::USER_UPDATED)
+                .target(user.id)
+                .details(json!({ "source": "cli", "fields": ["password"] }));
+            services::record_audit_event(&db_pool, config.get_audit().get_hmac_key(), event)
+                .await?;
+
+            println!("Password of {} updated", user.username);
+            Ok(())
+        }
+        UserCommand::List { org, limit, offset } => {
+            let org_id = resolve_org(&db_pool, org.as_deref()).await?;
+            let users = services::get_all_users(&db_pool, org_id, limit, offset).await?;
+            println!("{}", serde_json::to_string_pretty(&users)?);
+            Ok(())
+        }
+        UserCommand::Import {
+            path,
+            format,
+            dry_run,
+            invite,
+            org,
+        } => {
+            let org_id = resolve_org(&db_pool, org.as_deref()).await?;
+            let format = format
+                .or_else(|| BulkFormat::from_path(&path))
+                .ok_or_else(|| anyhow!("Unable to infer the format of {}", path.display()))?;
+            let file = File::open(&path)
+                .await
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+
+            let options = ImportUsersQueryDto {
+                format,
+                dry_run,
+                invite,
+            };
+            let report =
+                services::import_users(&db_pool, &config, org_id, BufReader::new(file), &options)
+                    .await?;
+
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(())
+        }
+        UserCommand::Export {
+            format,
+            output,
+            org,
+        } => {
+            let org_id = resolve_org(&db_pool, org.as_deref()).await?;
+            let mut writer: Box<dyn AsyncWrite + Unpin> = match &output {
+                Some(path) => Box::new(
+                    File::create(path)
+                        .await
+                        .with_context(|| format!("Failed to create {}", path.display()))?,
+                ),
+                None => Box::new(io::stdout()),
+            };
+
+            let mut chunks = std::pin::pin!(services::export_users(db_pool, org_id, format));
+            while let Some(chunk) = chunks.try_next().await? {
+                writer.write_all(&chunk).await?;
+            }
+            writer.flush().await.context("Failed to write export")
+        }
+    }
+}
+
+async fn run_audit_command(command: AuditCommand, config: AppConfig) -> AppResult<()> {
+    let db_pool = create_connection_pool(config.get_database()).await?;
+
+    match command {
+        AuditCommand::Verify { checkpoints } => {
+            let key = config.get_audit().get_hmac_key();
+            let mut report = services::verify_audit_chain(&db_pool, key).await?;
+            if let (None, Some(path)) = (&report.broken_link, checkpoints) {
+                let contents = tokio::fs::read_to_string(&path)
+                    .await
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let checkpoints = contents
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(serde_json::from_str)
+                    .collect::<Result<Vec<_>, _>>()
+                    .with_context(|| format!("Failed to parse {}", path.display()))?;
+                report.broken_link =
+                    services::verify_audit_checkpoints(&db_pool, &checkpoints).await?;
+            }
+
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            match report.broken_link {
+                Some(link) => Err(anyhow!(
+                    "Audit log is broken at event {} ({})",
+                    link.seq,
+                    link.reason
+                )),
+                None => Ok(()),
+            }
+        }
+        AuditCommand::Checkpoint { output } => {
+            let path = output
+                .or_else(|| {
+                    Some(PathBuf::from(config.get_audit().get_checkpoint_path()))
+                        .filter(|path| !path.as_os_str().is_empty())
+                })
+                .ok_or_else(|| anyhow!("No checkpoint file given or configured"))?;
+            match services::export_audit_checkpoint(&db_pool, &path).await? {
+                Some(checkpoint) => println!("{}", serde_json::to_string_pretty(&checkpoint)?),
+                None => println!("The audit log has no chained event yet"),
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn run_migrate_command(command: MigrateCommand, config: AppConfig) -> AppResult<()> {
+    if config.get_database().get_backend() == DatabaseBackend::Sqlite {
+        return Err(anyhow!(
+            "SQLite databases are migrated when opened; unset database.url to migrate Postgres"
+        ));
+    }
+    let db_pool = create_connection_pool(config.get_database()).await?;
+
+    match command {
+        MigrateCommand::Up => {
+            services::run_migrations(&db_pool).await?;
+        }
+        MigrateCommand::Down { target } => {
+            let reverted = services::revert_migrations(&db_pool, target).await?;
+            if reverted.is_empty() {
+                println!("No migration to revert");
+            }
+            for version in reverted {
+                println!("Reverted {}", version);
+            }
+        }
+        MigrateCommand::Status => {}
+    }
+
+    let status = services::get_migration_status(&db_pool).await?;
+    println!("{}", serde_json::to_string_pretty(&status)?);
+    Ok(())
+}
+
+async fn run_session_command(command: SessionCommand, config: AppConfig) -> AppResult<()> {
+    let db_pool = create_connection_pool(config.get_database()).await?;
+
+    match command {
+        SessionCommand::Revoke { user, org } => {
+            let org_id = resolve_org(&db_pool, org.as_deref()).await?;
+            let user = resolve_user(&db_pool, &config, org_id, &user).await?;
+            if services::get_session_by_user_id(&db_pool, user.id)
+                .await?
+                .is_none()
+            {
+                println!("{} has no session", user.username);
+                return Ok(());
+            }
+
+            services::revoke_session(&db_pool, user.id).await?;
+            let event = AuditEvent::new(AuditEvent::SESSION_REVOKED)
+                .target(user.id)
+                .details(json!({ "source": "cli" }));
+            services::record_audit_event(&db_pool, config.get_audit().get_hmac_key(), event)
+                .await?;
+            println!("Session of {} revoked", user.username);
+            Ok(())
+        }
+    }
+}
+
+fn run_keys_command(command: KeysCommand) -> AppResult<()> {
+    match command {
+        KeysCommand::Rotate { keys } => {
+            let all = keys.is_empty();
+            if all || keys.contains(&KeyKind::Jwt) {
+                println!("APP__JWT__SECRET={}", generate_secret(48));
+            }
+            if all || keys.contains(&KeyKind::Cookie) {
+                println!("APP__SERVER__COOKIE_SECRET={}", generate_secret(64));
+            }
+            eprintln!(
+                "Deploying these secrets invalidates every token or cookie signed with the previous ones"
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn run_config_command(command: ConfigCommand, config: AppConfig) -> AppResult<()> {
+    match command {
+        ConfigCommand::Check => {
+            let checks = services::check_config(&config).await;
+            println!("{}", serde_json::to_string_pretty(&checks)?);
+            let failed: Vec<_> = checks
+                .iter()
+                .filter(|check| !check.ok)
+                .map(|check| check.name.as_str())
+                .collect();
+            if failed.is_empty() {
+                Ok(())
+            } else {
+                Err(anyhow!("Configuration check failed: {}", failed.join(", ")))
+            }
+        }
+    }
+}
+
+/// Returns `password`, or the first line of standard input when it is not given.
+async fn read_password(password: Option<String>) -> AppResult<String> {
+    if let Some(password) = password {
+        return Ok(password);
+    }
+
+    eprintln!("Password:");
+    let mut line = String::new();
+    BufReader::new(io::stdin())
+        .read_line(&mut line)
+        .await
+        .context("Failed to read the password")?;
+    let password = line.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        bail!("No password given");
+    }
+    Ok(password)
+}
+
+async fn enforce_password_policy(
+    users: &PgStore,
+    config: &AppConfig,
+    password: &str,
+    user_inputs: &[&str],
+    user_id: Option<Uuid>,
+) -> AppResult<()> {
+    let breached_passwords = BreachedPasswords::from_config(config.get_breached_passwords())?;
+    let violations = services::validate_password(
+        users,
+        config,
+        breached_passwords.as_ref(),
+        password,
+        user_inputs,
+        user_id,
+    )
+    .await?;
+    if !violations.is_empty() {
+        bail!(
+            "Password does not meet the password policy: {}",
+            violations
+                .iter()
+                .map(|violation| violation.message.as_str())
+                .collect::<Vec<_>>()
+                .join("; ")
+        );
+    }
+    Ok(())
+}
+
+/// Looks up a user by ID, or by email or username in the namespace of `org_id`.
+async fn resolve_user(
+    pool: &PgPool,
+    config: &AppConfig,
+    org_id: Option<Uuid>,
+    user: &str,
+) -> AppResult<User> {
+    let found = if let Ok(id) = Uuid::parse_str(user) {
+        services::get_user_by_id(pool, id).await?
+    } else if user.contains('@') {
+        services::find_user_by_email(pool, config, org_id, &user.to_lowercase()).await?
+    } else {
+        services::find_user_by_username(pool, config, org_id, &user.to_lowercase()).await?
+    };
+    found.ok_or_else(|| anyhow!("User {} not found", user))
+}
+
+fn generate_secret(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+async fn resolve_org(pool: &PgPool, slug: Option<&str>) -> AppResult<Option<Uuid>> {
+    match slug {
+        Some(slug) => services::get_organization_by_slug(pool, slug)
+            .await?
+            .map(|org| Some(org.id))
+            .ok_or_else(|| anyhow!("Organization {} not found", slug)),
+        None => Ok(None),
+    }
+}
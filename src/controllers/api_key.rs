@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    bootstrap::AppState,
+    dto::{ApiKeyReqDto, ApiKeyResDto, CreateApiKeyResDto, GetAllApiKeysResDto},
+    middlewares::auth::require_own_login,
+    middlewares::context::RequestContext,
+    models::AuditEvent,
+    services,
+    token::Claims,
+    utils::{AppError, SuccessResponse},
+};
+
+use super::audit;
+
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    context: RequestContext,
+    claims: Claims,
+    Json(dto): Json<ApiKeyReqDto>,
+) -> Result<SuccessResponse<CreateApiKeyResDto>, AppError> {
+    require_own_login(&claims)?;
+    dto.validate()
+        .map_err(|e| AppError::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{}", e)))?;
+
+    if dto
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return Err(AppError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Expiry must be in the future",
+        ));
+    }
+
+    // A key cannot be given permissions its owner does not hold
+    let unknown: Vec<&String> = dto
+        .scopes
+        .iter()
+        .filter(|scope| !claims.has_permission(scope))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(
+            AppError::new(StatusCode::UNPROCESSABLE_ENTITY, "Scopes not held")
+                .with_details(unknown),
+        );
+    }
+
+    let (api_key, key) = services::create_api_key(
+        state.require_db_pool()?,
+        *claims.get_jti(),
+        *claims.get_org_id(),
+        &dto.name,
+        dto.scopes,
+        dto.expires_at,
+    )
+    .await?;
+    tracing::info!(
+        "Created API key {} for user with ID: {}",
+        api_key.prefix,
+        api_key.user_id
+    );
+    let event = AuditEvent::new(AuditEvent::API_KEY_CREATED)
+        .actor(api_key.user_id)
+        .target(api_key.user_id)
+        .details(json!({
+            "apiKeyId": api_key.id,
+            "prefix": api_key.prefix,
+            "scopes": api_key.scopes,
+            "expiresAt": api_key.expires_at,
+        }));
+    audit(&state, &context, event).await?;
+    Ok(SuccessResponse::created(CreateApiKeyResDto {
+        key,
+        api_key: ApiKeyResDto::from(api_key),
+    }))
+}
+
+pub async fn get_my_api_keys(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<SuccessResponse<GetAllApiKeysResDto>, AppError> {
+    let api_keys = services::get_api_keys_by_user_id(state.require_db_pool()?, *claims.get_jti())
+        .await?
+        .into_iter()
+        .map(ApiKeyResDto::from)
+        .collect();
+    Ok(SuccessResponse::ok(GetAllApiKeysResDto { api_keys }))
+}
+
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    context: RequestContext,
+    claims: Claims,
+) -> Result<impl IntoResponse, AppError> {
+    require_own_login(&claims)?;
+
+    if !services::revoke_api_key(state.require_db_pool()?, *claims.get_jti(), id).await? {
+        return Err(AppError::new(StatusCode::NOT_FOUND, "API key not found"));
+    }
+    tracing::info!("Revoked API key with ID: {}", id);
+    let event = AuditEvent::new(AuditEvent::API_KEY_REVOKED)
+        .actor(*claims.get_jti())
+        .target(*claims.get_jti())
+        .details(json!({ "apiKeyId": id }));
+    audit(&state, &context, event).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
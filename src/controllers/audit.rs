@@ -0,0 +1,40 @@
+use axum::extract::{Query, State};
+
+use crate::{
+    bootstrap::AppState,
+    dto::{AuditChainReportDto, GetAllAuditEventsQueryDto, GetAllAuditEventsResDto},
+    middlewares::auth::require_permission,
+    models::Permission,
+    services,
+    token::Claims,
+    utils::{AppError, SuccessResponse},
+};
+
+pub async fn get_all_audit_events(
+    State(state): State<AppState>,
+    claims: Claims,
+    Query(query): Query<GetAllAuditEventsQueryDto>,
+) -> Result<SuccessResponse<GetAllAuditEventsResDto>, AppError> {
+    require_permission(&claims, Permission::AUDIT_READ)?;
+
+    let limit = query.limit.unwrap_or(50).min(100);
+    let offset = query.offset.unwrap_or(0);
+
+    let audit_events =
+        services::get_audit_events(state.require_db_pool()?, &query.filter(), limit, offset)
+            .await?;
+    Ok(SuccessResponse::ok(GetAllAuditEventsResDto {
+        audit_events,
+    }))
+}
+
+pub async fn verify_audit_log(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<SuccessResponse<AuditChainReportDto>, AppError> {
+    require_permission(&claims, Permission::AUDIT_READ)?;
+
+    let key = state.get_config().get_audit().get_hmac_key();
+    let report = services::verify_audit_chain(state.require_db_pool()?, key).await?;
+    Ok(SuccessResponse::ok(report))
+}
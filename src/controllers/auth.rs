@@ -1,75 +1,166 @@
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use axum_extra::extract::PrivateCookieJar;
 use chrono::Duration;
+use serde_json::json;
+use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
     bootstrap::AppState,
     dto::{process_optional_fields, LoginReqDto, LoginResDto},
-    models::Session,
-    repositories::{create_session, delete_session_by_user_id},
-    services::{get_session_by_user_id, get_user_by_username_or_email},
-    token::{Claims, TokenManager},
-    utils::{check_password, AppError, SuccessResponse},
+    middlewares::context::RequestContext,
+    models::{AuditEvent, Scope, Session, User},
+    services::{end_impersonation, get_membership, get_organization_by_slug},
+    token::{Claims, Grant, TokenManager},
+    utils::{check_password, hash_password, needs_rehash, record_login, AppError, SuccessResponse},
 };
 
-use super::create_cookie_session;
+use super::{
+    audit, create_cookie_session, create_session_cookie, parse_scope, refresh_token_duration,
+    session_lifetime, use_session,
+};
 
 pub async fn login(
     State(state): State<AppState>,
+    context: RequestContext,
     jar: PrivateCookieJar,
     Json(dto): Json<LoginReqDto>,
 ) -> Result<(PrivateCookieJar, SuccessResponse<LoginResDto>), AppError> {
+    let login = dto.username.clone().or_else(|| dto.email.clone());
+    let org = dto.org.clone();
+
+    match authenticate(&state, jar, dto).await {
+        Ok((user_id, jar, res)) => {
+            let event = AuditEvent::new(AuditEvent::LOGIN_SUCCEEDED)
+                .actor(user_id)
+                .target(user_id)
+                .details(json!({ "sessionId": res.body.session_id, "org": org }));
+            audit(&state, &context, event).await?;
+            record_login(true);
+            Ok((jar, res))
+        }
+        Err(e) => {
+            // Rejected requests only; malformed ones are not login attempts
+            let status = e.details().status;
+            if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+                let event = AuditEvent::new(AuditEvent::LOGIN_FAILED).details(json!({
+                    "login": login,
+                    "org": org,
+                    "reason": e.details().message,
+                }));
+                audit(&state, &context, event).await?;
+                record_login(false);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Checks the credentials and opens or reuses a session, returning the user ID along with the
+/// response.
+async fn authenticate(
+    state: &AppState,
+    jar: PrivateCookieJar,
+    dto: LoginReqDto,
+) -> Result<(Uuid, PrivateCookieJar, SuccessResponse<LoginResDto>), AppError> {
     dto.validate()
         .map_err(|e| AppError::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{}", e)))?;
 
     let (username, email) = process_optional_fields(dto.username, dto.email)?;
+    let scope = parse_scope(dto.scope.as_deref(), &Scope::all())?;
 
-    let user = get_user_by_username_or_email(state.get_db_pool(), &username, &email)
+    let requested_org = match &dto.org {
+        Some(slug) => Some(
+            get_organization_by_slug(state.require_db_pool()?, slug)
+                .await?
+                .ok_or_else(|| AppError::new(StatusCode::UNAUTHORIZED, "Invalid credentials"))?
+                .id,
+        ),
+        None => None,
+    };
+
+    let user = find_login_user(state, requested_org, &username, &email)
         .await?
         .ok_or_else(|| AppError::new(StatusCode::UNAUTHORIZED, "Invalid credentials"))?;
 
-    if !check_password(&dto.password, &user.password_hash)? {
+    let hashing = state.get_config().get_password_hashing();
+    if !check_password(&dto.password, &user.password_hash, hashing)? {
         return Err(AppError::new(
             StatusCode::UNAUTHORIZED,
             "Invalid credentials",
         ));
     }
 
+    // Transparently upgrade hashes created with outdated parameters while the plaintext is at hand
+    if needs_rehash(&user.password_hash, hashing) {
+        let password_hash = hash_password(&dto.password, hashing)?;
+        state
+            .get_user_store()
+            .update_user_password_hash(user.id, &password_hash)
+            .await?;
+    }
+
+    let org_id = requested_org.or(user.org_id);
+    if let Some(org_id) = org_id {
+        if get_membership(state.require_db_pool()?, org_id, user.id)
+            .await?
+            .is_none()
+        {
+            return Err(AppError::new(
+                StatusCode::FORBIDDEN,
+                "Not a member of this organization",
+            ));
+        }
+    }
+
+    let permissions = state
+        .get_user_store()
+        .resolve_permissions(user.id, org_id)
+        .await?;
     let token_manager =
         TokenManager::new(state.get_config().get_jwt().get_secret().as_bytes(), None);
 
-    if let Some(session) = get_session_by_user_id(state.get_db_pool(), user.id).await? {
+    let sessions = state.get_session_store();
+    if let Some(session) = sessions.get_session_by_user_id(user.id).await? {
         // Check for stale sessions
-        // Enforce the policy of a single active session per user, ensuring it is not expired or revoked
+        // Enforce the policy of a single active session per user, ensuring it is not expired, revoked or idle
         // Prevent the accumulation of stale sessions in the database
         // This approach might be revised in the future if requirements change
-        if session.is_expired() || session.is_revoked {
-            delete_session_by_user_id(state.get_db_pool(), session.user_id).await?;
+        // A session opened for another organization or scope is replaced as well, since its refresh token carries them
+        // So is a session of another class, since the lifetime of its refresh token and cookie depends on it
+        let same_grant = token_manager
+            .validate_refresh_token(&session.refresh_token)
+            .is_ok_and(|claims| *claims.get_org_id() == org_id && *claims.get_scope() == scope);
+        let session = if same_grant && session.is_persistent == dto.remember_me {
+            use_session(state, session).await?
         } else {
+            sessions.delete_session_by_user_id(session.user_id).await?;
+            None
+        };
+        if let Some(session) = session {
             let duration = Duration::seconds(
                 *state
                     .get_config()
                     .get_jwt()
                     .get_access_token_expiration_secs(),
             );
-            let (access_token, access_claims) = token_manager.create_access_token(
-                session.user_id,
-                &user.email,
-                user.is_admin,
-                duration,
-            )?;
-
-            let jar = jar.add(create_cookie_session(
-                &session.refresh_token,
-                session.expires_at.timestamp(),
-            ));
+            let grant = Grant {
+                permissions,
+                org_id,
+                scope,
+                act: None,
+            };
+            let (access_token, access_claims) =
+                token_manager.create_access_token(session.user_id, &user.email, grant, duration)?;
+
+            let jar = jar.add(create_session_cookie(&session));
 
             let access_token_expires_at = *access_claims.get_exp() - *access_claims.get_iat();
             let refresh_token_expires_at =
                 session.expires_at.timestamp() - session.created_at.timestamp();
 
             return Ok((
+                user.id,
                 jar,
                 SuccessResponse::created(LoginResDto {
                     session_id: session.id,
@@ -83,41 +174,53 @@ pub async fn login(
         }
     }
 
-    let refresh_exp_secs = *state
-        .get_config()
-        .get_jwt()
-        .get_refresh_token_expiration_secs();
     let access_exp_secs = *state
         .get_config()
         .get_jwt()
         .get_access_token_expiration_secs();
-    let refresh_duration = Duration::seconds(refresh_exp_secs);
+    let refresh_duration = refresh_token_duration(state, dto.remember_me);
     let access_duration = Duration::seconds(access_exp_secs);
 
-    let (refresh_token, refresh_claims) = token_manager.create_refresh_token(
+    // Refresh tokens carry no permissions; they are resolved again on every refresh
+    let refresh_grant = Grant {
+        permissions: Vec::new(),
+        org_id,
+        scope: scope.clone(),
+        act: None,
+    };
+    let (refresh_token, _) = token_manager.create_refresh_token(
         user.id,
         &user.email,
-        user.is_admin,
+        refresh_grant,
         refresh_duration,
     )?;
 
+    let access_grant = Grant {
+        permissions,
+        org_id,
+        scope,
+        act: None,
+    };
     let (access_token, access_claims) =
-        token_manager.create_access_token(user.id, &user.email, user.is_admin, access_duration)?;
+        token_manager.create_access_token(user.id, &user.email, access_grant, access_duration)?;
 
-    let session = Session::new(user.id, &refresh_token, Duration::seconds(refresh_exp_secs));
+    let session = Session::new(
+        user.id,
+        &refresh_token,
+        session_lifetime(state, dto.remember_me),
+    )
+    .with_persistent(dto.remember_me);
+    let refresh_token_expires_at = session.expires_at.timestamp() - session.created_at.timestamp();
 
     // Store the session in the database
-    create_session(state.get_db_pool(), &session).await?;
+    sessions.create_session(&session).await?;
 
     // Add the refresh token to the cookie jar
-    let jar = jar.add(create_cookie_session(
-        &refresh_token,
-        *refresh_claims.get_exp(),
-    ));
+    let jar = jar.add(create_session_cookie(&session));
 
     let access_token_expires_at = *access_claims.get_exp() - *access_claims.get_iat();
-    let refresh_token_expires_at = *refresh_claims.get_exp() - *refresh_claims.get_iat();
 
+    let user_id = user.id;
     let body = LoginResDto {
         session_id: session.id,
         access_token,
@@ -127,17 +230,59 @@ pub async fn login(
         user: user.into(),
     };
 
-    Ok((jar, SuccessResponse::created(body)))
+    Ok((user_id, jar, SuccessResponse::created(body)))
+}
+
+/// Finds the user signing in. When usernames are unique per organization, the requested
+/// organization is searched first, then users outside any organization, who may sign in
+/// to organizations they are members of.
+async fn find_login_user(
+    state: &AppState,
+    org_id: Option<Uuid>,
+    username: &str,
+    email: &str,
+) -> Result<Option<User>, AppError> {
+    let users = state.get_user_store();
+    let config = state.get_config();
+
+    let user = users
+        .find_user_by_username_or_email(config, org_id, username, email)
+        .await?;
+    if user.is_some() || org_id.is_none() || !*config.get_organizations().get_unique_per_org() {
+        return Ok(user);
+    }
+    Ok(users
+        .find_user_by_username_or_email(config, None, username, email)
+        .await?)
 }
 
 pub async fn logout(
     State(state): State<AppState>,
+    context: RequestContext,
     claims: Claims,
     jar: PrivateCookieJar,
 ) -> Result<impl IntoResponse, AppError> {
-    delete_session_by_user_id(state.get_db_pool(), *claims.get_jti()).await?;
+    // Logging out of an impersonation stops it and leaves the user's own session alone
+    if let Some(act) = claims.get_act() {
+        end_impersonation(state.require_db_pool()?, act.impersonation_id).await?;
+        let event = AuditEvent::new(AuditEvent::IMPERSONATION_STOPPED)
+            .actor(act.sub)
+            .target(*claims.get_jti())
+            .details(json!({ "impersonationId": act.impersonation_id }));
+        audit(&state, &context, event).await?;
+        return Ok((jar, StatusCode::NO_CONTENT));
+    }
+
+    state
+        .get_session_store()
+        .delete_session_by_user_id(*claims.get_jti())
+        .await?;
+    let event = AuditEvent::new(AuditEvent::LOGOUT)
+        .actor(*claims.get_jti())
+        .target(*claims.get_jti());
+    audit(&state, &context, event).await?;
 
-    let cookie = create_cookie_session("", 0);
+    let cookie = create_cookie_session("", Some(0));
     let jar = jar.add(cookie);
 
     Ok((jar, StatusCode::NO_CONTENT))
@@ -0,0 +1,152 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::Duration;
+use serde_json::json;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    bootstrap::AppState,
+    dto::{
+        GetAllImpersonationsQueryDto, GetAllImpersonationsResDto, ImpersonateReqDto,
+        ImpersonateResDto,
+    },
+    middlewares::{
+        auth::{require_own_login, require_permission},
+        context::RequestContext,
+    },
+    models::{AuditEvent, Impersonation, Permission, Scope},
+    services,
+    token::{Actor, Claims, Grant, TokenManager},
+    utils::{AppError, SuccessResponse},
+};
+
+use super::{audit, ensure_user_manageable};
+
+pub async fn start_impersonation(
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+    context: RequestContext,
+    claims: Claims,
+    Json(dto): Json<ImpersonateReqDto>,
+) -> Result<SuccessResponse<ImpersonateResDto>, AppError> {
+    require_permission(&claims, Permission::USERS_IMPERSONATE)?;
+    require_own_login(&claims)?;
+    dto.validate()
+        .map_err(|e| AppError::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{}", e)))?;
+
+    if user_id == *claims.get_jti() {
+        return Err(AppError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Cannot impersonate yourself",
+        ));
+    }
+    let user = ensure_user_manageable(&state, &claims, user_id).await?;
+
+    // Impersonating must not give access to anything the admin could not already do
+    let permissions = state
+        .get_user_store()
+        .resolve_permissions(user.id, user.org_id)
+        .await?;
+    if permissions
+        .iter()
+        .any(|permission| !claims.has_permission(permission))
+    {
+        return Err(AppError::new(
+            StatusCode::FORBIDDEN,
+            "Cannot impersonate a user with permissions you do not hold",
+        ));
+    }
+
+    let duration = Duration::seconds(
+        *state
+            .get_config()
+            .get_impersonation()
+            .get_token_expiration_secs(),
+    );
+    let impersonation = services::create_impersonation(
+        state.require_db_pool()?,
+        &Impersonation::new(*claims.get_jti(), user.id, dto.reason, duration),
+    )
+    .await?;
+
+    let token_manager =
+        TokenManager::new(state.get_config().get_jwt().get_secret().as_bytes(), None);
+    let grant = Grant {
+        permissions,
+        org_id: user.org_id,
+        scope: Scope::IMPERSONATION
+            .iter()
+            .map(|scope| scope.to_string())
+            .collect(),
+        act: Some(Actor {
+            sub: *claims.get_jti(),
+            impersonation_id: impersonation.id,
+        }),
+    };
+    let (access_token, _) =
+        token_manager.create_access_token(user.id, &user.email, grant, duration)?;
+
+    tracing::info!(
+        "User {} started impersonating user {} ({})",
+        impersonation.actor_id,
+        impersonation.user_id,
+        impersonation.id
+    );
+    let event = AuditEvent::new(AuditEvent::IMPERSONATION_STARTED)
+        .actor(impersonation.actor_id)
+        .target(impersonation.user_id)
+        .details(json!({
+            "impersonationId": impersonation.id,
+            "reason": impersonation.reason,
+        }));
+    audit(&state, &context, event).await?;
+    Ok(SuccessResponse::created(ImpersonateResDto {
+        access_token,
+        access_token_expires_at: impersonation.expires_at,
+        impersonation,
+    }))
+}
+
+pub async fn get_all_impersonations(
+    State(state): State<AppState>,
+    claims: Claims,
+    Query(query): Query<GetAllImpersonationsQueryDto>,
+) -> Result<SuccessResponse<GetAllImpersonationsResDto>, AppError> {
+    require_permission(&claims, Permission::USERS_IMPERSONATE)?;
+
+    let limit = query.limit.unwrap_or(50).min(100);
+    let offset = query.offset.unwrap_or(0);
+
+    let impersonations =
+        services::get_all_impersonations(state.require_db_pool()?, limit, offset).await?;
+    Ok(SuccessResponse::ok(GetAllImpersonationsResDto {
+        impersonations,
+    }))
+}
+
+pub async fn stop_impersonation(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    context: RequestContext,
+    claims: Claims,
+) -> Result<impl IntoResponse, AppError> {
+    require_permission(&claims, Permission::USERS_IMPERSONATE)?;
+
+    let impersonation = services::get_impersonation_by_id(state.require_db_pool()?, id)
+        .await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "Impersonation not found"))?;
+
+    services::end_impersonation(state.require_db_pool()?, id).await?;
+    tracing::info!("User {} stopped impersonation {}", claims.get_jti(), id);
+    let event = AuditEvent::new(AuditEvent::IMPERSONATION_STOPPED)
+        .actor(*claims.get_jti())
+        .target(impersonation.user_id)
+        .details(json!({ "impersonationId": id }));
+    audit(&state, &context, event).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,43 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::{
+    bootstrap::AppState,
+    middlewares::auth::require_permission,
+    models::Permission,
+    token::Claims,
+    utils::{record_active_sessions, record_db_pool, AppError},
+};
+
+/// Content type of the Prometheus text format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Serves the metrics on the admin port, which is trusted to be unreachable from the outside.
+pub async fn get_metrics(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    render_metrics(&state).await
+}
+
+/// Serves the metrics alongside the API, to tokens allowed to read them.
+pub async fn get_api_metrics(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<impl IntoResponse, AppError> {
+    require_permission(&claims, Permission::METRICS_READ)?;
+    render_metrics(&state).await
+}
+
+async fn render_metrics(state: &AppState) -> Result<impl IntoResponse, AppError> {
+    let Some(handle) = state.get_metrics() else {
+        return Err(AppError::new(StatusCode::NOT_FOUND, "Metrics are disabled"));
+    };
+
+    // Gauges are sampled on scrape, so that they are as fresh as the counters
+    let active_sessions = state.get_session_store().count_active_sessions().await?;
+    record_active_sessions(active_sessions);
+    record_db_pool(state.get_db_pool());
+
+    Ok(([(header::CONTENT_TYPE, CONTENT_TYPE)], handle.render()))
+}
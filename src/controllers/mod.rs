@@ -1,22 +1,210 @@
+mod api_key;
+mod audit;
 mod auth;
 mod health_check;
+mod impersonation;
+mod metrics;
+mod organization;
+mod rate_limit;
+mod role;
 mod session;
 mod user;
+mod webhook;
 
+pub use api_key::*;
+pub use audit::*;
 pub use auth::*;
+use axum::http::StatusCode;
 use axum_extra::extract::cookie::{Cookie, SameSite};
+use chrono::{Duration, Utc};
 pub use health_check::*;
+pub use impersonation::*;
+pub use metrics::*;
+pub use organization::*;
+pub use rate_limit::*;
+pub use role::*;
 pub use session::*;
 pub use user::*;
+use uuid::Uuid;
+pub use webhook::*;
 
-pub(super) fn create_cookie_session(refresh_token: impl Into<String>, ttl: i64) -> Cookie<'static> {
-    let max_age = time::Duration::seconds(ttl);
-    Cookie::build(("refresh_token", refresh_token.into()))
+use crate::{
+    bootstrap::AppState,
+    middlewares::context::RequestContext,
+    models::{AuditEvent, Session, User},
+    token::Claims,
+    utils::AppError,
+};
+
+/// Builds the cookie holding a refresh token. Without a `ttl`, it is a browser session cookie
+/// that goes away when the browser closes.
+pub(super) fn create_cookie_session(
+    refresh_token: impl Into<String>,
+    ttl: Option<i64>,
+) -> Cookie<'static> {
+    let cookie = Cookie::build(("refresh_token", refresh_token.into()))
         .http_only(true)
         .secure(true)
         .same_site(SameSite::Strict)
-        .path("/")
-        .max_age(max_age)
-        .expires(time::OffsetDateTime::now_utc() + max_age)
-        .build()
+        .path("/");
+    match ttl {
+        Some(ttl) => {
+            let max_age = time::Duration::seconds(ttl);
+            cookie
+                .max_age(max_age)
+                .expires(time::OffsetDateTime::now_utc() + max_age)
+                .build()
+        }
+        None => cookie.build(),
+    }
+}
+
+/// Builds the cookie holding the refresh token of `session`, which only outlives the browser
+/// session for persistent sessions.
+pub(super) fn create_session_cookie(session: &Session) -> Cookie<'static> {
+    let ttl = session
+        .is_persistent
+        .then(|| (session.expires_at - Utc::now()).num_seconds());
+    create_cookie_session(&session.refresh_token, ttl)
+}
+
+/// Records a use of `session`, sliding its expiry when configured.
+///
+/// Sessions that expired, were revoked or went unused for longer than the idle timeout are
+/// deleted instead, and `None` is returned.
+pub(super) async fn use_session(
+    state: &AppState,
+    mut session: Session,
+) -> Result<Option<Session>, AppError> {
+    let config = state.get_config().get_session();
+    let sessions = state.get_session_store();
+    let idle_timeout = Duration::seconds(*config.get_idle_timeout_secs());
+    if session.is_expired() || session.is_revoked || session.is_idle(idle_timeout) {
+        sessions.delete_session_by_user_id(session.user_id).await?;
+        return Ok(None);
+    }
+
+    let sliding = config
+        .get_sliding_expiration()
+        .then(|| session_lifetime(state, session.is_persistent));
+    session.touch(sliding, Duration::seconds(*config.get_max_lifetime_secs()));
+    sessions.touch_session(&session).await?;
+    Ok(Some(session))
+}
+
+/// How long sessions of a class last, or are extended by when sliding, never more than the
+/// maximum lifetime of a session.
+pub(super) fn session_lifetime(state: &AppState, is_persistent: bool) -> Duration {
+    let config = state.get_config();
+    let lifetime = if is_persistent {
+        Duration::seconds(*config.get_session().get_remember_me_lifetime_secs())
+    } else {
+        Duration::seconds(*config.get_jwt().get_refresh_token_expiration_secs())
+    };
+    lifetime.min(Duration::seconds(
+        *config.get_session().get_max_lifetime_secs(),
+    ))
+}
+
+/// How long refresh tokens are issued for. With sliding expiration they must outlive every
+/// extension of their session, which then decides when they stop working, as only the
+/// refresh token of the user's current session is accepted.
+pub(super) fn refresh_token_duration(state: &AppState, is_persistent: bool) -> Duration {
+    let config = state.get_config().get_session();
+    let lifetime = session_lifetime(state, is_persistent);
+    if *config.get_sliding_expiration() {
+        lifetime.max(Duration::seconds(*config.get_max_lifetime_secs()))
+    } else {
+        lifetime
+    }
+}
+
+/// Loads the target user of an admin action, hiding users outside the caller's organization.
+///
+/// Tokens issued for an organization can only reach users belonging to that organization;
+/// global tokens can reach every user.
+pub(super) async fn ensure_user_in_scope(
+    state: &AppState,
+    claims: &Claims,
+    user_id: Uuid,
+) -> Result<User, AppError> {
+    let user = state
+        .get_user_store()
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "User not found"))?;
+
+    match claims.get_org_id() {
+        Some(org_id) if user.org_id != Some(*org_id) => {
+            Err(AppError::new(StatusCode::NOT_FOUND, "User not found"))
+        }
+        _ => Ok(user),
+    }
+}
+
+/// Loads the target user of an admin action that changes them, as [`ensure_user_in_scope`]
+/// does.
+///
+/// Tokens issued for an organization cannot change users who also hold roles outside it,
+/// such as a global admin belonging to the organization.
+pub(super) async fn ensure_user_manageable(
+    state: &AppState,
+    claims: &Claims,
+    user_id: Uuid,
+) -> Result<User, AppError> {
+    let user = ensure_user_in_scope(state, claims, user_id).await?;
+    if claims.get_org_id().is_some()
+        && !state
+            .get_user_store()
+            .resolve_permissions(user.id, None)
+            .await?
+            .is_empty()
+    {
+        return Err(AppError::new(
+            StatusCode::FORBIDDEN,
+            "User holds roles outside the organization",
+        ));
+    }
+    Ok(user)
+}
+
+/// Parses a space-delimited `scope` request parameter, which may only ask for a subset of
+/// `allowed`. Every allowed scope is granted when no scope is requested.
+pub(super) fn parse_scope(
+    requested: Option<&str>,
+    allowed: &[String],
+) -> Result<Vec<String>, AppError> {
+    let Some(requested) = requested else {
+        return Ok(allowed.to_vec());
+    };
+
+    let mut scope: Vec<String> = Vec::new();
+    let mut invalid = Vec::new();
+    for name in requested.split_whitespace() {
+        if !allowed.iter().any(|allowed| allowed == name) {
+            invalid.push(name);
+        } else if !scope.iter().any(|s| s == name) {
+            scope.push(name.to_string());
+        }
+    }
+
+    if invalid.is_empty() {
+        Ok(scope)
+    } else {
+        Err(AppError::new(StatusCode::UNPROCESSABLE_ENTITY, "Invalid scope").with_details(invalid))
+    }
+}
+
+/// Records `event` in the audit log, along with where the request came from.
+pub(super) async fn audit(
+    state: &AppState,
+    context: &RequestContext,
+    event: AuditEvent,
+) -> Result<(), AppError> {
+    let event = event.origin(context.ip.clone(), context.user_agent.clone());
+    let key = state.get_config().get_audit().get_hmac_key();
+    Ok(state
+        .get_audit_store()
+        .record_audit_event(key, event)
+        .await?)
 }
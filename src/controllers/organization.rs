@@ -0,0 +1,214 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    bootstrap::AppState,
+    dto::{
+        GetAllMembershipsResDto, GetAllOrganizationsQueryDto, GetAllOrganizationsResDto,
+        MembershipReqDto, OrganizationReqDto,
+    },
+    middlewares::auth::require_permission,
+    middlewares::context::RequestContext,
+    models::{AuditEvent, Membership, Organization, Permission},
+    services,
+    token::Claims,
+    utils::{AppError, SuccessResponse},
+};
+
+use super::audit;
+
+pub async fn get_all_organizations(
+    State(state): State<AppState>,
+    claims: Claims,
+    Query(query): Query<GetAllOrganizationsQueryDto>,
+) -> Result<SuccessResponse<GetAllOrganizationsResDto>, AppError> {
+    require_permission(&claims, Permission::ORGS_READ)?;
+
+    let limit = query.limit.unwrap_or(50).min(100);
+    let offset = query.offset.unwrap_or(0);
+
+    let organizations =
+        services::get_all_organizations(state.require_db_pool()?, limit, offset).await?;
+    Ok(SuccessResponse::ok(GetAllOrganizationsResDto {
+        organizations,
+    }))
+}
+
+pub async fn get_organization(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    claims: Claims,
+) -> Result<SuccessResponse<Organization>, AppError> {
+    require_permission(&claims, Permission::ORGS_READ)?;
+
+    let organization = find_organization(&state, id).await?;
+    Ok(SuccessResponse::ok(organization))
+}
+
+pub async fn create_organization(
+    State(state): State<AppState>,
+    context: RequestContext,
+    claims: Claims,
+    Json(dto): Json<OrganizationReqDto>,
+) -> Result<SuccessResponse<Organization>, AppError> {
+    require_permission(&claims, Permission::ORGS_MANAGE)?;
+    dto.validate()
+        .map_err(|e| AppError::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{}", e)))?;
+
+    if services::get_organization_by_slug(state.require_db_pool()?, &dto.slug)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::new(
+            StatusCode::CONFLICT,
+            "Organization already exists",
+        ));
+    }
+
+    let organization = services::create_organization(
+        state.require_db_pool()?,
+        &Organization::new(dto.name, dto.slug),
+    )
+    .await?;
+    tracing::info!("Created organization {}", organization.slug);
+    let event = AuditEvent::new(AuditEvent::ORG_CREATED)
+        .actor(*claims.get_jti())
+        .details(json!({ "orgId": organization.id, "slug": organization.slug }));
+    audit(&state, &context, event).await?;
+    Ok(SuccessResponse::created(organization))
+}
+
+pub async fn delete_organization(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    context: RequestContext,
+    claims: Claims,
+) -> Result<impl IntoResponse, AppError> {
+    require_permission(&claims, Permission::ORGS_MANAGE)?;
+
+    let organization = find_organization(&state, id).await?;
+    let users = services::delete_organization(state.require_db_pool()?, id).await?;
+    tracing::info!(
+        "Deleted organization {} with {} users",
+        organization.slug,
+        users.len()
+    );
+    let user_ids: Vec<Uuid> = users.iter().map(|user| user.id).collect();
+    let event = AuditEvent::new(AuditEvent::ORG_DELETED)
+        .actor(*claims.get_jti())
+        .details(json!({
+            "orgId": organization.id,
+            "slug": organization.slug,
+            "deletedUserIds": user_ids,
+        }));
+    audit(&state, &context, event).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_members(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    claims: Claims,
+) -> Result<SuccessResponse<GetAllMembershipsResDto>, AppError> {
+    require_org_access(&claims, id, Permission::ORGS_READ)?;
+
+    find_organization(&state, id).await?;
+    let members = services::get_memberships_by_org_id(state.require_db_pool()?, id).await?;
+    Ok(SuccessResponse::ok(GetAllMembershipsResDto { members }))
+}
+
+pub async fn put_member(
+    State(state): State<AppState>,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+    context: RequestContext,
+    claims: Claims,
+    Json(dto): Json<MembershipReqDto>,
+) -> Result<SuccessResponse<Membership>, AppError> {
+    require_org_access(&claims, id, Permission::ORGS_MANAGE)?;
+
+    find_organization(&state, id).await?;
+    // Organization admins can only enroll users belonging to their organization
+    state
+        .get_user_store()
+        .get_user_by_id(user_id)
+        .await?
+        .filter(|user| claims.get_org_id().is_none() || user.org_id == Some(id))
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "User not found"))?;
+    if let Some(role_id) = dto.role_id {
+        services::get_role_by_id(state.require_db_pool()?, role_id)
+            .await?
+            .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "Role not found"))?;
+    }
+
+    let membership = services::upsert_membership(
+        state.require_db_pool()?,
+        &Membership::new(id, user_id, dto.role_id),
+    )
+    .await?;
+    tracing::info!(
+        "Set membership of user {} in organization {} with role {:?}",
+        user_id,
+        id,
+        dto.role_id
+    );
+    let event = AuditEvent::new(AuditEvent::MEMBER_UPDATED)
+        .actor(*claims.get_jti())
+        .target(user_id)
+        .details(json!({ "orgId": id, "roleId": dto.role_id }));
+    audit(&state, &context, event).await?;
+    Ok(SuccessResponse::ok(membership))
+}
+
+pub async fn delete_member(
+    State(state): State<AppState>,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+    context: RequestContext,
+    claims: Claims,
+) -> Result<impl IntoResponse, AppError> {
+    require_org_access(&claims, id, Permission::ORGS_MANAGE)?;
+
+    services::get_membership(state.require_db_pool()?, id, user_id)
+        .await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "Membership not found"))?;
+
+    services::delete_membership(state.require_db_pool()?, id, user_id).await?;
+    tracing::info!("Removed user {} from organization {}", user_id, id);
+    let event = AuditEvent::new(AuditEvent::MEMBER_REMOVED)
+        .actor(*claims.get_jti())
+        .target(user_id)
+        .details(json!({ "orgId": id }));
+    audit(&state, &context, event).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Members of an organization can be managed by its own admins, through a token issued for
+/// that organization, or by global admins holding `global_permission`.
+fn require_org_access(
+    claims: &Claims,
+    org_id: Uuid,
+    global_permission: &str,
+) -> Result<(), AppError> {
+    match claims.get_org_id() {
+        Some(claim_org_id) if *claim_org_id == org_id => {
+            require_permission(claims, Permission::MEMBERS_MANAGE)
+        }
+        Some(_) => Err(AppError::new(
+            StatusCode::NOT_FOUND,
+            "Organization not found",
+        )),
+        None => require_permission(claims, global_permission),
+    }
+}
+
+async fn find_organization(state: &AppState, id: Uuid) -> Result<Organization, AppError> {
+    services::get_organization_by_id(state.require_db_pool()?, id)
+        .await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "Organization not found"))
+}
@@ -0,0 +1,32 @@
+use axum::extract::State;
+
+use crate::{
+    bootstrap::AppState,
+    dto::GetRateLimitsResDto,
+    middlewares::auth::require_permission,
+    models::Permission,
+    token::Claims,
+    utils::{AppError, SuccessResponse},
+};
+
+/// Clients listed per policy, the busiest first.
+const MAX_CLIENTS: usize = 100;
+
+pub async fn get_rate_limits(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<SuccessResponse<GetRateLimitsResDto>, AppError> {
+    require_permission(&claims, Permission::RATE_LIMITS_READ)?;
+
+    let rate_limiter = state.get_rate_limiter();
+    let backend = if rate_limiter.is_shared().await {
+        "redis"
+    } else {
+        "memory"
+    };
+    let policies = rate_limiter.state(MAX_CLIENTS).await;
+    Ok(SuccessResponse::ok(GetRateLimitsResDto {
+        backend: backend.to_string(),
+        policies,
+    }))
+}
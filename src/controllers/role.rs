@@ -0,0 +1,307 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    bootstrap::AppState,
+    dto::{GetAllPermissionsResDto, GetAllRolesResDto, PatchRoleReqDto, RoleReqDto, RoleResDto},
+    middlewares::{auth::require_permission, context::RequestContext},
+    models::{AuditEvent, Permission, Role, User},
+    services,
+    token::Claims,
+    utils::{AppError, SuccessResponse},
+};
+
+use super::audit;
+
+pub async fn get_all_roles(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<SuccessResponse<GetAllRolesResDto>, AppError> {
+    require_permission(&claims, Permission::ROLES_READ)?;
+
+    let mut roles = Vec::new();
+    for role in services::get_all_roles(state.require_db_pool()?).await? {
+        let permissions = services::get_role_permissions(state.require_db_pool()?, role.id).await?;
+        roles.push(RoleResDto::new(role, permissions));
+    }
+    Ok(SuccessResponse::ok(GetAllRolesResDto { roles }))
+}
+
+pub async fn get_all_permissions(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<SuccessResponse<GetAllPermissionsResDto>, AppError> {
+    require_permission(&claims, Permission::ROLES_READ)?;
+
+    let permissions = services::get_all_permissions(state.require_db_pool()?).await?;
+    Ok(SuccessResponse::ok(GetAllPermissionsResDto { permissions }))
+}
+
+pub async fn get_role(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    claims: Claims,
+) -> Result<SuccessResponse<RoleResDto>, AppError> {
+    require_permission(&claims, Permission::ROLES_READ)?;
+
+    let role = find_role(&state, id).await?;
+    let permissions = services::get_role_permissions(state.require_db_pool()?, role.id).await?;
+    Ok(SuccessResponse::ok(RoleResDto::new(role, permissions)))
+}
+
+pub async fn create_role(
+    State(state): State<AppState>,
+    context: RequestContext,
+    claims: Claims,
+    Json(dto): Json<RoleReqDto>,
+) -> Result<SuccessResponse<RoleResDto>, AppError> {
+    require_permission(&claims, Permission::ROLES_MANAGE)?;
+    dto.validate()
+        .map_err(|e| AppError::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{}", e)))?;
+
+    let name = dto.name.to_lowercase();
+    if services::get_role_by_name(state.require_db_pool()?, &name)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::new(StatusCode::CONFLICT, "Role already exists"));
+    }
+    check_permissions_exist(&state, &dto.permissions).await?;
+
+    let role =
+        services::create_role(state.require_db_pool()?, &Role::new(name, dto.description)).await?;
+    services::set_role_permissions(state.require_db_pool()?, role.id, &dto.permissions).await?;
+    tracing::info!(
+        "Created role {} with permissions {:?}",
+        role.name,
+        dto.permissions
+    );
+
+    let event = AuditEvent::new(AuditEvent::ROLE_CREATED)
+        .actor(*claims.get_jti())
+        .target(role.id)
+        .details(json!({ "role": role.name, "permissions": dto.permissions }));
+    audit(&state, &context, event).await?;
+
+    let permissions = services::get_role_permissions(state.require_db_pool()?, role.id).await?;
+    Ok(SuccessResponse::created(RoleResDto::new(role, permissions)))
+}
+
+pub async fn update_role(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    context: RequestContext,
+    claims: Claims,
+    Json(dto): Json<PatchRoleReqDto>,
+) -> Result<SuccessResponse<RoleResDto>, AppError> {
+    require_permission(&claims, Permission::ROLES_MANAGE)?;
+    dto.validate()
+        .map_err(|e| AppError::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{}", e)))?;
+
+    let mut role = find_role(&state, id).await?;
+    if role.is_admin() {
+        return Err(AppError::new(
+            StatusCode::FORBIDDEN,
+            "The built-in admin role cannot be modified",
+        ));
+    }
+
+    if let Some(name) = dto.name {
+        let name = name.to_lowercase();
+        if name != role.name
+            && services::get_role_by_name(state.require_db_pool()?, &name)
+                .await?
+                .is_some()
+        {
+            return Err(AppError::new(StatusCode::CONFLICT, "Role already exists"));
+        }
+        role.name = name;
+    }
+
+    if dto.description.is_some() {
+        role.description = dto.description;
+    }
+
+    if let Some(permissions) = &dto.permissions {
+        check_permissions_exist(&state, permissions).await?;
+    }
+
+    let role = services::update_role(state.require_db_pool()?, &role).await?;
+    if let Some(permissions) = &dto.permissions {
+        services::set_role_permissions(state.require_db_pool()?, role.id, permissions).await?;
+    }
+    let event = AuditEvent::new(AuditEvent::ROLE_UPDATED)
+        .actor(*claims.get_jti())
+        .target(role.id)
+        .details(json!({ "role": role.name, "permissions": dto.permissions }));
+    audit(&state, &context, event).await?;
+
+    let permissions = services::get_role_permissions(state.require_db_pool()?, role.id).await?;
+    Ok(SuccessResponse::ok(RoleResDto::new(role, permissions)))
+}
+
+pub async fn delete_role(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    context: RequestContext,
+    claims: Claims,
+) -> Result<impl IntoResponse, AppError> {
+    require_permission(&claims, Permission::ROLES_MANAGE)?;
+
+    let role = find_role(&state, id).await?;
+    if role.is_admin() {
+        return Err(AppError::new(
+            StatusCode::FORBIDDEN,
+            "The built-in admin role cannot be deleted",
+        ));
+    }
+
+    services::delete_role(state.require_db_pool()?, id).await?;
+    tracing::info!("Deleted role {}", role.name);
+    let event = AuditEvent::new(AuditEvent::ROLE_DELETED)
+        .actor(*claims.get_jti())
+        .target(role.id)
+        .details(json!({ "role": role.name }));
+    audit(&state, &context, event).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_user_roles(
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+    claims: Claims,
+) -> Result<SuccessResponse<GetAllRolesResDto>, AppError> {
+    require_permission(&claims, Permission::ROLES_READ)?;
+
+    find_user(&state, user_id).await?;
+    let mut roles = Vec::new();
+    for role in services::get_user_roles(state.require_db_pool()?, user_id).await? {
+        let permissions = services::get_role_permissions(state.require_db_pool()?, role.id).await?;
+        roles.push(RoleResDto::new(role, permissions));
+    }
+    Ok(SuccessResponse::ok(GetAllRolesResDto { roles }))
+}
+
+pub async fn grant_user_role(
+    State(state): State<AppState>,
+    Path((user_id, role_id)): Path<(Uuid, Uuid)>,
+    context: RequestContext,
+    claims: Claims,
+) -> Result<impl IntoResponse, AppError> {
+    require_permission(&claims, Permission::ROLES_ASSIGN)?;
+
+    let user = find_user(&state, user_id).await?;
+    let role = find_role(&state, role_id).await?;
+    require_role_held(&state, &claims, &role).await?;
+
+    services::assign_user_role(state.require_db_pool()?, user.id, &role).await?;
+    tracing::info!(
+        "User {} granted role {} to user {}",
+        claims.get_jti(),
+        role.name,
+        user.id
+    );
+    let event = AuditEvent::new(AuditEvent::ROLE_GRANTED)
+        .actor(*claims.get_jti())
+        .target(user.id)
+        .details(json!({ "roleId": role.id, "role": role.name }));
+    audit(&state, &context, event).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn revoke_user_role(
+    State(state): State<AppState>,
+    Path((user_id, role_id)): Path<(Uuid, Uuid)>,
+    context: RequestContext,
+    claims: Claims,
+) -> Result<impl IntoResponse, AppError> {
+    require_permission(&claims, Permission::ROLES_ASSIGN)?;
+
+    let user = find_user(&state, user_id).await?;
+    let role = find_role(&state, role_id).await?;
+    require_role_held(&state, &claims, &role).await?;
+
+    if !services::revoke_user_role(state.require_db_pool()?, user.id, &role).await? {
+        // Either the user did not hold the role, or they are the last admin
+        if role.is_admin() && state.get_user_store().is_last_admin(user.id).await? {
+            return Err(AppError::new(
+                StatusCode::CONFLICT,
+                "Cannot revoke the admin role from the last admin",
+            ));
+        }
+        return Err(AppError::new(
+            StatusCode::NOT_FOUND,
+            "User does not have this role",
+        ));
+    }
+
+    tracing::info!(
+        "User {} revoked role {} from user {}",
+        claims.get_jti(),
+        role.name,
+        user.id
+    );
+    let event = AuditEvent::new(AuditEvent::ROLE_REVOKED)
+        .actor(*claims.get_jti())
+        .target(user.id)
+        .details(json!({ "roleId": role.id, "role": role.name }));
+    audit(&state, &context, event).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn find_user(state: &AppState, id: Uuid) -> Result<User, AppError> {
+    state
+        .get_user_store()
+        .get_user_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "User not found"))
+}
+
+async fn find_role(state: &AppState, id: Uuid) -> Result<Role, AppError> {
+    services::get_role_by_id(state.require_db_pool()?, id)
+        .await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "Role not found"))
+}
+
+/// Refuses to grant or revoke `role` unless the caller holds every permission it grants, so
+/// that `roles:assign` cannot be used to hand out more than the caller has.
+async fn require_role_held(state: &AppState, claims: &Claims, role: &Role) -> Result<(), AppError> {
+    let not_held: Vec<String> = services::get_role_permissions(state.require_db_pool()?, role.id)
+        .await?
+        .into_iter()
+        .filter(|permission| !claims.has_permission(permission))
+        .collect();
+
+    if not_held.is_empty() {
+        Ok(())
+    } else {
+        Err(
+            AppError::new(StatusCode::FORBIDDEN, "Role grants permissions not held")
+                .with_details(not_held),
+        )
+    }
+}
+
+async fn check_permissions_exist(state: &AppState, permissions: &[String]) -> Result<(), AppError> {
+    let known = services::get_all_permissions(state.require_db_pool()?).await?;
+    let unknown: Vec<&String> = permissions
+        .iter()
+        .filter(|name| !known.iter().any(|permission| &permission.name == *name))
+        .collect();
+
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(
+            AppError::new(StatusCode::UNPROCESSABLE_ENTITY, "Unknown permissions")
+                .with_details(unknown),
+        )
+    }
+}
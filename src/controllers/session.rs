@@ -6,38 +6,63 @@ use axum::{
 };
 use axum_extra::extract::PrivateCookieJar;
 use chrono::Duration;
+use serde_json::json;
 use uuid::Uuid;
 
 use crate::{
     bootstrap::AppState,
     dto::{AccessTokenReqDto, AccessTokenResDto},
-    middlewares::auth::{check_admin, RefreshClaims},
-    services::{delete_session_by_user_id, get_session_by_user_id, revoke_session},
-    token::{Claims, TokenManager},
-    utils::{AppError, SuccessResponse},
+    middlewares::auth::{get_session_token, require_own_login, require_permission, RefreshClaims},
+    middlewares::context::RequestContext,
+    models::{AuditEvent, Permission, Session},
+    services::get_membership,
+    token::{Claims, Grant, TokenManager},
+    utils::{record_session_refresh, AppError, SuccessResponse},
 };
 
-use super::create_cookie_session;
+use super::{
+    audit, create_cookie_session, create_session_cookie, ensure_user_manageable, parse_scope,
+    use_session,
+};
 
 pub async fn refresh_session_by_cookie(
     State(state): State<AppState>,
+    context: RequestContext,
+    jar: PrivateCookieJar,
     claims: RefreshClaims,
-) -> Result<SuccessResponse<AccessTokenResDto>, AppError> {
+) -> Result<(PrivateCookieJar, SuccessResponse<AccessTokenResDto>), AppError> {
     let token_manager =
         TokenManager::new(state.get_config().get_jwt().get_secret().as_bytes(), None);
 
-    handle_stale_sessions(
+    let grant = Grant {
+        permissions: Vec::new(),
+        org_id: *claims.0.get_org_id(),
+        scope: claims.0.get_scope().clone(),
+        act: None,
+    };
+    let refresh_token = get_session_token(&jar).unwrap_or_default();
+    let (session, res) = handle_stale_sessions(
         &state,
+        &refresh_token,
         *claims.0.get_jti(),
-        *claims.0.get_is_admin(),
         claims.0.get_sub(),
+        grant,
         token_manager,
     )
-    .await
+    .await?;
+
+    let event = AuditEvent::new(AuditEvent::SESSION_REFRESHED)
+        .actor(*claims.0.get_jti())
+        .target(*claims.0.get_jti());
+    audit(&state, &context, event).await?;
+
+    // The cookie is set again, so that it follows the expiry of persistent sessions
+    Ok((jar.add(create_session_cookie(&session)), res))
 }
 
 pub async fn refresh_session_by_body(
     State(state): State<AppState>,
+    context: RequestContext,
     Json(dto): Json<AccessTokenReqDto>,
 ) -> Result<SuccessResponse<AccessTokenResDto>, AppError> {
     let token_manager =
@@ -45,24 +70,46 @@ pub async fn refresh_session_by_body(
 
     let token = token_manager.validate_refresh_token(&dto.refresh_token)?;
 
-    handle_stale_sessions(
+    let grant = Grant {
+        permissions: Vec::new(),
+        org_id: *token.get_org_id(),
+        scope: parse_scope(dto.scope.as_deref(), token.get_scope())?,
+        act: None,
+    };
+    let (_, res) = handle_stale_sessions(
         &state,
+        &dto.refresh_token,
         *token.get_jti(),
-        *token.get_is_admin(),
         token.get_sub(),
+        grant,
         token_manager,
     )
-    .await
+    .await?;
+
+    let event = AuditEvent::new(AuditEvent::SESSION_REFRESHED)
+        .actor(*token.get_jti())
+        .target(*token.get_jti());
+    audit(&state, &context, event).await?;
+    Ok(res)
 }
 
 pub async fn revoke_my_session(
     State(state): State<AppState>,
+    context: RequestContext,
     jar: PrivateCookieJar,
     claims: Claims,
 ) -> Result<impl IntoResponse, AppError> {
-    revoke_session(state.get_db_pool(), *claims.get_jti()).await?;
-
-    let cookie = create_cookie_session("", 0);
+    require_own_login(&claims)?;
+    state
+        .get_session_store()
+        .revoke_session(*claims.get_jti())
+        .await?;
+    let event = AuditEvent::new(AuditEvent::SESSION_REVOKED)
+        .actor(*claims.get_jti())
+        .target(*claims.get_jti());
+    audit(&state, &context, event).await?;
+
+    let cookie = create_cookie_session("", Some(0));
     let jar = jar.add(cookie);
 
     Ok((jar, StatusCode::NO_CONTENT))
@@ -71,51 +118,90 @@ pub async fn revoke_my_session(
 pub async fn revoke_user_session(
     State(state): State<AppState>,
     Path(user_id): Path<Uuid>,
+    context: RequestContext,
     claims: Claims,
 ) -> Result<impl IntoResponse, AppError> {
-    check_admin(&claims)?;
-
-    revoke_session(state.get_db_pool(), user_id).await?;
+    require_permission(&claims, Permission::SESSIONS_REVOKE)?;
+    ensure_user_manageable(&state, &claims, user_id).await?;
+
+    state.get_session_store().revoke_session(user_id).await?;
+    let event = AuditEvent::new(AuditEvent::SESSION_REVOKED)
+        .actor(*claims.get_jti())
+        .target(user_id);
+    audit(&state, &context, event).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn revoke_all_sessions(
     State(state): State<AppState>,
+    context: RequestContext,
     claims: Claims,
 ) -> Result<impl IntoResponse, AppError> {
-    check_admin(&claims)?;
+    require_permission(&claims, Permission::SESSIONS_REVOKE)?;
 
-    revoke_session(state.get_db_pool(), Uuid::nil()).await?;
+    let sessions = state.get_session_store();
+    match claims.get_org_id() {
+        Some(org_id) => sessions.revoke_org_sessions(*org_id).await?,
+        None => sessions.revoke_all_sessions().await?,
+    }
+    let event = AuditEvent::new(AuditEvent::SESSION_REVOKED)
+        .actor(*claims.get_jti())
+        .details(json!({ "all": true, "orgId": claims.get_org_id() }));
+    audit(&state, &context, event).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Issues an access token through the session of `user_id`, which must hold `refresh_token`:
+/// tokens of a session since logged out or replaced are rejected however long they last.
 async fn handle_stale_sessions(
     state: &AppState,
+    refresh_token: &str,
     user_id: Uuid,
-    is_admin: bool,
     sub: &str,
+    mut grant: Grant,
     token_manager: TokenManager<'_>,
-) -> Result<SuccessResponse<AccessTokenResDto>, AppError> {
-    if let Some(session) = get_session_by_user_id(state.get_db_pool(), user_id).await? {
-        if session.is_expired() || session.is_revoked {
-            delete_session_by_user_id(state.get_db_pool(), session.user_id).await?;
+) -> Result<(Session, SuccessResponse<AccessTokenResDto>), AppError> {
+    let Some(session) = state
+        .get_session_store()
+        .get_session_by_user_id(user_id)
+        .await?
+    else {
+        return Err(AppError::new(StatusCode::UNAUTHORIZED, "Invalid token"));
+    };
+    if session.refresh_token != refresh_token {
+        return Err(AppError::new(StatusCode::UNAUTHORIZED, "Invalid token"));
+    }
+    let Some(session) = use_session(state, session).await? else {
+        return Err(AppError::new(StatusCode::UNAUTHORIZED, "Invalid token"));
+    };
+
+    let duration = Duration::seconds(
+        *state
+            .get_config()
+            .get_jwt()
+            .get_access_token_expiration_secs(),
+    );
+    // Permissions are resolved again so that role changes apply from the next refresh
+    // Membership is checked again so that removed members cannot refresh into the organization
+    if let Some(org_id) = grant.org_id {
+        if get_membership(state.require_db_pool()?, org_id, session.user_id)
+            .await?
+            .is_none()
+        {
             return Err(AppError::new(StatusCode::UNAUTHORIZED, "Invalid token"));
-        } else {
-            let duration = Duration::seconds(
-                *state
-                    .get_config()
-                    .get_jwt()
-                    .get_access_token_expiration_secs(),
-            );
-            let (access_token, access_claims) =
-                token_manager.create_access_token(session.user_id, sub, is_admin, duration)?;
-
-            return Ok(SuccessResponse::created(AccessTokenResDto {
-                access_token,
-                access_token_expires_at: *access_claims.get_exp(),
-            }));
         }
     }
-
-    Err(AppError::new(StatusCode::UNAUTHORIZED, "Invalid token"))
+    grant.permissions = state
+        .get_user_store()
+        .resolve_permissions(session.user_id, grant.org_id)
+        .await?;
+    let (access_token, access_claims) =
+        token_manager.create_access_token(session.user_id, sub, grant, duration)?;
+    record_session_refresh();
+
+    let res = SuccessResponse::created(AccessTokenResDto {
+        access_token,
+        access_token_expires_at: *access_claims.get_exp(),
+    });
+    Ok((session, res))
 }
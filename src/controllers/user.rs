@@ -1,30 +1,38 @@
+use std::io;
+
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
     response::IntoResponse,
     Json,
 };
+use futures_util::TryStreamExt;
+use serde_json::json;
+use tokio_util::io::StreamReader;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
     bootstrap::AppState,
     dto::{
-        process_optional_fields, GetAllUsersQueryDto, GetAllUsersResDto, PatchReqDto, UserReqDto,
+        process_optional_fields, AcceptInviteReqDto, ExportUsersQueryDto, GetAllUsersQueryDto,
+        GetAllUsersResDto, ImportReportDto, ImportUsersQueryDto, PatchReqDto, UserReqDto,
         UserResDto,
     },
-    middlewares::auth::check_admin,
-    models::User,
-    services::{
-        self, get_user_by_email, get_user_by_github_id, get_user_by_id, get_user_by_username,
-        get_user_by_username_or_email,
-    },
+    middlewares::auth::{require_own_login, require_permission},
+    middlewares::context::RequestContext,
+    models::{AuditEvent, Permission, User},
+    services,
     token::Claims,
-    utils::{hash_password, AppError, SuccessResponse},
+    utils::{hash_password, password_policy_error, AppError, SuccessResponse},
 };
 
+use super::{audit, ensure_user_in_scope, ensure_user_manageable};
+
 pub async fn register(
     State(state): State<AppState>,
+    context: RequestContext,
     Json(dto): Json<UserReqDto>,
 ) -> Result<SuccessResponse<UserResDto>, AppError> {
     dto.validate()
@@ -32,14 +40,18 @@ pub async fn register(
 
     let (username, email) = process_optional_fields(dto.username, dto.email)?;
 
-    if get_user_by_username_or_email(state.get_db_pool(), &username, &email)
+    if state
+        .get_user_store()
+        .find_user_by_username_or_email(state.get_config(), None, &username, &email)
         .await?
         .is_some()
     {
         return Err(AppError::new(StatusCode::CONFLICT, "User already exists"));
     }
 
-    let password_hash = hash_password(&dto.password)?;
+    enforce_password_policy(&state, &dto.password, &[&username, &email], None).await?;
+
+    let password_hash = hash_password(&dto.password, state.get_config().get_password_hashing())?;
 
     let new_user = User::new(
         dto.github_id,
@@ -49,7 +61,19 @@ pub async fn register(
         dto.avatar_url,
     );
     tracing::info!("Creating new user: {}", new_user);
-    let user = services::create_user(state.get_db_pool(), &new_user).await?;
+    let users = state.get_user_store();
+    let user = users.create_user(&new_user).await?;
+    users
+        .record_password_history(
+            state.get_config().get_password_policy(),
+            user.id,
+            &user.password_hash,
+        )
+        .await?;
+    let event = AuditEvent::new(AuditEvent::USER_CREATED)
+        .actor(user.id)
+        .target(user.id);
+    audit(&state, &context, event).await?;
     Ok(SuccessResponse::created(UserResDto::from(user)))
 }
 
@@ -58,7 +82,7 @@ pub async fn get_all_users(
     claims: Claims,
     Query(query): Query<GetAllUsersQueryDto>,
 ) -> Result<SuccessResponse<GetAllUsersResDto>, AppError> {
-    check_admin(&claims)?;
+    require_permission(&claims, Permission::USERS_READ)?;
 
     let limit = query.limit.unwrap_or(50);
     let offset = query.offset.unwrap_or(0);
@@ -66,47 +90,89 @@ pub async fn get_all_users(
     // Limit the number of users to fetch to 100 for now.
     let limit = limit.min(100);
 
-    let users = services::get_all_users(state.get_db_pool(), limit, offset).await?;
+    let users = state
+        .get_user_store()
+        .get_all_users(*claims.get_org_id(), limit, offset)
+        .await?;
     Ok(SuccessResponse::ok(GetAllUsersResDto::from(users)))
 }
 
 pub async fn update_me(
     State(state): State<AppState>,
+    context: RequestContext,
     claims: Claims,
     Json(dto): Json<PatchReqDto>,
 ) -> Result<SuccessResponse<UserResDto>, AppError> {
-    handle_patch_updates(&state, dto, *claims.get_jti()).await
+    require_own_login(&claims)?;
+
+    let fields = dto.changed_fields();
+    let res = handle_patch_updates(&state, dto, *claims.get_jti()).await?;
+    let event = AuditEvent::new(AuditEvent::USER_UPDATED)
+        .actor(*claims.get_jti())
+        .target(*claims.get_jti())
+        .details(json!({ "fields": fields }));
+    audit(&state, &context, event).await?;
+    Ok(res)
 }
 
 pub async fn update_user(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
+    context: RequestContext,
     claims: Claims,
     Json(dto): Json<PatchReqDto>,
 ) -> Result<SuccessResponse<UserResDto>, AppError> {
-    check_admin(&claims)?;
+    require_permission(&claims, Permission::USERS_UPDATE)?;
+    ensure_user_manageable(&state, &claims, id).await?;
 
-    handle_patch_updates(&state, dto, id).await
+    let fields = dto.changed_fields();
+    let res = handle_patch_updates(&state, dto, id).await?;
+    let event = AuditEvent::new(AuditEvent::USER_UPDATED)
+        .actor(*claims.get_jti())
+        .target(id)
+        .details(json!({ "fields": fields }));
+    audit(&state, &context, event).await?;
+    Ok(res)
 }
 
 pub async fn delete_user(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
+    context: RequestContext,
     claims: Claims,
 ) -> Result<impl IntoResponse, AppError> {
-    check_admin(&claims)?;
+    require_permission(&claims, Permission::USERS_DELETE)?;
+    let user = ensure_user_manageable(&state, &claims, id).await?;
+    ensure_not_last_admin(&state, id).await?;
 
-    services::delete_user(state.get_db_pool(), id).await?;
+    state.get_user_store().delete_user(id).await?;
     tracing::info!("Deleted user with ID: {}", id);
+    let event = AuditEvent::new(AuditEvent::USER_DELETED)
+        .actor(*claims.get_jti())
+        .target(id)
+        .details(json!({ "username": user.username, "email": user.email }));
+    audit(&state, &context, event).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn delete_me(
     State(state): State<AppState>,
+    context: RequestContext,
     claims: Claims,
 ) -> Result<impl IntoResponse, AppError> {
-    services::delete_user(state.get_db_pool(), *claims.get_jti()).await?;
+    require_own_login(&claims)?;
+    ensure_not_last_admin(&state, *claims.get_jti()).await?;
+
+    state
+        .get_user_store()
+        .delete_user(*claims.get_jti())
+        .await?;
     tracing::info!("Deleted user with ID: {}", claims.get_jti());
+    let event = AuditEvent::new(AuditEvent::USER_DELETED)
+        .actor(*claims.get_jti())
+        .target(*claims.get_jti())
+        .details(json!({ "email": claims.get_sub() }));
+    audit(&state, &context, event).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -115,20 +181,125 @@ pub async fn get_user(
     Path(id): Path<Uuid>,
     claims: Claims,
 ) -> Result<SuccessResponse<UserResDto>, AppError> {
-    check_admin(&claims)?;
-    let user = get_user_by_id(state.get_db_pool(), id)
+    require_permission(&claims, Permission::USERS_READ)?;
+    let user = ensure_user_in_scope(&state, &claims, id).await?;
+    Ok(SuccessResponse::ok(UserResDto::from(user)))
+}
+
+pub async fn get_me(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<SuccessResponse<UserResDto>, AppError> {
+    let user = state
+        .get_user_store()
+        .get_user_by_id(*claims.get_jti())
         .await?
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "User not found"))?;
     Ok(SuccessResponse::ok(UserResDto::from(user)))
 }
 
-pub async fn get_me(
+pub async fn import_users(
+    State(state): State<AppState>,
+    context: RequestContext,
+    claims: Claims,
+    Query(query): Query<ImportUsersQueryDto>,
+    body: Body,
+) -> Result<SuccessResponse<ImportReportDto>, AppError> {
+    require_permission(&claims, Permission::USERS_IMPORT)?;
+
+    let reader = StreamReader::new(body.into_data_stream().map_err(io::Error::other));
+    let report = services::import_users(
+        state.require_db_pool()?,
+        state.get_config(),
+        *claims.get_org_id(),
+        reader,
+        &query,
+    )
+    .await?;
+    if !report.dry_run {
+        let event = AuditEvent::new(AuditEvent::USERS_IMPORTED)
+            .actor(*claims.get_jti())
+            .details(json!({
+                "orgId": claims.get_org_id(),
+                "total": report.total,
+                "imported": report.imported,
+                "failed": report.failed,
+            }));
+        audit(&state, &context, event).await?;
+    }
+    Ok(SuccessResponse::ok(report))
+}
+
+pub async fn export_users(
     State(state): State<AppState>,
     claims: Claims,
+    Query(query): Query<ExportUsersQueryDto>,
+) -> Result<impl IntoResponse, AppError> {
+    require_permission(&claims, Permission::USERS_EXPORT)?;
+
+    let headers = [
+        (
+            header::CONTENT_TYPE,
+            query.format.content_type().to_string(),
+        ),
+        (
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"users.{}\"",
+                query.format.extension()
+            ),
+        ),
+    ];
+    let body = Body::from_stream(services::export_users(
+        state.require_db_pool()?.clone(),
+        *claims.get_org_id(),
+        query.format,
+    ));
+    Ok((headers, body))
+}
+
+pub async fn accept_invite(
+    State(state): State<AppState>,
+    context: RequestContext,
+    Json(dto): Json<AcceptInviteReqDto>,
 ) -> Result<SuccessResponse<UserResDto>, AppError> {
-    let user = get_user_by_id(state.get_db_pool(), *claims.get_jti())
+    let invite = services::get_user_invite_by_token(state.require_db_pool()?, &dto.token)
+        .await?
+        .filter(|invite| !invite.is_expired())
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "Invalid or expired invite"))?;
+
+    let users = state.get_user_store();
+    let user = users
+        .get_user_by_id(invite.user_id)
         .await?
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "User not found"))?;
+
+    enforce_password_policy(
+        &state,
+        &dto.password,
+        &[&user.username, &user.email],
+        Some(user.id),
+    )
+    .await?;
+
+    let password_hash = hash_password(&dto.password, state.get_config().get_password_hashing())?;
+    users
+        .update_user_password_hash(user.id, &password_hash)
+        .await?;
+    users
+        .record_password_history(
+            state.get_config().get_password_policy(),
+            user.id,
+            &password_hash,
+        )
+        .await?;
+    services::delete_user_invites(state.require_db_pool()?, user.id).await?;
+
+    tracing::info!("Accepted invite for user with ID: {}", user.id);
+    let event = AuditEvent::new(AuditEvent::INVITE_ACCEPTED)
+        .actor(user.id)
+        .target(user.id);
+    audit(&state, &context, event).await?;
     Ok(SuccessResponse::ok(UserResDto::from(user)))
 }
 
@@ -140,13 +311,16 @@ async fn handle_patch_updates(
     dto.validate()
         .map_err(|e| AppError::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{}", e)))?;
 
-    let mut user = get_user_by_id(state.get_db_pool(), user_id)
+    let users = state.get_user_store();
+    let mut user = users
+        .get_user_by_id(user_id)
         .await?
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "User not found"))?;
 
     if dto.username.is_some() {
         let username = dto.username.unwrap_or_default();
-        if get_user_by_username(state.get_db_pool(), &username)
+        if users
+            .find_user_by_username(state.get_config(), user.org_id, &username)
             .await?
             .is_some()
         {
@@ -160,7 +334,8 @@ async fn handle_patch_updates(
 
     if dto.email.is_some() {
         let email = dto.email.unwrap_or_default();
-        if get_user_by_email(state.get_db_pool(), &email)
+        if users
+            .find_user_by_email(state.get_config(), user.org_id, &email)
             .await?
             .is_some()
         {
@@ -169,11 +344,8 @@ async fn handle_patch_updates(
         user.email = email;
     }
 
-    if dto.github_id.is_some() {
-        if get_user_by_github_id(state.get_db_pool(), dto.github_id.unwrap())
-            .await?
-            .is_some()
-        {
+    if let Some(github_id) = dto.github_id {
+        if users.get_user_by_github_id(github_id).await?.is_some() {
             return Err(AppError::new(
                 StatusCode::CONFLICT,
                 "GitHub ID already exists",
@@ -182,15 +354,64 @@ async fn handle_patch_updates(
         user.github_id = dto.github_id;
     }
 
-    if dto.password.is_some() {
-        let password = dto.password.unwrap_or_default();
-        user.password_hash = hash_password(&password)?;
+    let password_changed = dto.password.is_some();
+    if let Some(password) = dto.password {
+        enforce_password_policy(
+            state,
+            &password,
+            &[&user.username, &user.email],
+            Some(user.id),
+        )
+        .await?;
+        user.password_hash = hash_password(&password, state.get_config().get_password_hashing())?;
     }
 
     if dto.avatar_url.is_some() {
         user.avatar_url = dto.avatar_url;
     }
 
-    let user = services::update_user(state.get_db_pool(), &user).await?;
+    let user = users.update_user(&user).await?;
+    if password_changed {
+        users
+            .record_password_history(
+                state.get_config().get_password_policy(),
+                user.id,
+                &user.password_hash,
+            )
+            .await?;
+    }
     Ok(SuccessResponse::ok(UserResDto::from(user)))
 }
+
+async fn ensure_not_last_admin(state: &AppState, user_id: Uuid) -> Result<(), AppError> {
+    if state.get_user_store().is_last_admin(user_id).await? {
+        return Err(AppError::new(
+            StatusCode::CONFLICT,
+            "Cannot delete the last admin",
+        ));
+    }
+    Ok(())
+}
+
+async fn enforce_password_policy(
+    state: &AppState,
+    password: &str,
+    user_inputs: &[&str],
+    user_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let violations = services::validate_password(
+        state.get_user_store().as_ref(),
+        state.get_config(),
+        state.get_breached_passwords().as_deref(),
+        password,
+        user_inputs,
+        user_id,
+    )
+    .await?;
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(password_policy_error(violations))
+    }
+}
@@ -0,0 +1,206 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    bootstrap::AppState,
+    dto::{
+        CreateWebhookSubscriptionResDto, GetAllWebhookDeliveriesQueryDto,
+        GetAllWebhookDeliveriesResDto, GetAllWebhookSubscriptionsResDto,
+        PatchWebhookSubscriptionReqDto, WebhookSubscriptionReqDto,
+    },
+    middlewares::auth::require_permission,
+    middlewares::context::RequestContext,
+    models::{AuditEvent, OutboxEvent, Permission, WebhookSubscription},
+    services,
+    token::Claims,
+    utils::{AppError, SuccessResponse},
+};
+
+use super::audit;
+
+pub async fn create_webhook_subscription(
+    State(state): State<AppState>,
+    context: RequestContext,
+    claims: Claims,
+    Json(dto): Json<WebhookSubscriptionReqDto>,
+) -> Result<SuccessResponse<CreateWebhookSubscriptionResDto>, AppError> {
+    require_permission(&claims, Permission::WEBHOOKS_MANAGE)?;
+    dto.validate()
+        .map_err(|e| AppError::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{}", e)))?;
+    check_event_types(&dto.event_types)?;
+
+    let subscription =
+        services::create_webhook_subscription(state.require_db_pool()?, &dto.url, dto.event_types)
+            .await?;
+    tracing::info!(
+        "User {} subscribed {} to webhooks",
+        claims.get_jti(),
+        subscription.url
+    );
+    let event = AuditEvent::new(AuditEvent::WEBHOOK_CREATED)
+        .actor(*claims.get_jti())
+        .details(json!({
+            "subscriptionId": subscription.id,
+            "url": subscription.url,
+            "eventTypes": subscription.event_types,
+        }));
+    audit(&state, &context, event).await?;
+    Ok(SuccessResponse::created(CreateWebhookSubscriptionResDto {
+        secret: subscription.secret.clone(),
+        subscription,
+    }))
+}
+
+pub async fn get_all_webhook_subscriptions(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<SuccessResponse<GetAllWebhookSubscriptionsResDto>, AppError> {
+    require_permission(&claims, Permission::WEBHOOKS_MANAGE)?;
+
+    let subscriptions = services::get_webhook_subscriptions(state.require_db_pool()?).await?;
+    Ok(SuccessResponse::ok(GetAllWebhookSubscriptionsResDto {
+        subscriptions,
+    }))
+}
+
+pub async fn get_webhook_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    claims: Claims,
+) -> Result<SuccessResponse<WebhookSubscription>, AppError> {
+    require_permission(&claims, Permission::WEBHOOKS_MANAGE)?;
+
+    let subscription = find_subscription(&state, id).await?;
+    Ok(SuccessResponse::ok(subscription))
+}
+
+pub async fn update_webhook_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    context: RequestContext,
+    claims: Claims,
+    Json(dto): Json<PatchWebhookSubscriptionReqDto>,
+) -> Result<SuccessResponse<WebhookSubscription>, AppError> {
+    require_permission(&claims, Permission::WEBHOOKS_MANAGE)?;
+    dto.validate()
+        .map_err(|e| AppError::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{}", e)))?;
+
+    let mut subscription = find_subscription(&state, id).await?;
+    if let Some(url) = dto.url {
+        subscription.url = url;
+    }
+    if let Some(event_types) = dto.event_types {
+        check_event_types(&event_types)?;
+        subscription.event_types = event_types;
+    }
+    if let Some(is_active) = dto.is_active {
+        subscription.is_active = is_active;
+    }
+
+    let subscription =
+        services::update_webhook_subscription(state.require_db_pool()?, &subscription).await?;
+    tracing::info!(
+        "User {} updated webhook subscription {}",
+        claims.get_jti(),
+        id
+    );
+    let event = AuditEvent::new(AuditEvent::WEBHOOK_UPDATED)
+        .actor(*claims.get_jti())
+        .details(json!({
+            "subscriptionId": subscription.id,
+            "url": subscription.url,
+            "eventTypes": subscription.event_types,
+            "isActive": subscription.is_active,
+        }));
+    audit(&state, &context, event).await?;
+    Ok(SuccessResponse::ok(subscription))
+}
+
+pub async fn delete_webhook_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    context: RequestContext,
+    claims: Claims,
+) -> Result<impl IntoResponse, AppError> {
+    require_permission(&claims, Permission::WEBHOOKS_MANAGE)?;
+
+    if !services::delete_webhook_subscription(state.require_db_pool()?, id).await? {
+        return Err(AppError::new(
+            StatusCode::NOT_FOUND,
+            "Webhook subscription not found",
+        ));
+    }
+    tracing::info!(
+        "User {} deleted webhook subscription {}",
+        claims.get_jti(),
+        id
+    );
+    let event = AuditEvent::new(AuditEvent::WEBHOOK_DELETED)
+        .actor(*claims.get_jti())
+        .details(json!({ "subscriptionId": id }));
+    audit(&state, &context, event).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_dead_webhook_deliveries(
+    State(state): State<AppState>,
+    claims: Claims,
+    Query(query): Query<GetAllWebhookDeliveriesQueryDto>,
+) -> Result<SuccessResponse<GetAllWebhookDeliveriesResDto>, AppError> {
+    require_permission(&claims, Permission::WEBHOOKS_MANAGE)?;
+
+    let limit = query.limit.unwrap_or(50).min(100);
+    let offset = query.offset.unwrap_or(0);
+
+    let deliveries =
+        services::get_dead_webhook_deliveries(state.require_db_pool()?, limit, offset).await?;
+    Ok(SuccessResponse::ok(GetAllWebhookDeliveriesResDto {
+        deliveries,
+    }))
+}
+
+pub async fn retry_webhook_delivery(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    claims: Claims,
+) -> Result<impl IntoResponse, AppError> {
+    require_permission(&claims, Permission::WEBHOOKS_MANAGE)?;
+
+    if !services::retry_webhook_delivery(state.require_db_pool()?, id).await? {
+        return Err(AppError::new(
+            StatusCode::NOT_FOUND,
+            "Dead webhook delivery not found",
+        ));
+    }
+    tracing::info!("User {} retried webhook delivery {}", claims.get_jti(), id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn find_subscription(state: &AppState, id: Uuid) -> Result<WebhookSubscription, AppError> {
+    services::get_webhook_subscription_by_id(state.require_db_pool()?, id)
+        .await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "Webhook subscription not found"))
+}
+
+fn check_event_types(event_types: &[String]) -> Result<(), AppError> {
+    let unknown: Vec<&String> = event_types
+        .iter()
+        .filter(|event_type| !OutboxEvent::ALL.contains(&event_type.as_str()))
+        .collect();
+
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(
+            AppError::new(StatusCode::UNPROCESSABLE_ENTITY, "Unknown event types")
+                .with_details(unknown),
+        )
+    }
+}
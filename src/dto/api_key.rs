@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::ApiKey;
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ApiKeyReqDto {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    /// Permissions the key may use, among those held by its owner.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// The key never expires when omitted.
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyResDto {
+    pub id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub is_revoked: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyResDto {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            id: api_key.id,
+            name: api_key.name,
+            prefix: api_key.prefix,
+            scopes: api_key.scopes,
+            expires_at: api_key.expires_at,
+            is_revoked: api_key.is_revoked,
+            created_at: api_key.created_at,
+        }
+    }
+}
+
+/// The created key, whose secret is only ever returned here.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyResDto {
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyResDto,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetAllApiKeysResDto {
+    pub api_keys: Vec<ApiKeyResDto>,
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{AuditEvent, AuditEventFilter};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetAllAuditEventsQueryDto {
+    #[serde(default)]
+    pub event_type: Option<String>,
+    #[serde(default)]
+    pub actor_id: Option<Uuid>,
+    #[serde(default)]
+    pub target_id: Option<Uuid>,
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+impl GetAllAuditEventsQueryDto {
+    pub fn filter(&self) -> AuditEventFilter {
+        AuditEventFilter {
+            event_type: self.event_type.clone(),
+            actor_id: self.actor_id,
+            target_id: self.target_id,
+            from: self.from,
+            to: self.to,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetAllAuditEventsResDto {
+    pub audit_events: Vec<AuditEvent>,
+}
+
+/// The first event whose link in the audit chain does not hold.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenLinkDto {
+    pub seq: i64,
+    pub id: Uuid,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditChainReportDto {
+    /// Chained events checked, up to the first broken link.
+    pub checked: i64,
+    /// Events recorded before chaining, which cannot be checked.
+    pub unchained: i64,
+    /// Hash of the last event checked.
+    pub head: Option<String>,
+    pub broken_link: Option<BrokenLinkDto>,
+}
+
+/// Pins an event of the audit chain outside the database, so that truncating the log can be
+/// detected as well.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditCheckpointDto {
+    pub seq: i64,
+    pub id: Uuid,
+    pub hash: String,
+    pub exported_at: DateTime<Utc>,
+}
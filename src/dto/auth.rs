@@ -14,7 +14,6 @@ pub struct RegisterDto {
     #[serde(default, deserialize_with = "super::to_lowercase")]
     pub email: Option<String>,
 
-    #[validate(length(min = 8, max = 128))]
     pub password: String,
 
     #[serde(default)]
@@ -25,7 +24,7 @@ pub struct RegisterDto {
     pub avatar_url: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Default, Serialize, Deserialize, Validate)]
 pub struct LoginReqDto {
     #[validate(length(min = 3, max = 30))]
     #[serde(default, deserialize_with = "super::to_lowercase")]
@@ -35,8 +34,22 @@ pub struct LoginReqDto {
     #[serde(default, deserialize_with = "super::to_lowercase")]
     pub email: Option<String>,
 
-    #[validate(length(min = 8, max = 128))]
+    // Only bound the input here: the password policy applies when a password is set,
+    // so tightening it must not lock out users with older passwords.
+    #[validate(length(min = 1, max = 1024))]
     pub password: String,
+
+    /// Slug of the organization to sign in to; defaults to the user's own organization.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+
+    /// Space-delimited scopes to limit the tokens to; every scope is granted when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+
+    /// Opens a persistent session, kept across browser restarts, instead of a browser session.
+    #[serde(default)]
+    pub remember_me: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
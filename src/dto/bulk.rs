@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::User;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkFormat {
+    #[default]
+    Csv,
+    Jsonl,
+}
+
+impl BulkFormat {
+    /// Infers the format from a file extension (`.csv`, `.jsonl` or `.ndjson`).
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(Self::Csv),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Jsonl => "application/x-ndjson",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportUsersQueryDto {
+    #[serde(default)]
+    pub format: BulkFormat,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Import rows without a `password_hash` and issue invite tokens for them.
+    #[serde(default)]
+    pub invite: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportUsersQueryDto {
+    #[serde(default)]
+    pub format: BulkFormat,
+}
+
+/// A single user in an import file. Columns not listed here, such as the `id` and
+/// timestamps of an export, are ignored.
+#[derive(Debug, Deserialize, Validate)]
+pub struct ImportUserRowDto {
+    #[validate(length(min = 3, max = 30))]
+    pub username: String,
+    #[validate(email, length(max = 320))]
+    pub email: String,
+    #[serde(default)]
+    pub password_hash: Option<String>,
+    #[validate(url)]
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    #[serde(default)]
+    pub github_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportUserRowDto {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub is_admin: bool,
+    pub avatar_url: Option<String>,
+    pub github_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<User> for ExportUserRowDto {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            is_admin: user.is_admin,
+            avatar_url: user.avatar_url,
+            github_id: user.github_id,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReportDto {
+    pub dry_run: bool,
+    pub total: usize,
+    pub imported: usize,
+    pub failed: usize,
+    pub errors: Vec<ImportErrorDto>,
+    pub invites: Vec<ImportInviteDto>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportErrorDto {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportInviteDto {
+    pub line: usize,
+    pub email: String,
+    pub token: String,
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// The outcome of checking one part of the configuration.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigCheckDto {
+    /// The configuration section checked, such as `jwt` or `database`.
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
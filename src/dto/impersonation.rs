@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::models::Impersonation;
+
+#[derive(Debug, Default, Serialize, Deserialize, Validate)]
+pub struct ImpersonateReqDto {
+    /// Why the user is impersonated, kept in the audit trail.
+    #[validate(length(max = 255))]
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImpersonateResDto {
+    pub access_token: String,
+    pub access_token_expires_at: DateTime<Utc>,
+    pub impersonation: Impersonation,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetAllImpersonationsQueryDto {
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetAllImpersonationsResDto {
+    pub impersonations: Vec<Impersonation>,
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// Where the database stands with one of the embedded migrations.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationStatusDto {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    /// Whether the applied script differs from the embedded one.
+    pub checksum_mismatch: bool,
+}
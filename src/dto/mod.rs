@@ -1,11 +1,31 @@
+mod api_key;
+mod audit;
 mod auth;
+mod bulk;
+mod config;
+mod impersonation;
+mod migration;
+mod organization;
+mod rate_limit;
+mod role;
 mod session;
 mod user;
+mod webhook;
 
+pub use api_key::*;
+pub use audit::*;
 pub use auth::*;
 use axum::http::StatusCode;
+pub use bulk::*;
+pub use config::*;
+pub use impersonation::*;
+pub use migration::*;
+pub use organization::*;
+pub use rate_limit::*;
+pub use role::*;
 pub use session::*;
 pub use user::*;
+pub use webhook::*;
 
 use serde::{Deserialize, Deserializer};
 
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::models::{Membership, Organization};
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct OrganizationReqDto {
+    #[validate(length(min = 2, max = 100))]
+    pub name: String,
+    #[validate(length(min = 2, max = 50), custom(function = "validate_slug"))]
+    pub slug: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetAllOrganizationsQueryDto {
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetAllOrganizationsResDto {
+    pub organizations: Vec<Organization>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MembershipReqDto {
+    /// Role granting the member permissions within the organization.
+    #[serde(default)]
+    pub role_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetAllMembershipsResDto {
+    pub members: Vec<Membership>,
+}
+
+/// Slugs select an organization at login, so they are kept to lowercase ASCII letters,
+/// digits and inner hyphens.
+fn validate_slug(slug: &str) -> Result<(), ValidationError> {
+    let valid = slug
+        .bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-');
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("slug"))
+    }
+}
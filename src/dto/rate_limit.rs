@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::RateLimitPolicyState;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetRateLimitsResDto {
+    /// Where the counters are kept: `redis`, or `memory` while Redis is unreachable.
+    pub backend: String,
+    pub policies: Vec<RateLimitPolicyState>,
+}
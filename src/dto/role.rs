@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{Permission, Role};
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct RoleReqDto {
+    #[validate(length(min = 2, max = 50))]
+    pub name: String,
+    #[validate(length(max = 255))]
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PatchRoleReqDto {
+    #[validate(length(min = 2, max = 50))]
+    #[serde(default)]
+    pub name: Option<String>,
+    #[validate(length(max = 255))]
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Replaces the role's permissions when present.
+    #[serde(default)]
+    pub permissions: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct RoleResDto {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+}
+
+impl RoleResDto {
+    pub fn new(role: Role, permissions: Vec<String>) -> Self {
+        Self {
+            id: role.id,
+            name: role.name,
+            description: role.description,
+            permissions,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetAllRolesResDto {
+    pub roles: Vec<RoleResDto>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetAllPermissionsResDto {
+    pub permissions: Vec<Permission>,
+}
@@ -3,9 +3,12 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Deserialize)]
 pub struct AccessTokenReqDto {
     pub refresh_token: String,
+    /// Space-delimited subset of the refresh token's scopes to limit the access token to.
+    #[serde(default)]
+    pub scope: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AccessTokenResDto {
     pub access_token: String,
@@ -11,7 +11,6 @@ pub struct UserReqDto {
     #[validate(email, length(max = 320))]
     #[serde(default, deserialize_with = "super::to_lowercase")]
     pub email: Option<String>,
-    #[validate(length(min = 8, max = 128))]
     #[serde(default)]
     pub password: String,
     #[validate(url)]
@@ -50,7 +49,6 @@ pub struct PatchReqDto {
     #[validate(email, length(max = 320))]
     #[serde(default, deserialize_with = "super::to_lowercase")]
     pub email: Option<String>,
-    #[validate(length(min = 8, max = 128))]
     #[serde(default)]
     pub password: Option<String>,
     #[validate(url)]
@@ -60,6 +58,22 @@ pub struct PatchReqDto {
     pub github_id: Option<i64>,
 }
 
+impl PatchReqDto {
+    /// Names the fields being changed, without their values.
+    pub fn changed_fields(&self) -> Vec<&'static str> {
+        [
+            ("username", self.username.is_some()),
+            ("email", self.email.is_some()),
+            ("password", self.password.is_some()),
+            ("avatar_url", self.avatar_url.is_some()),
+            ("github_id", self.github_id.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(field, changed)| changed.then_some(field))
+        .collect()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GetAllUsersQueryDto {
     #[serde(default)]
@@ -80,3 +94,9 @@ impl From<Vec<User>> for GetAllUsersResDto {
         }
     }
 }
+
+#[derive(Debug, Deserialize)]
+pub struct AcceptInviteReqDto {
+    pub token: String,
+    pub password: String,
+}
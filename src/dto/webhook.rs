@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::models::{WebhookDelivery, WebhookSubscription};
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct WebhookSubscriptionReqDto {
+    #[validate(url)]
+    pub url: String,
+    /// Event types to deliver; every type when empty.
+    #[serde(default)]
+    pub event_types: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Validate)]
+pub struct PatchWebhookSubscriptionReqDto {
+    #[validate(url)]
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub event_types: Option<Vec<String>>,
+    #[serde(default)]
+    pub is_active: Option<bool>,
+}
+
+/// The created subscription, whose signing secret is only ever returned here.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookSubscriptionResDto {
+    pub secret: String,
+    #[serde(flatten)]
+    pub subscription: WebhookSubscription,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetAllWebhookSubscriptionsResDto {
+    pub subscriptions: Vec<WebhookSubscription>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetAllWebhookDeliveriesQueryDto {
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetAllWebhookDeliveriesResDto {
+    pub deliveries: Vec<WebhookDelivery>,
+}
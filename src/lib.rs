@@ -3,11 +3,13 @@
 //----------------------------------------------------------------------
 
 pub mod bootstrap;
+pub mod cli;
 pub mod controllers;
 pub mod dto;
 pub mod middlewares;
 pub mod models;
 pub mod repositories;
 pub mod services;
+pub mod stores;
 pub mod token;
 pub mod utils;
@@ -1,10 +1,12 @@
 use auth::{
-    bootstrap::run_application,
-    utils::{AppResult, CONFIG},
+    cli::{run, Cli},
+    utils::{AppConfig, AppResult},
 };
+use clap::Parser;
 
 #[tokio::main]
 async fn main() -> AppResult<()> {
     dotenv::dotenv().ok();
-    run_application(CONFIG.to_owned()).await
+    let cli = Cli::parse();
+    run(cli, AppConfig::new()?).await
 }
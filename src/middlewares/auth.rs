@@ -1,10 +1,13 @@
 #![deny(missing_docs)]
 //! This module provides middleware extractors for handling JWT authorization,
 //! ensuring requests contain valid access or refresh tokens where needed.
+//! Personal API keys are accepted wherever access tokens are.
 
 use crate::{
     bootstrap::AppState,
-    token::{Claims, TokenManager},
+    models::{ApiKey, Scope},
+    services,
+    token::{Claims, Grant, TokenManager, Typ},
     utils::AppError,
 };
 use axum::{
@@ -18,9 +21,14 @@ use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
 };
+use chrono::Duration;
+
+/// Header carrying an API key, as an alternative to `Authorization: Bearer ak_...`.
+const API_KEY_HEADER: &str = "x-api-key";
 
 /// Middleware extractor that validates the `Authorization: Bearer` header for access tokens.
 ///
+/// An API key may be given instead, either as the bearer token or in the `X-API-Key` header.
 /// If the token is invalid or missing, it returns an `AppError` with a `UNAUTHORIZED` status.
 #[async_trait]
 impl FromRequestParts<AppState> for Claims {
@@ -30,21 +38,85 @@ impl FromRequestParts<AppState> for Claims {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
+        // Already authenticated by a route layer such as `RequireScopeLayer`
+        if let Some(claims) = parts.extensions.get::<Claims>() {
+            return Ok(claims.clone());
+        }
+
+        if let Some(header) = parts.headers.get(API_KEY_HEADER) {
+            let key = header
+                .to_str()
+                .map_err(|_| AppError::new(StatusCode::UNAUTHORIZED, "Invalid API key"))?;
+            return claims_from_api_key(state, key).await;
+        }
+
         let TypedHeader(Authorization(bearer)) = parts
             .extract::<TypedHeader<Authorization<Bearer>>>()
             .await
             .map_err(|e| AppError::new(StatusCode::UNAUTHORIZED, format!("{}", e)))?;
 
+        if bearer.token().starts_with(ApiKey::PREFIX) {
+            return claims_from_api_key(state, bearer.token()).await;
+        }
+
         // Configure the TokenManager
         let token_manager =
             TokenManager::new(state.get_config().get_jwt().get_secret().as_bytes(), None);
 
-        token_manager
+        let claims = token_manager
             .validate_access_token(bearer.token())
-            .map_err(|e| AppError::new(StatusCode::UNAUTHORIZED, format!("{}", e)))
+            .map_err(|e| AppError::new(StatusCode::UNAUTHORIZED, format!("{}", e)))?;
+
+        // Impersonation tokens stop working as soon as the impersonation is stopped
+        if let Some(act) = claims.get_act() {
+            let active =
+                services::get_impersonation_by_id(state.require_db_pool()?, act.impersonation_id)
+                    .await?
+                    .is_some_and(|impersonation| impersonation.is_active());
+            if !active {
+                return Err(AppError::new(
+                    StatusCode::UNAUTHORIZED,
+                    "Impersonation has ended",
+                ));
+            }
+        }
+
+        Ok(claims)
     }
 }
 
+/// Resolves the claims of a request authenticated with an API key.
+///
+/// The claims carry the key's scopes still held by its owner, so revoking a role also
+/// narrows the keys created while holding it.
+async fn claims_from_api_key(state: &AppState, key: &str) -> Result<Claims, AppError> {
+    let api_key = services::get_active_api_key(state.require_db_pool()?, key)
+        .await?
+        .ok_or_else(|| AppError::new(StatusCode::UNAUTHORIZED, "Invalid API key"))?;
+    let user = state
+        .get_user_store()
+        .get_user_by_id(api_key.user_id)
+        .await?
+        .ok_or_else(|| AppError::new(StatusCode::UNAUTHORIZED, "Invalid API key"))?;
+    let permissions =
+        services::resolve_api_key_permissions(state.require_db_pool()?, &api_key).await?;
+
+    let duration = Duration::seconds(
+        *state
+            .get_config()
+            .get_jwt()
+            .get_access_token_expiration_secs(),
+    );
+    // Keys are limited by their own scopes, i.e. permissions, rather than OAuth scopes
+    let grant = Grant {
+        permissions,
+        org_id: api_key.org_id,
+        scope: Scope::all(),
+        act: None,
+    };
+    Ok(Claims::new(user.id, user.email, grant, duration, Typ::Access).with_api_key(api_key.id))
+}
+
 /// A wrapper type to signal that the contained `Claims` come from a refresh token.
 pub struct RefreshClaims(pub Claims);
 
@@ -83,17 +155,36 @@ impl FromRequestParts<AppState> for RefreshClaims {
 /// Extracts the `refresh_token` from the user's cookie jar.
 ///
 /// Returns `Some(token)` if present, otherwise `None`.
-fn get_session_token(jar: &PrivateCookieJar) -> Option<String> {
+pub fn get_session_token(jar: &PrivateCookieJar) -> Option<String> {
     jar.get("refresh_token")
         .map(|cookie| cookie.value().to_owned())
 }
 
-/// Checks if the user has admin privileges.
-pub fn check_admin(claims: &Claims) -> Result<(), AppError> {
-    if !claims.get_is_admin() {
+/// Checks if the claims grant `permission`, e.g. `require_permission(&claims, Permission::USERS_DELETE)`.
+pub fn require_permission(claims: &Claims, permission: &str) -> Result<(), AppError> {
+    if !claims.has_permission(permission) {
+        Err(AppError::new(
+            StatusCode::FORBIDDEN,
+            format!("Access denied: {} permission required", permission),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Rejects requests not authenticated by the user's own login, i.e. with an API key or an
+/// impersonation token, for privileged actions such as managing API keys, changing the
+/// password or deleting the account.
+pub fn require_own_login(claims: &Claims) -> Result<(), AppError> {
+    if claims.get_api_key_id().is_some() {
+        Err(AppError::new(
+            StatusCode::FORBIDDEN,
+            "Access denied: not allowed with an API key",
+        ))
+    } else if claims.get_act().is_some() {
         Err(AppError::new(
             StatusCode::FORBIDDEN,
-            "Access denied: admin privileges required",
+            "Access denied: not allowed while impersonating",
         ))
     } else {
         Ok(())
@@ -0,0 +1,61 @@
+#![deny(missing_docs)]
+//! This module provides an extractor describing where a request comes from, for the audit log.
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts},
+};
+
+use crate::bootstrap::AppState;
+
+/// The client IP address and user agent of a request.
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    /// The client IP address, if known.
+    pub ip: Option<String>,
+    /// The `User-Agent` header, if any.
+    pub user_agent: Option<String>,
+}
+
+/// Extracts the `RequestContext`; it never rejects a request.
+///
+/// The IP address comes from the first `X-Forwarded-For` entry when the server is configured
+/// to trust it, and from the peer address otherwise.
+#[async_trait]
+impl FromRequestParts<AppState> for RequestContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let forwarded_for = if *state.get_config().get_server().get_trust_forwarded_for() {
+            parts
+                .headers
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(',').next())
+                .map(|ip| ip.trim().to_string())
+                .filter(|ip| !ip.is_empty())
+        } else {
+            None
+        };
+        let ip = forwarded_for.or_else(|| {
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string())
+        });
+
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        Ok(Self { ip, user_agent })
+    }
+}
@@ -0,0 +1,80 @@
+#![deny(missing_docs)]
+//! This module provides a layer recording the count and latency of HTTP requests.
+
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    response::Response,
+};
+use futures_util::future::BoxFuture;
+use tower::{Layer, Service};
+
+use crate::utils::record_request;
+
+/// Label of requests that matched no route, so that unknown paths do not add series.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Layer recording every request by method, route template and status, e.g.
+/// `router.layer(RequestMetricsLayer)`.
+///
+/// Added last, it also records the requests rejected by the other layers, such as the rate
+/// limit.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestMetricsLayer;
+
+impl<S> Layer<S> for RequestMetricsLayer {
+    type Service = RequestMetrics<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestMetrics { inner }
+    }
+}
+
+/// Service created by [`RequestMetricsLayer`].
+#[derive(Debug, Clone)]
+pub struct RequestMetrics<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for RequestMetrics<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // The service polled ready is the one to call; leave a fresh clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let method = request.method().clone();
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|path| path.as_str().to_string())
+            .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+        Box::pin(async move {
+            let start = Instant::now();
+            let res = inner.call(request).await?;
+            record_request(
+                method.as_str(),
+                &route,
+                res.status().as_u16(),
+                start.elapsed(),
+            );
+            Ok(res)
+        })
+    }
+}
@@ -1 +1,6 @@
 pub mod auth;
+pub mod context;
+pub mod metrics;
+pub mod rate_limit;
+pub mod scope;
+pub mod trace;
@@ -0,0 +1,300 @@
+#![deny(missing_docs)]
+//! This module provides a layer enforcing a rate limit policy per client.
+
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{FromRequestParts, Request},
+    http::{header, request::Parts, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::CookieJar;
+use futures_util::future::BoxFuture;
+use sha2::{Digest, Sha256};
+use tower::{Layer, Service};
+
+use crate::{
+    bootstrap::AppState,
+    middlewares::context::RequestContext,
+    models::ApiKey,
+    services,
+    token::TokenManager,
+    utils::{
+        record_rate_limit_rejection, AppError, KeyStrategy, RateLimitDecision, RateLimitPolicy,
+    },
+};
+
+/// Header carrying an API key, as accepted by the `Claims` extractor.
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Largest body buffered to find an identifier, as for the `Json` extractor.
+const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// What a [`RateLimitLayer`] counts requests against.
+#[derive(Debug, Clone, Copy)]
+pub enum RateLimitKey {
+    /// The client, according to `rate_limit.key_strategy`.
+    Client,
+    /// The client IP address, whatever the key strategy.
+    Ip,
+    /// The first of these fields found in the JSON body, or else in the cookies, such as
+    /// the username a login attempt is for. Requests carrying none are not limited.
+    Identifier(&'static [&'static str]),
+}
+
+/// Layer limiting each client to the budget of `policy`, e.g.
+/// `router.layer(RateLimitLayer::new(state.clone(), RateLimitPolicy::from_config(..)))`.
+///
+/// Clients are told their budget through the `RateLimit-Limit`, `RateLimit-Remaining`,
+/// `RateLimit-Reset` and `RateLimit-Policy` headers, reporting the tightest budget when
+/// several layers apply. Requests over budget are rejected with `TOO_MANY_REQUESTS` and a
+/// `Retry-After` header.
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    state: AppState,
+    policy: Arc<RateLimitPolicy>,
+    read_policy: Option<Arc<RateLimitPolicy>>,
+    key: RateLimitKey,
+}
+
+impl RateLimitLayer {
+    /// Creates a layer enforcing `policy` on each client.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The application state holding the limiter.
+    /// * `policy` - The budget of each client.
+    pub fn new(state: AppState, policy: RateLimitPolicy) -> Self {
+        state.get_rate_limiter().register(&policy);
+        Self {
+            state,
+            policy: Arc::new(policy),
+            read_policy: None,
+            key: RateLimitKey::Client,
+        }
+    }
+
+    /// Counts requests against `key` instead of the client.
+    pub fn keyed_by(mut self, key: RateLimitKey) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Enforces `policy` instead on `GET` and `HEAD` requests.
+    pub fn reads(mut self, policy: RateLimitPolicy) -> Self {
+        self.state.get_rate_limiter().register(&policy);
+        self.read_policy = Some(Arc::new(policy));
+        self
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            state: self.state.clone(),
+            policy: self.policy.clone(),
+            read_policy: self.read_policy.clone(),
+            key: self.key,
+        }
+    }
+}
+
+/// Service created by [`RateLimitLayer`].
+#[derive(Debug, Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    state: AppState,
+    policy: Arc<RateLimitPolicy>,
+    read_policy: Option<Arc<RateLimitPolicy>>,
+    key: RateLimitKey,
+}
+
+impl<S> Service<Request> for RateLimit<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // The service polled ready is the one to call; leave a fresh clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let state = self.state.clone();
+        let policy = match (&self.read_policy, request.method()) {
+            (Some(read_policy), &Method::GET | &Method::HEAD) => read_policy.clone(),
+            _ => self.policy.clone(),
+        };
+        let key = self.key;
+
+        Box::pin(async move {
+            let (mut parts, mut body) = request.into_parts();
+            let key = match key {
+                RateLimitKey::Client => client_key(&mut parts, &state).await,
+                RateLimitKey::Ip => ip_key(&mut parts, &state).await,
+                RateLimitKey::Identifier(fields) => {
+                    let bytes = match to_bytes(body, MAX_BODY_SIZE).await {
+                        Ok(bytes) => bytes,
+                        Err(_) => {
+                            return Ok(AppError::new(
+                                StatusCode::PAYLOAD_TOO_LARGE,
+                                "Request body too large",
+                            )
+                            .into_response());
+                        }
+                    };
+                    let key = identifier_key(&parts, &bytes, fields);
+                    body = Body::from(bytes);
+                    match key {
+                        Some(key) => key,
+                        None => return inner.call(Request::from_parts(parts, body)).await,
+                    }
+                }
+            };
+            let decision = state.get_rate_limiter().check(&policy, &key).await;
+
+            if !decision.allowed {
+                record_rate_limit_rejection(&policy.name);
+                let mut res = AppError::new(StatusCode::TOO_MANY_REQUESTS, "Too many requests")
+                    .into_response();
+                set_headers(res.headers_mut(), &decision);
+                res.headers_mut()
+                    .insert(header::RETRY_AFTER, HeaderValue::from(decision.reset_secs));
+                return Ok(res);
+            }
+
+            let mut res = inner.call(Request::from_parts(parts, body)).await?;
+            set_headers(res.headers_mut(), &decision);
+            Ok(res)
+        })
+    }
+}
+
+/// Identifies the client of a request according to `rate_limit.key_strategy`.
+///
+/// Tokens and API keys only identify a client once they are found valid, otherwise a client
+/// could earn a fresh budget with each made-up token.
+async fn client_key(parts: &mut Parts, state: &AppState) -> String {
+    let strategy = *state.get_config().get_rate_limit().get_key_strategy();
+
+    if strategy != KeyStrategy::Ip {
+        let api_key = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| bearer_token(parts).filter(|token| token.starts_with(ApiKey::PREFIX)));
+
+        if let Some(key) = api_key {
+            let found = match state.require_db_pool() {
+                Ok(db_pool) => services::get_active_api_key(db_pool, key)
+                    .await
+                    .ok()
+                    .flatten(),
+                Err(_) => None,
+            };
+            if found.is_some() {
+                // Only a digest is kept, so the limiter never holds credentials
+                return format!("token:{}", digest(key));
+            }
+        } else if let Some(token) = bearer_token(parts) {
+            let token_manager =
+                TokenManager::new(state.get_config().get_jwt().get_secret().as_bytes(), None);
+            if let Ok(claims) = token_manager.validate_access_token(token) {
+                return match strategy {
+                    KeyStrategy::User => format!("user:{}", claims.get_jti()),
+                    _ => format!("token:{}", digest(token)),
+                };
+            }
+        }
+    }
+
+    ip_key(parts, state).await
+}
+
+async fn ip_key(parts: &mut Parts, state: &AppState) -> String {
+    let context = match RequestContext::from_request_parts(parts, state).await {
+        Ok(context) => context,
+        Err(infallible) => match infallible {},
+    };
+    format!("ip:{}", context.ip.as_deref().unwrap_or("unknown"))
+}
+
+/// Identifies what a request is about from the first of `fields` found in its JSON body or
+/// cookies. Only a digest is kept, as identifiers may be credentials such as refresh tokens.
+fn identifier_key(parts: &Parts, body: &[u8], fields: &[&str]) -> Option<String> {
+    let json: Option<serde_json::Value> = serde_json::from_slice(body).ok();
+    let from_body = fields.iter().find_map(|field| {
+        let value = json.as_ref()?.get(field)?.as_str()?;
+        normalize_identifier(field, value)
+    });
+    let identifier = from_body.or_else(|| {
+        let cookies = CookieJar::from_headers(&parts.headers);
+        fields.iter().find_map(|field| {
+            let cookie = cookies.get(field)?;
+            normalize_identifier(field, cookie.value())
+        })
+    })?;
+    Some(format!("identifier:{}", digest(&identifier)))
+}
+
+/// Trims and lowercases usernames and emails, so that variants of one account share a
+/// budget, and keeps other identifiers such as tokens exactly as given.
+fn normalize_identifier(field: &str, value: &str) -> Option<String> {
+    let value = match field {
+        "username" | "email" => value.trim().to_lowercase(),
+        _ => value.to_string(),
+    };
+    Some(value).filter(|value| !value.is_empty())
+}
+
+fn digest(value: &str) -> String {
+    Sha256::digest(value.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn bearer_token(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &RateLimitDecision) {
+    // An inner layer may already have reported a tighter budget
+    let reported = headers
+        .get("ratelimit-remaining")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if reported.is_some_and(|remaining| remaining <= decision.remaining) {
+        return;
+    }
+
+    let values = [
+        ("ratelimit-limit", HeaderValue::from(decision.limit)),
+        ("ratelimit-remaining", HeaderValue::from(decision.remaining)),
+        ("ratelimit-reset", HeaderValue::from(decision.reset_secs)),
+    ];
+    for (name, value) in values {
+        headers.insert(HeaderName::from_static(name), value);
+    }
+    if let Ok(policy) = HeaderValue::from_str(&decision.policy) {
+        headers.insert(HeaderName::from_static("ratelimit-policy"), policy);
+    }
+}
@@ -0,0 +1,65 @@
+#![deny(missing_docs)]
+//! This module provides the spans of the `TraceLayer`, continuing the traces of callers.
+
+use std::time::Duration;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::HeaderMap,
+    response::Response,
+};
+use opentelemetry::{global, propagation::Extractor};
+use tracing::{field::Empty, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Reads the W3C trace context, such as `traceparent`, from request headers.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// Creates the span of a request, as a child of the trace given by its `traceparent` header
+/// when there is one, e.g. `TraceLayer::new_for_http().make_span_with(make_request_span)`.
+///
+/// The span is named after the route template rather than the path, so that requests to
+/// `/users/:id` are grouped together whatever the ID. Requests matching no route share the
+/// name `unmatched`, as their paths are chosen by clients.
+pub fn make_request_span(request: &Request) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str());
+    let name = match route {
+        Some(route) => format!("{} {}", request.method(), route),
+        None => "unmatched".to_string(),
+    };
+    let span = tracing::info_span!(
+        "request",
+        otel.name = name,
+        otel.kind = "server",
+        http.request.method = %request.method(),
+        http.route = route,
+        url.path = request.uri().path(),
+        http.response.status_code = Empty,
+    );
+
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    // Fails only when no OpenTelemetry layer is installed, in which case there is no trace
+    let _ = span.set_parent(parent);
+    span
+}
+
+/// Records the status of a response on the span of its request, e.g.
+/// `TraceLayer::new_for_http().on_response(record_response)`.
+pub fn record_response(response: &Response, _latency: Duration, span: &Span) {
+    span.record("http.response.status_code", response.status().as_u16());
+}
@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Represents a long-lived personal API key.
+///
+/// Only a hash of the key is stored; the key itself is shown once when it is created.
+/// A key never grants more than its owner currently holds: its `scopes` are intersected
+/// with the owner's permissions on every request.
+///
+/// ## Fields
+/// - `id` - A unique identifier for the key.
+/// - `user_id` - The unique ID of the owner.
+/// - `org_id` - The organization the key acts in, if any.
+/// - `name` - A label chosen by the owner.
+/// - `prefix` - The first characters of the key, to tell keys apart.
+/// - `key_hash` - The SHA-256 hash of the key.
+/// - `scopes` - The permissions the key may use.
+/// - `expires_at` - Optional timestamp when the key expires.
+/// - `is_revoked` - Indicates whether the key has been revoked.
+/// - `created_at` - Timestamp when the key was created.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub org_id: Option<Uuid>,
+    pub name: String,
+    pub prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub is_revoked: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl ApiKey {
+    /// Prefix marking API keys, so they can be told apart from JWTs.
+    pub const PREFIX: &'static str = "ak_";
+
+    /// Creates a new `ApiKey` instance with default values for `id` and `created_at`.
+    ///
+    /// ## Parameters
+    /// - `user_id` - The unique ID of the owner.
+    /// - `org_id` - The organization the key acts in, if any.
+    /// - `name` - A label chosen by the owner.
+    /// - `prefix` - The first characters of the key.
+    /// - `key_hash` - The SHA-256 hash of the key.
+    /// - `scopes` - The permissions the key may use.
+    /// - `expires_at` - Optional timestamp when the key expires.
+    ///
+    /// ## Returns
+    /// A new `ApiKey` instance.
+    pub fn new(
+        user_id: Uuid,
+        org_id: Option<Uuid>,
+        name: impl Into<String>,
+        prefix: impl Into<String>,
+        key_hash: impl Into<String>,
+        scopes: Vec<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            org_id,
+            name: name.into(),
+            prefix: prefix.into(),
+            key_hash: key_hash.into(),
+            scopes,
+            expires_at,
+            is_revoked: false,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Checks if the key has expired.
+    ///
+    /// ## Returns
+    /// - `true` if the key has an expiry and the current timestamp is past it.
+    /// - `false` otherwise.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at < Utc::now())
+    }
+}
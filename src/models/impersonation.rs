@@ -0,0 +1,68 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Records an admin logging in as a user, from start to stop.
+///
+/// ## Fields
+/// - `id` - A unique identifier for the impersonation.
+/// - `actor_id` - The unique ID of the admin impersonating the user.
+/// - `user_id` - The unique ID of the impersonated user.
+/// - `reason` - Optional justification given by the admin.
+/// - `started_at` - Timestamp when the impersonation started.
+/// - `expires_at` - Timestamp when the impersonation token expires.
+/// - `ended_at` - Timestamp when the impersonation was stopped, if it was.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Impersonation {
+    pub id: Uuid,
+    pub actor_id: Uuid,
+    pub user_id: Uuid,
+    pub reason: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl Impersonation {
+    /// Creates a new `Impersonation` starting now and lasting `duration`.
+    ///
+    /// ## Parameters
+    /// - `actor_id` - The unique ID of the admin.
+    /// - `user_id` - The unique ID of the impersonated user.
+    /// - `reason` - Optional justification given by the admin.
+    /// - `duration` - A `chrono::Duration` indicating the impersonation's lifespan.
+    ///
+    /// ## Returns
+    /// A new `Impersonation` instance.
+    pub fn new(actor_id: Uuid, user_id: Uuid, reason: Option<String>, duration: Duration) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            actor_id,
+            user_id,
+            reason,
+            started_at: now,
+            expires_at: now + duration,
+            ended_at: None,
+        }
+    }
+
+    /// Checks if the impersonation is still running.
+    ///
+    /// ## Returns
+    /// - `true` if it has neither been stopped nor expired.
+    /// - `false` otherwise.
+    pub fn is_active(&self) -> bool {
+        self.ended_at.is_none() && self.expires_at > Utc::now()
+    }
+}
@@ -2,12 +2,34 @@
 // Modules
 //----------------------------------------------------------------------
 
+mod api_key;
+mod audit_event;
+mod impersonation;
+mod organization;
+mod outbox_event;
+mod password_history;
+mod permission;
+mod role;
+mod scope;
 mod session;
 mod user;
+mod user_invite;
+mod webhook;
 
 //----------------------------------------------------------------------
 // Exports
 //----------------------------------------------------------------------
 
+pub use api_key::*;
+pub use audit_event::*;
+pub use impersonation::*;
+pub use organization::*;
+pub use outbox_event::*;
+pub use password_history::*;
+pub use permission::*;
+pub use role::*;
+pub use scope::*;
 pub use session::*;
 pub use user::*;
+pub use user_invite::*;
+pub use webhook::*;
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Represents a tenant organization hosted on the deployment.
+///
+/// ## Fields
+/// - `id` - A unique identifier for the organization.
+/// - `name` - The display name of the organization.
+/// - `slug` - The unique, URL-safe handle used to select the organization at login.
+/// - `created_at` - Timestamp when the organization was created.
+/// - `updated_at` - Timestamp of the last update.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Organization {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Represents a user's membership in an organization.
+///
+/// ## Fields
+/// - `org_id` - The unique ID of the organization.
+/// - `user_id` - The unique ID of the member.
+/// - `role_id` - Optional role granting the member permissions within the organization.
+/// - `created_at` - Timestamp when the membership was created.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Membership {
+    pub org_id: Uuid,
+    pub user_id: Uuid,
+    pub role_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl Organization {
+    /// Creates a new `Organization` instance with default values for `id` and timestamps.
+    ///
+    /// ## Parameters
+    /// - `name` - The display name of the organization.
+    /// - `slug` - The unique handle of the organization.
+    ///
+    /// ## Returns
+    /// A new `Organization` instance.
+    pub fn new(name: impl Into<String>, slug: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            slug: slug.into(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+impl Membership {
+    /// Creates a new `Membership` timestamped now.
+    ///
+    /// ## Parameters
+    /// - `org_id` - The unique ID of the organization.
+    /// - `user_id` - The unique ID of the member.
+    /// - `role_id` - Optional role of the member within the organization.
+    ///
+    /// ## Returns
+    /// A new `Membership` instance.
+    pub fn new(org_id: Uuid, user_id: Uuid, role_id: Option<Uuid>) -> Self {
+        Self {
+            org_id,
+            user_id,
+            role_id,
+            created_at: Utc::now(),
+        }
+    }
+}
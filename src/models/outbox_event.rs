@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Records an identity event for downstream services, in the same transaction as the change it
+/// describes, until the dispatcher fans it out to the webhook subscriptions.
+///
+/// ## Fields
+/// - `id` - A unique identifier for the event, sent along with every delivery.
+/// - `event_type` - What happened, such as `user.created`.
+/// - `payload` - The event data sent to subscribers.
+/// - `created_at` - Timestamp when the event happened.
+/// - `dispatched_at` - Timestamp when deliveries were queued for the event, if they were.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxEvent {
+    pub id: Uuid,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub dispatched_at: Option<DateTime<Utc>>,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl OutboxEvent {
+    /// A user registered or was created by an admin, an import or the bootstrap.
+    pub const USER_CREATED: &'static str = "user.created";
+    /// A user's email address changed.
+    pub const USER_EMAIL_CHANGED: &'static str = "user.email_changed";
+    /// A user was deleted.
+    pub const USER_DELETED: &'static str = "user.deleted";
+    /// Every event type, which subscriptions may filter on.
+    pub const ALL: &'static [&'static str] = &[
+        Self::USER_CREATED,
+        Self::USER_EMAIL_CHANGED,
+        Self::USER_DELETED,
+    ];
+
+    /// Creates a new, undispatched `OutboxEvent` of `event_type` happening now.
+    ///
+    /// ## Parameters
+    /// - `event_type` - What happened, one of the associated constants.
+    /// - `payload` - The event data sent to subscribers.
+    ///
+    /// ## Returns
+    /// A new `OutboxEvent` instance.
+    pub fn new(event_type: impl Into<String>, payload: serde_json::Value) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            event_type: event_type.into(),
+            payload,
+            created_at: Utc::now(),
+            dispatched_at: None,
+        }
+    }
+}
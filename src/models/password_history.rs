@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Represents a previously used password of a user.
+///
+/// Entries are kept so that a new password can be compared against the most
+/// recent ones and reuse can be refused.
+///
+/// ## Fields
+/// - `id` - A unique identifier for the entry.
+/// - `user_id` - The unique ID of the user the password belonged to.
+/// - `password_hash` - The hash of the password.
+/// - `created_at` - Timestamp when the password was set.
+#[derive(Debug, Clone, FromRow)]
+pub struct PasswordHistory {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub password_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl PasswordHistory {
+    /// Creates a new `PasswordHistory` entry timestamped now.
+    ///
+    /// ## Parameters
+    /// - `user_id` - The unique ID of the user the password belongs to.
+    /// - `password_hash` - The hash of the password.
+    ///
+    /// ## Returns
+    /// A new `PasswordHistory` instance.
+    pub fn new(user_id: Uuid, password_hash: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            password_hash: password_hash.into(),
+            created_at: Utc::now(),
+        }
+    }
+}
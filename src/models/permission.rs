@@ -0,0 +1,99 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Represents a single grantable action, such as `users:delete`.
+///
+/// Permissions are seeded by migrations; roles group them and users receive them
+/// through their roles.
+///
+/// ## Fields
+/// - `id` - A unique identifier for the permission.
+/// - `name` - The unique `resource:action` name checked by route guards.
+/// - `description` - Optional human-readable description.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Permission {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl Permission {
+    /// List users and view any user.
+    pub const USERS_READ: &'static str = "users:read";
+    /// Update any user.
+    pub const USERS_UPDATE: &'static str = "users:update";
+    /// Delete any user.
+    pub const USERS_DELETE: &'static str = "users:delete";
+    /// Bulk import users.
+    pub const USERS_IMPORT: &'static str = "users:import";
+    /// Bulk export users.
+    pub const USERS_EXPORT: &'static str = "users:export";
+    /// Log in as a user holding no permission the impersonator lacks.
+    pub const USERS_IMPERSONATE: &'static str = "users:impersonate";
+    /// Revoke the sessions of any user.
+    pub const SESSIONS_REVOKE: &'static str = "sessions:revoke";
+    /// List roles and permissions.
+    pub const ROLES_READ: &'static str = "roles:read";
+    /// Create, update and delete roles.
+    pub const ROLES_MANAGE: &'static str = "roles:manage";
+    /// Grant and revoke the roles of any user.
+    pub const ROLES_ASSIGN: &'static str = "roles:assign";
+    /// List organizations and their members.
+    pub const ORGS_READ: &'static str = "orgs:read";
+    /// Create and delete organizations and manage any membership.
+    pub const ORGS_MANAGE: &'static str = "orgs:manage";
+    /// Manage the memberships of the current organization.
+    pub const MEMBERS_MANAGE: &'static str = "members:manage";
+    /// List the audit log.
+    pub const AUDIT_READ: &'static str = "audit:read";
+    /// Manage webhook subscriptions and retry dead deliveries.
+    pub const WEBHOOKS_MANAGE: &'static str = "webhooks:manage";
+    /// View the usage of every rate limit policy.
+    pub const RATE_LIMITS_READ: &'static str = "rate_limits:read";
+    /// Scrape the metrics served alongside the API.
+    pub const METRICS_READ: &'static str = "metrics:read";
+
+    /// Every permission seeded by the migrations, all of which the admin role is granted.
+    pub const ALL: &'static [&'static str] = &[
+        Self::USERS_READ,
+        Self::USERS_UPDATE,
+        Self::USERS_DELETE,
+        Self::USERS_IMPORT,
+        Self::USERS_EXPORT,
+        Self::USERS_IMPERSONATE,
+        Self::SESSIONS_REVOKE,
+        Self::ROLES_READ,
+        Self::ROLES_MANAGE,
+        Self::ROLES_ASSIGN,
+        Self::ORGS_READ,
+        Self::ORGS_MANAGE,
+        Self::MEMBERS_MANAGE,
+        Self::AUDIT_READ,
+        Self::WEBHOOKS_MANAGE,
+        Self::RATE_LIMITS_READ,
+        Self::METRICS_READ,
+    ];
+
+    /// The permissions that a membership role can grant inside an organization. Anything
+    /// else, such as managing roles, only applies to tokens issued outside an organization.
+    pub const ORG_SCOPED: &'static [&'static str] = &[
+        Self::USERS_READ,
+        Self::USERS_UPDATE,
+        Self::USERS_DELETE,
+        Self::USERS_IMPORT,
+        Self::USERS_EXPORT,
+        Self::SESSIONS_REVOKE,
+        Self::MEMBERS_MANAGE,
+    ];
+}
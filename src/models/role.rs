@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Represents a named set of permissions that can be assigned to users.
+///
+/// ## Fields
+/// - `id` - A unique identifier for the role.
+/// - `name` - The unique name of the role.
+/// - `description` - Optional human-readable description.
+/// - `created_at` - Timestamp when the role was created.
+/// - `updated_at` - Timestamp of the last update.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Role {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl Role {
+    /// The built-in role granted every permission.
+    pub const ADMIN: &'static str = "admin";
+    /// The built-in role managing the users of an organization, granted through a membership.
+    pub const ORG_ADMIN: &'static str = "org_admin";
+
+    /// Creates a new `Role` instance with default values for `id` and timestamps.
+    ///
+    /// ## Parameters
+    /// - `name` - The unique name of the role.
+    /// - `description` - Optional human-readable description.
+    ///
+    /// ## Returns
+    /// A new `Role` instance.
+    pub fn new(name: impl Into<String>, description: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            description,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Checks if this is the built-in admin role.
+    pub fn is_admin(&self) -> bool {
+        self.name == Self::ADMIN
+    }
+}
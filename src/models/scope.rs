@@ -0,0 +1,45 @@
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// The OAuth-style scopes a token can be issued with.
+///
+/// Scopes limit the routes a token can reach, independently of the permissions the user
+/// holds: a token for a third-party integration may be issued with `profile:read` only, so
+/// it can read the user's profile but neither change nor delete the account.
+pub struct Scope;
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl Scope {
+    /// Read the logged-in user's profile.
+    pub const PROFILE_READ: &'static str = "profile:read";
+    /// Update the logged-in user's profile, including their password.
+    pub const PROFILE_WRITE: &'static str = "profile:write";
+    /// Delete the logged-in user's account.
+    pub const ACCOUNT_DELETE: &'static str = "account:delete";
+    /// Create, list and revoke the logged-in user's API keys.
+    pub const API_KEYS: &'static str = "api_keys";
+    /// Use the admin endpoints the user's permissions allow.
+    pub const ADMIN: &'static str = "admin";
+
+    /// Every scope, granted when a login does not ask for a subset.
+    pub const ALL: &'static [&'static str] = &[
+        Self::PROFILE_READ,
+        Self::PROFILE_WRITE,
+        Self::ACCOUNT_DELETE,
+        Self::API_KEYS,
+        Self::ADMIN,
+    ];
+
+    /// The scopes of impersonation tokens, which cannot change or delete the account, manage
+    /// API keys or reach admin endpoints.
+    pub const IMPERSONATION: &'static [&'static str] = &[Self::PROFILE_READ];
+
+    /// Returns every scope as owned strings.
+    pub fn all() -> Vec<String> {
+        Self::ALL.iter().map(|scope| scope.to_string()).collect()
+    }
+}
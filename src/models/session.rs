@@ -18,19 +18,24 @@ use uuid::Uuid;
 /// - `user_id` - The unique ID of the user associated with this session.
 /// - `refresh_token` - A token used to refresh the session.
 /// - `is_revoked` - Indicates whether the session has been revoked.
+/// - `is_persistent` - Indicates whether the session was opened with "remember me", so that
+///   its cookie outlives the browser session.
 /// - `expires_at` - Timestamp when the session expires.
 /// - `created_at` - Timestamp when the session was created.
 /// - `updated_at` - Timestamp of the last update.
-#[derive(Debug, Serialize, FromRow)]
+/// - `last_used_at` - Timestamp of the last login or refresh through the session.
+#[derive(Debug, Clone, Serialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct Session {
     pub id: Uuid,
     pub user_id: Uuid,
     pub refresh_token: String,
     pub is_revoked: bool,
+    pub is_persistent: bool,
     pub expires_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
 }
 
 //----------------------------------------------------------------------
@@ -48,17 +53,32 @@ impl Session {
     /// ## Returns
     /// A new `Session` instance.
     pub fn new(user_id: Uuid, refresh_token: impl Into<String>, duration: Duration) -> Self {
+        let now = Utc::now();
         Self {
             id: Uuid::new_v4(),
             user_id,
             refresh_token: refresh_token.into(),
             is_revoked: false,
-            expires_at: Utc::now() + duration,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+            is_persistent: false,
+            expires_at: now + duration,
+            created_at: now,
+            updated_at: now,
+            last_used_at: now,
         }
     }
 
+    /// Sets the class of the session.
+    ///
+    /// ## Parameters
+    /// - `is_persistent` - Whether the session was opened with "remember me".
+    ///
+    /// ## Returns
+    /// The updated `Session` instance.
+    pub fn with_persistent(mut self, is_persistent: bool) -> Self {
+        self.is_persistent = is_persistent;
+        self
+    }
+
     /// Checks if the session has expired.
     ///
     /// ## Returns
@@ -67,6 +87,35 @@ impl Session {
     pub fn is_expired(&self) -> bool {
         self.expires_at < Utc::now()
     }
+
+    /// Checks if the session went unused for longer than `idle_timeout`.
+    ///
+    /// ## Parameters
+    /// - `idle_timeout` - How long a session may go unused; a zero timeout never elapses.
+    ///
+    /// ## Returns
+    /// - `true` if the session was last used more than `idle_timeout` ago.
+    /// - `false` otherwise.
+    pub fn is_idle(&self, idle_timeout: Duration) -> bool {
+        idle_timeout > Duration::zero() && self.last_used_at + idle_timeout < Utc::now()
+    }
+
+    /// Records a use of the session, optionally sliding its expiry.
+    ///
+    /// ## Parameters
+    /// - `sliding` - When given, the session is extended to expire after this duration from
+    ///   now, but never shortened by it.
+    /// - `max_lifetime` - The longest the session may live from its creation, however active;
+    ///   a session set to expire later is cut short.
+    pub fn touch(&mut self, sliding: Option<Duration>, max_lifetime: Duration) {
+        let now = Utc::now();
+        self.last_used_at = now;
+        self.updated_at = now;
+        if let Some(duration) = sliding {
+            self.expires_at = self.expires_at.max(now + duration);
+        }
+        self.expires_at = self.expires_at.min(self.created_at + max_lifetime);
+    }
 }
 
 impl fmt::Display for Session {
@@ -78,21 +127,25 @@ impl fmt::Display for Session {
     ///   id: "550e8400-e29b-41d4-a716-446655440000",
     ///   user_id: "123e4567-e89b-12d3-a456-426614174000",
     ///   is_revoked: false,
+    ///   is_persistent: false,
     ///   expires_at: "2024-01-01T12:00:00Z",
     ///   created_at: "2024-01-01T11:00:00Z",
-    ///   updated_at: "2024-01-01T11:00:00Z"
+    ///   updated_at: "2024-01-01T11:00:00Z",
+    ///   last_used_at: "2024-01-01T11:00:00Z"
     /// }
     /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Session: {{ id: {}, user_id: {}, is_revoked: {}, expires_at: {}, created_at: {}, updated_at: {} }}",
+            "Session: {{ id: {}, user_id: {}, is_revoked: {}, is_persistent: {}, expires_at: {}, created_at: {}, updated_at: {}, last_used_at: {} }}",
             self.id,
             self.user_id,
             self.is_revoked,
+            self.is_persistent,
             self.expires_at,
             self.created_at,
-            self.updated_at
+            self.updated_at,
+            self.last_used_at
         )
     }
 }
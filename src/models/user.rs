@@ -21,10 +21,11 @@ use uuid::Uuid;
 /// - `email` - The user's email address.
 /// - `password_hash` - The hashed password (not serialized for security).
 /// - `avatar_url` - Optional URL of the user's avatar image.
-/// - `is_admin` - Boolean flag indicating if the user has admin privileges.
+/// - `is_admin` - Boolean flag indicating if the user holds the admin role.
+/// - `org_id` - Optional organization the user was created in, which scopes their username and email.
 /// - `created_at` - Timestamp of user creation.
 /// - `updated_at` - Timestamp of the last update.
-#[derive(Debug, Serialize, FromRow)]
+#[derive(Debug, Clone, Serialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     pub id: Uuid,
@@ -37,6 +38,8 @@ pub struct User {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub avatar_url: Option<String>,
     pub is_admin: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -68,10 +71,23 @@ impl User {
             password_hash: password_hash.into(),
             avatar_url,
             is_admin: false,
+            org_id: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
     }
+
+    /// Places the user in an organization.
+    ///
+    /// ## Parameters
+    /// - `org_id` - The organization the user belongs to, if any.
+    ///
+    /// ## Returns
+    /// The updated `User` instance.
+    pub fn with_org(mut self, org_id: Option<Uuid>) -> Self {
+        self.org_id = org_id;
+        self
+    }
 }
 
 //----------------------------------------------------------------------
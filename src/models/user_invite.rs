@@ -0,0 +1,61 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Represents a pending invitation for an imported user to choose a password.
+///
+/// Only a hash of the invite token is stored; the token itself is handed out once
+/// in the import report.
+///
+/// ## Fields
+/// - `id` - A unique identifier for the invite.
+/// - `user_id` - The unique ID of the invited user.
+/// - `token_hash` - The SHA-256 hash of the invite token.
+/// - `expires_at` - Timestamp when the invite expires.
+/// - `created_at` - Timestamp when the invite was created.
+#[derive(Debug, FromRow)]
+pub struct UserInvite {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl UserInvite {
+    /// Creates a new `UserInvite` valid for `duration` from now.
+    ///
+    /// ## Parameters
+    /// - `user_id` - The unique ID of the invited user.
+    /// - `token_hash` - The SHA-256 hash of the invite token.
+    /// - `duration` - A `chrono::Duration` indicating the invite's lifespan.
+    ///
+    /// ## Returns
+    /// A new `UserInvite` instance.
+    pub fn new(user_id: Uuid, token_hash: impl Into<String>, duration: Duration) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            token_hash: token_hash.into(),
+            expires_at: Utc::now() + duration,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Checks if the invite has expired.
+    ///
+    /// ## Returns
+    /// - `true` if the current timestamp is past the `expires_at` timestamp.
+    /// - `false` otherwise.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Utc::now()
+    }
+}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Represents an endpoint receiving identity events.
+///
+/// ## Fields
+/// - `id` - A unique identifier for the subscription.
+/// - `url` - The endpoint the events are posted to.
+/// - `secret` - The key signing the deliveries, shown once when the subscription is created.
+/// - `event_types` - The event types delivered; every type when empty.
+/// - `is_active` - Indicates whether events are delivered to the endpoint.
+/// - `created_at` - Timestamp when the subscription was created.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookSubscription {
+    pub id: Uuid,
+    pub url: String,
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    pub event_types: Vec<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Tracks the delivery of an outbox event to a subscription, across retries.
+///
+/// ## Fields
+/// - `id` - A unique identifier for the delivery.
+/// - `event_id` - The unique ID of the outbox event delivered.
+/// - `subscription_id` - The unique ID of the subscription delivered to.
+/// - `status` - `pending`, `delivered`, or `dead` once every attempt failed.
+/// - `attempts` - The number of attempts made so far.
+/// - `next_attempt_at` - Timestamp when the delivery is next attempted, while pending.
+/// - `last_error` - Why the last attempt failed, if it did.
+/// - `delivered_at` - Timestamp when the endpoint accepted the event, if it did.
+/// - `created_at` - Timestamp when the delivery was queued.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub subscription_id: Uuid,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl WebhookSubscription {
+    /// Prefix marking webhook signing secrets.
+    pub const SECRET_PREFIX: &'static str = "whsec_";
+
+    /// Creates a new, active `WebhookSubscription` with default values for `id` and `created_at`.
+    ///
+    /// ## Parameters
+    /// - `url` - The endpoint the events are posted to.
+    /// - `secret` - The key signing the deliveries.
+    /// - `event_types` - The event types delivered; every type when empty.
+    ///
+    /// ## Returns
+    /// A new `WebhookSubscription` instance.
+    pub fn new(
+        url: impl Into<String>,
+        secret: impl Into<String>,
+        event_types: Vec<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            url: url.into(),
+            secret: secret.into(),
+            event_types,
+            is_active: true,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+impl WebhookDelivery {
+    /// Waiting for its next attempt.
+    pub const PENDING: &'static str = "pending";
+    /// Accepted by the endpoint.
+    pub const DELIVERED: &'static str = "delivered";
+    /// Given up on after every attempt failed; listed as a dead letter.
+    pub const DEAD: &'static str = "dead";
+}
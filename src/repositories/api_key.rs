@@ -0,0 +1,107 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{models::ApiKey, utils::AppResult};
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn create_api_key(pool: &PgPool, api_key: &ApiKey) -> AppResult<ApiKey> {
+    sqlx::query_as!(
+        ApiKey,
+        r#"
+        INSERT INTO api_keys (id, user_id, org_id, name, prefix, key_hash, scopes, expires_at, is_revoked, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING *
+        "#,
+        api_key.id,
+        api_key.user_id,
+        api_key.org_id,
+        api_key.name,
+        api_key.prefix,
+        api_key.key_hash,
+        &api_key.scopes,
+        api_key.expires_at,
+        api_key.is_revoked,
+        api_key.created_at
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to create API key ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_api_key_by_key_hash(pool: &PgPool, key_hash: &str) -> AppResult<Option<ApiKey>> {
+    sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT * FROM api_keys
+        WHERE key_hash = $1
+        "#,
+        key_hash
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get API key ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_api_keys_by_user_id(pool: &PgPool, user_id: Uuid) -> AppResult<Vec<ApiKey>> {
+    sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT * FROM api_keys
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get API keys ({})", e))
+}
+
+/// Revokes the key `id` if it belongs to `user_id`, returning whether a key was revoked.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn revoke_api_key(pool: &PgPool, user_id: Uuid, id: Uuid) -> AppResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE api_keys
+        SET is_revoked = true
+        WHERE id = $1 AND user_id = $2
+        "#,
+        id,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to revoke API key ({})", e))?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Deletes up to `limit` API keys that expired before `before`, and returns how many were
+/// deleted.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_expired_api_keys(
+    pool: &PgPool,
+    before: DateTime<Utc>,
+    limit: i64,
+) -> AppResult<u64> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM api_keys
+        WHERE id IN (
+            SELECT id FROM api_keys
+            WHERE expires_at < $1
+            LIMIT $2
+        )
+        "#,
+        before,
+        limit
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to delete expired API keys ({})", e))?;
+    Ok(result.rows_affected())
+}
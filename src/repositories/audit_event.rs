@@ -0,0 +1,157 @@
+use anyhow::anyhow;
+use sqlx::PgPool;
+use tracing::instrument;
+
+use crate::{
+    models::{AuditEvent, AuditEventFilter},
+    utils::AppResult,
+};
+
+/// Appends `event` to the audit log, chained to the latest event.
+///
+/// Appends are serialized with a transaction-scoped advisory lock so that two events are never
+/// chained to the same predecessor.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn create_audit_event(
+    pool: &PgPool,
+    event: &AuditEvent,
+    key: Option<&[u8]>,
+) -> AppResult<AuditEvent> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Unable to begin transaction ({})", e))?;
+
+    sqlx::query!("SELECT pg_advisory_xact_lock(hashtext('audit_events'))")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Unable to lock audit log ({})", e))?;
+
+    let prev_hash = sqlx::query_scalar!(
+        r#"
+        SELECT hash FROM audit_events
+        ORDER BY seq DESC
+        LIMIT 1
+        "#
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Unable to get latest audit event ({})", e))?
+    .flatten();
+    let hash = event.chain_hash(prev_hash.as_deref(), key);
+
+    let event = sqlx::query_as!(
+        AuditEvent,
+        r#"
+        INSERT INTO audit_events (id, event_type, actor_id, target_id, ip, user_agent, details, created_at, prev_hash, hash)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING *
+        "#,
+        event.id,
+        event.event_type,
+        event.actor_id,
+        event.target_id,
+        event.ip,
+        event.user_agent,
+        event.details,
+        event.created_at,
+        prev_hash,
+        hash
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Unable to create audit event ({})", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Unable to commit audit event ({})", e))?;
+    Ok(event)
+}
+
+/// Lists events matching `filter`, latest first.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_audit_events(
+    pool: &PgPool,
+    filter: &AuditEventFilter,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<AuditEvent>> {
+    sqlx::query_as!(
+        AuditEvent,
+        r#"
+        SELECT * FROM audit_events
+        WHERE ($1::TEXT IS NULL OR event_type = $1)
+          AND ($2::UUID IS NULL OR actor_id = $2)
+          AND ($3::UUID IS NULL OR target_id = $3)
+          AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4)
+          AND ($5::TIMESTAMPTZ IS NULL OR created_at < $5)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $6 OFFSET $7
+        "#,
+        filter.event_type,
+        filter.actor_id,
+        filter.target_id,
+        filter.from,
+        filter.to,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get audit events ({})", e))
+}
+
+/// Lists up to `limit` events recorded after position `after_seq`, in chain order.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_audit_events_after(
+    pool: &PgPool,
+    after_seq: i64,
+    limit: i64,
+) -> AppResult<Vec<AuditEvent>> {
+    sqlx::query_as!(
+        AuditEvent,
+        r#"
+        SELECT * FROM audit_events
+        WHERE seq > $1
+        ORDER BY seq
+        LIMIT $2
+        "#,
+        after_seq,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get audit events ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_audit_event_by_seq(pool: &PgPool, seq: i64) -> AppResult<Option<AuditEvent>> {
+    sqlx::query_as!(
+        AuditEvent,
+        r#"
+        SELECT * FROM audit_events
+        WHERE seq = $1
+        "#,
+        seq
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get audit event ({})", e))
+}
+
+/// Gets the latest event that is part of the chain.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_latest_chained_audit_event(pool: &PgPool) -> AppResult<Option<AuditEvent>> {
+    sqlx::query_as!(
+        AuditEvent,
+        r#"
+        SELECT * FROM audit_events
+        WHERE hash IS NOT NULL
+        ORDER BY seq DESC
+        LIMIT 1
+        "#
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get latest audit event ({})", e))
+}
@@ -0,0 +1,112 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{models::Impersonation, utils::AppResult};
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn create_impersonation(
+    pool: &PgPool,
+    impersonation: &Impersonation,
+) -> AppResult<Impersonation> {
+    sqlx::query_as!(
+        Impersonation,
+        r#"
+        INSERT INTO impersonations (id, actor_id, user_id, reason, started_at, expires_at, ended_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING *
+        "#,
+        impersonation.id,
+        impersonation.actor_id,
+        impersonation.user_id,
+        impersonation.reason,
+        impersonation.started_at,
+        impersonation.expires_at,
+        impersonation.ended_at
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to create impersonation ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_impersonation_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Impersonation>> {
+    sqlx::query_as!(
+        Impersonation,
+        r#"
+        SELECT * FROM impersonations
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get impersonation ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_all_impersonations(
+    pool: &PgPool,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<Impersonation>> {
+    sqlx::query_as!(
+        Impersonation,
+        r#"
+        SELECT * FROM impersonations
+        ORDER BY started_at DESC
+        LIMIT $1 OFFSET $2
+        "#,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get impersonations ({})", e))
+}
+
+/// Stops the impersonation `id` unless it was already stopped.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn end_impersonation(pool: &PgPool, id: Uuid) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE impersonations
+        SET ended_at = $1
+        WHERE id = $2 AND ended_at IS NULL
+        "#,
+        Utc::now(),
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to end impersonation ({})", e))?;
+    Ok(())
+}
+
+/// Deletes up to `limit` impersonations that expired, or were stopped, before `before`, and
+/// returns how many were deleted.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_ended_impersonations(
+    pool: &PgPool,
+    before: DateTime<Utc>,
+    limit: i64,
+) -> AppResult<u64> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM impersonations
+        WHERE id IN (
+            SELECT id FROM impersonations
+            WHERE expires_at < $1 OR ended_at < $1
+            LIMIT $2
+        )
+        "#,
+        before,
+        limit
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to delete ended impersonations ({})", e))?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,29 @@
+use anyhow::anyhow;
+use sqlx::PgExecutor;
+use tracing::instrument;
+
+use crate::utils::AppResult;
+
+/// Key of the advisory lock held while migrations run, so that replicas booting together
+/// apply them one at a time.
+const MIGRATION_LOCK_KEY: i64 = 0x6175_7468_6d69_6772;
+
+/// Waits for and takes the migration lock, which is held until released or until the
+/// connection closes.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn lock_migrations<'e>(executor: impl PgExecutor<'e>) -> AppResult<()> {
+    sqlx::query!("SELECT pg_advisory_lock($1)", MIGRATION_LOCK_KEY)
+        .execute(executor)
+        .await
+        .map_err(|e| anyhow!("Unable to lock migrations ({})", e))?;
+    Ok(())
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn unlock_migrations<'e>(executor: impl PgExecutor<'e>) -> AppResult<()> {
+    sqlx::query!("SELECT pg_advisory_unlock($1)", MIGRATION_LOCK_KEY)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| anyhow!("Unable to unlock migrations ({})", e))?;
+    Ok(())
+}
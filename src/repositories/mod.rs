@@ -1,5 +1,25 @@
+mod api_key;
+mod audit_event;
+mod impersonation;
+mod migration;
+mod organization;
+mod outbox_event;
+mod password_history;
+mod role;
 mod session;
 mod user;
+mod user_invite;
+mod webhook;
 
+pub use api_key::*;
+pub use audit_event::*;
+pub use impersonation::*;
+pub use migration::*;
+pub use organization::*;
+pub use outbox_event::*;
+pub use password_history::*;
+pub use role::*;
 pub use session::*;
 pub use user::*;
+pub use user_invite::*;
+pub use webhook::*;
@@ -0,0 +1,200 @@
+use anyhow::anyhow;
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::{Membership, Organization},
+    utils::AppResult,
+};
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn create_organization(
+    pool: &PgPool,
+    organization: &Organization,
+) -> AppResult<Organization> {
+    sqlx::query_as!(
+        Organization,
+        r#"
+        INSERT INTO organizations (id, name, slug, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+        organization.id,
+        organization.name,
+        organization.slug,
+        organization.created_at,
+        organization.updated_at
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to create organization ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_organization_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Organization>> {
+    sqlx::query_as!(
+        Organization,
+        r#"
+        SELECT * FROM organizations
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get organization by id ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_organization_by_slug(
+    pool: &PgPool,
+    slug: &str,
+) -> AppResult<Option<Organization>> {
+    sqlx::query_as!(
+        Organization,
+        r#"
+        SELECT * FROM organizations
+        WHERE slug = $1
+        "#,
+        slug
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get organization by slug ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_all_organizations(
+    pool: &PgPool,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<Organization>> {
+    sqlx::query_as!(
+        Organization,
+        r#"
+        SELECT * FROM organizations
+        ORDER BY created_at DESC
+        LIMIT $1 OFFSET $2
+        "#,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get all organizations ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_organization<'e>(executor: impl PgExecutor<'e>, id: Uuid) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        DELETE FROM organizations
+        WHERE id = $1
+        "#,
+        id
+    )
+    .execute(executor)
+    .await
+    .map_err(|e| anyhow!("Unable to delete organization ({})", e))?;
+    Ok(())
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_membership(
+    pool: &PgPool,
+    org_id: Uuid,
+    user_id: Uuid,
+) -> AppResult<Option<Membership>> {
+    sqlx::query_as!(
+        Membership,
+        r#"
+        SELECT * FROM memberships
+        WHERE org_id = $1 AND user_id = $2
+        "#,
+        org_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get membership ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_memberships_by_org_id(pool: &PgPool, org_id: Uuid) -> AppResult<Vec<Membership>> {
+    sqlx::query_as!(
+        Membership,
+        r#"
+        SELECT * FROM memberships
+        WHERE org_id = $1
+        ORDER BY created_at
+        "#,
+        org_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get memberships ({})", e))
+}
+
+/// Creates a membership or, if the user is already a member, replaces their role.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn upsert_membership<'e>(
+    executor: impl PgExecutor<'e>,
+    membership: &Membership,
+) -> AppResult<Membership> {
+    sqlx::query_as!(
+        Membership,
+        r#"
+        INSERT INTO memberships (org_id, user_id, role_id, created_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (org_id, user_id) DO UPDATE SET role_id = EXCLUDED.role_id
+        RETURNING *
+        "#,
+        membership.org_id,
+        membership.user_id,
+        membership.role_id,
+        membership.created_at
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(|e| anyhow!("Unable to save membership ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_membership(pool: &PgPool, org_id: Uuid, user_id: Uuid) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        DELETE FROM memberships
+        WHERE org_id = $1 AND user_id = $2
+        "#,
+        org_id,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to delete membership ({})", e))?;
+    Ok(())
+}
+
+/// Resolves the permissions granted by the user's membership role in an organization.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_membership_permissions(
+    pool: &PgPool,
+    org_id: Uuid,
+    user_id: Uuid,
+) -> AppResult<Vec<String>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT permissions.name FROM permissions
+        JOIN role_permissions ON role_permissions.permission_id = permissions.id
+        JOIN memberships ON memberships.role_id = role_permissions.role_id
+        WHERE memberships.org_id = $1 AND memberships.user_id = $2
+        ORDER BY permissions.name
+        "#,
+        org_id,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get membership permissions ({})", e))
+}
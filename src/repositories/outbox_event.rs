@@ -0,0 +1,83 @@
+use anyhow::anyhow;
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::{OutboxEvent, WebhookDelivery},
+    utils::AppResult,
+};
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn create_outbox_event<'e>(
+    executor: impl PgExecutor<'e>,
+    event: &OutboxEvent,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO outbox_events (id, event_type, payload, created_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        event.id,
+        event.event_type,
+        event.payload,
+        event.created_at
+    )
+    .execute(executor)
+    .await
+    .map_err(|e| anyhow!("Unable to create outbox event ({})", e))?;
+    Ok(())
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_outbox_event_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<OutboxEvent>> {
+    sqlx::query_as!(
+        OutboxEvent,
+        r#"
+        SELECT * FROM outbox_events
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get outbox event ({})", e))
+}
+
+/// Queues a delivery of up to `limit` undispatched events to every active subscription
+/// interested in them, and marks the events dispatched, in a single statement.
+///
+/// Concurrent dispatchers skip the events locked by one another.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn dispatch_outbox_events(pool: &PgPool, limit: i64) -> AppResult<u64> {
+    let result = sqlx::query!(
+        r#"
+        WITH batch AS (
+            SELECT id, event_type FROM outbox_events
+            WHERE dispatched_at IS NULL
+            ORDER BY created_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        ),
+        queued AS (
+            INSERT INTO webhook_deliveries (id, event_id, subscription_id, status, next_attempt_at, created_at)
+            SELECT gen_random_uuid(), batch.id, webhook_subscriptions.id, $2, NOW(), NOW()
+            FROM batch
+            JOIN webhook_subscriptions ON webhook_subscriptions.is_active
+                AND (cardinality(webhook_subscriptions.event_types) = 0
+                    OR batch.event_type = ANY(webhook_subscriptions.event_types))
+            ON CONFLICT DO NOTHING
+        )
+        UPDATE outbox_events
+        SET dispatched_at = NOW()
+        FROM batch
+        WHERE outbox_events.id = batch.id
+        "#,
+        limit,
+        WebhookDelivery::PENDING
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to dispatch outbox events ({})", e))?;
+    Ok(result.rows_affected())
+}
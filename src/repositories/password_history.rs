@@ -0,0 +1,74 @@
+use anyhow::anyhow;
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{models::PasswordHistory, utils::AppResult};
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn create_password_history<'e>(
+    executor: impl PgExecutor<'e>,
+    entry: &PasswordHistory,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO password_history (id, user_id, password_hash, created_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        entry.id,
+        entry.user_id,
+        entry.password_hash,
+        entry.created_at
+    )
+    .execute(executor)
+    .await
+    .map_err(|e| anyhow!("Unable to create password history ({})", e))?;
+    Ok(())
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_password_history(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+) -> AppResult<Vec<PasswordHistory>> {
+    sqlx::query_as!(
+        PasswordHistory,
+        r#"
+        SELECT * FROM password_history
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+        user_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get password history ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn prune_password_history<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    keep: i64,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        DELETE FROM password_history
+        WHERE user_id = $1 AND id NOT IN (
+            SELECT id FROM password_history
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+        )
+        "#,
+        user_id,
+        keep
+    )
+    .execute(executor)
+    .await
+    .map_err(|e| anyhow!("Unable to prune password history ({})", e))?;
+    Ok(())
+}
@@ -0,0 +1,317 @@
+use anyhow::anyhow;
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::{Permission, Role},
+    utils::AppResult,
+};
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn create_role(pool: &PgPool, role: &Role) -> AppResult<Role> {
+    sqlx::query_as!(
+        Role,
+        r#"
+        INSERT INTO roles (id, name, description, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+        role.id,
+        role.name,
+        role.description,
+        role.created_at,
+        role.updated_at
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to create role ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_role_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Role>> {
+    sqlx::query_as!(
+        Role,
+        r#"
+        SELECT * FROM roles
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get role by id ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_role_by_name(pool: &PgPool, name: &str) -> AppResult<Option<Role>> {
+    sqlx::query_as!(
+        Role,
+        r#"
+        SELECT * FROM roles
+        WHERE name = $1
+        "#,
+        name
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get role by name ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_all_roles(pool: &PgPool) -> AppResult<Vec<Role>> {
+    sqlx::query_as!(
+        Role,
+        r#"
+        SELECT * FROM roles
+        ORDER BY name
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get all roles ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn update_role(pool: &PgPool, role: &Role) -> AppResult<Role> {
+    sqlx::query_as!(
+        Role,
+        r#"
+        UPDATE roles
+        SET name = $2, description = $3, updated_at = $4
+        WHERE id = $1
+        RETURNING *
+        "#,
+        role.id,
+        role.name,
+        role.description,
+        Utc::now()
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to update role ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_role(pool: &PgPool, id: Uuid) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        DELETE FROM roles
+        WHERE id = $1
+        "#,
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to delete role ({})", e))?;
+    Ok(())
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_all_permissions(pool: &PgPool) -> AppResult<Vec<Permission>> {
+    sqlx::query_as!(
+        Permission,
+        r#"
+        SELECT * FROM permissions
+        ORDER BY name
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get all permissions ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_role_permissions(pool: &PgPool, role_id: Uuid) -> AppResult<Vec<String>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT permissions.name FROM permissions
+        JOIN role_permissions ON role_permissions.permission_id = permissions.id
+        WHERE role_permissions.role_id = $1
+        ORDER BY permissions.name
+        "#,
+        role_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get role permissions ({})", e))
+}
+
+/// Replaces the permissions of a role with the named ones in a single transaction.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn set_role_permissions(
+    pool: &PgPool,
+    role_id: Uuid,
+    permissions: &[String],
+) -> AppResult<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Unable to begin transaction ({})", e))?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM role_permissions
+        WHERE role_id = $1
+        "#,
+        role_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Unable to clear role permissions ({})", e))?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO role_permissions (role_id, permission_id)
+        SELECT $1, id FROM permissions
+        WHERE name = ANY($2)
+        "#,
+        role_id,
+        permissions
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Unable to set role permissions ({})", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Unable to commit role permissions ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_user_permissions(pool: &PgPool, user_id: Uuid) -> AppResult<Vec<String>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT permissions.name FROM permissions
+        JOIN role_permissions ON role_permissions.permission_id = permissions.id
+        JOIN user_roles ON user_roles.role_id = role_permissions.role_id
+        WHERE user_roles.user_id = $1
+        ORDER BY permissions.name
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get user permissions ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_user_roles(pool: &PgPool, user_id: Uuid) -> AppResult<Vec<Role>> {
+    sqlx::query_as!(
+        Role,
+        r#"
+        SELECT roles.* FROM roles
+        JOIN user_roles ON user_roles.role_id = roles.id
+        WHERE user_roles.user_id = $1
+        ORDER BY roles.name
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get user roles ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn assign_user_role(pool: &PgPool, user_id: Uuid, role_id: Uuid) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO user_roles (user_id, role_id, created_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT DO NOTHING
+        "#,
+        user_id,
+        role_id,
+        Utc::now()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to assign user role ({})", e))?;
+    Ok(())
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn count_users_with_role(pool: &PgPool, role_id: Uuid) -> AppResult<i64> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!" FROM user_roles
+        WHERE role_id = $1
+        "#,
+        role_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to count users with role ({})", e))
+}
+
+/// Removes a role from a user and reports whether it was removed.
+///
+/// With `keep_last`, the role is left in place if the user is its only holder. The role
+/// row is locked for the duration so that concurrent revocations cannot both pass the check.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn revoke_user_role(
+    pool: &PgPool,
+    user_id: Uuid,
+    role_id: Uuid,
+    keep_last: bool,
+) -> AppResult<bool> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Unable to begin transaction ({})", e))?;
+
+    sqlx::query!(
+        r#"
+        SELECT id FROM roles
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        role_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Unable to lock role ({})", e))?;
+
+    let revoked = sqlx::query!(
+        r#"
+        DELETE FROM user_roles
+        WHERE user_id = $1 AND role_id = $2
+            AND (NOT $3 OR (SELECT COUNT(*) FROM user_roles WHERE role_id = $2) > 1)
+        "#,
+        user_id,
+        role_id,
+        keep_last
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Unable to revoke user role ({})", e))?
+    .rows_affected()
+        > 0;
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Unable to commit role revocation ({})", e))?;
+    Ok(revoked)
+}
+
+/// Sets `users.is_admin` to reflect whether the user holds the built-in admin role.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn sync_user_is_admin(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET is_admin = EXISTS (
+            SELECT 1 FROM user_roles
+            JOIN roles ON roles.id = user_roles.role_id
+            WHERE user_roles.user_id = $1 AND roles.name = 'admin'
+        )
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to sync user admin flag ({})", e))?;
+    Ok(())
+}
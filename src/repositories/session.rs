@@ -1,15 +1,17 @@
 use crate::{models::Session, utils::AppResult};
 use anyhow::anyhow;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
+use tracing::instrument;
 use uuid::Uuid;
 
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
 pub async fn create_session(pool: &PgPool, session: &Session) -> AppResult<Session> {
     sqlx::query_as!(
         Session,
         r#"
-        INSERT INTO sessions (id, user_id, refresh_token, expires_at, is_revoked, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        INSERT INTO sessions (id, user_id, refresh_token, expires_at, is_revoked, is_persistent, created_at, updated_at, last_used_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         RETURNING *
         "#,
         session.id,
@@ -17,14 +19,17 @@ pub async fn create_session(pool: &PgPool, session: &Session) -> AppResult<Sessi
         session.refresh_token,
         session.expires_at,
         session.is_revoked,
+        session.is_persistent,
         session.created_at,
-        session.updated_at
+        session.updated_at,
+        session.last_used_at
     )
     .fetch_one(pool)
     .await
     .map_err(|e| anyhow!("Unable to create session ({})", e))
 }
 
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
 pub async fn get_session_by_user_id(pool: &PgPool, user_id: Uuid) -> AppResult<Option<Session>> {
     sqlx::query_as!(
         Session,
@@ -39,6 +44,7 @@ pub async fn get_session_by_user_id(pool: &PgPool, user_id: Uuid) -> AppResult<O
     .map_err(|e| anyhow!("Unable to get session by ID ({})", e))
 }
 
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
 pub async fn revoke_session(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
     sqlx::query!(
         r#"
@@ -55,6 +61,62 @@ pub async fn revoke_session(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
     Ok(())
 }
 
+/// Saves when a session was last used and when it expires.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn touch_session(pool: &PgPool, session: &Session) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE sessions
+        SET last_used_at = $1, expires_at = $2, updated_at = $3
+        WHERE id = $4
+        "#,
+        session.last_used_at,
+        session.expires_at,
+        session.updated_at,
+        session.id,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to touch session ({})", e))?;
+    Ok(())
+}
+
+/// Revokes the sessions of every user that belongs to an organization.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn revoke_org_sessions(pool: &PgPool, org_id: Uuid) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE sessions
+        SET is_revoked = true, updated_at = $1
+        WHERE user_id IN (SELECT id FROM users WHERE org_id = $2)
+        "#,
+        Utc::now(),
+        org_id,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to revoke organization sessions ({})", e))?;
+    Ok(())
+}
+
+/// Revokes the sessions of every user.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn revoke_all_sessions(pool: &PgPool) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE sessions
+        SET is_revoked = true, updated_at = $1
+        WHERE NOT is_revoked
+        "#,
+        Utc::now(),
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to revoke all sessions ({})", e))?;
+    Ok(())
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
 pub async fn delete_session_by_user_id(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
     sqlx::query!(
         r#"
@@ -68,3 +130,46 @@ pub async fn delete_session_by_user_id(pool: &PgPool, user_id: Uuid) -> AppResul
     .map_err(|e| anyhow!("Unable to delete session ({})", e))?;
     Ok(())
 }
+
+/// Counts the sessions neither expired nor revoked at `now`.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn count_active_sessions(pool: &PgPool, now: DateTime<Utc>) -> AppResult<i64> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!" FROM sessions
+        WHERE NOT is_revoked AND expires_at > $1
+        "#,
+        now
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to count active sessions ({})", e))
+}
+
+/// Deletes up to `limit` sessions that expired, or were revoked, before `before`, or were last
+/// used before `idle_before` when given, and returns how many were deleted.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_stale_sessions(
+    pool: &PgPool,
+    before: DateTime<Utc>,
+    idle_before: Option<DateTime<Utc>>,
+    limit: i64,
+) -> AppResult<u64> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM sessions
+        WHERE id IN (
+            SELECT id FROM sessions
+            WHERE expires_at < $1 OR (is_revoked AND updated_at < $1) OR last_used_at < $3
+            LIMIT $2
+        )
+        "#,
+        before,
+        limit,
+        idle_before
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to delete stale sessions ({})", e))?;
+    Ok(result.rows_affected())
+}
@@ -1,19 +1,21 @@
 use anyhow::anyhow;
-use chrono::Utc;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{models::User, utils::AppResult};
 
-pub async fn create_user(pool: &PgPool, user: &User) -> AppResult<User> {
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn create_user<'e>(executor: impl PgExecutor<'e>, user: &User) -> AppResult<User> {
     sqlx::query_as!(
         User,
         r#"
         INSERT INTO users (
             id, github_id, username, email, 
-            password_hash, avatar_url, is_admin, created_at, updated_at
+            password_hash, avatar_url, is_admin, org_id, created_at, updated_at
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
         RETURNING *
         "#,
         user.id,
@@ -23,14 +25,16 @@ pub async fn create_user(pool: &PgPool, user: &User) -> AppResult<User> {
         user.password_hash,
         user.avatar_url,
         user.is_admin,
+        user.org_id,
         user.created_at,
         user.updated_at
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await
     .map_err(|e| anyhow!("Unable to create user ({})", e))
 }
 
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
 pub async fn get_user_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<User>> {
     sqlx::query_as!(
         User,
@@ -45,6 +49,7 @@ pub async fn get_user_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<User>>
     .map_err(|e| anyhow!("Unable to get user by id ({})", e))
 }
 
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
 pub async fn get_user_by_github_id(pool: &PgPool, github_id: i64) -> AppResult<Option<User>> {
     sqlx::query_as!(
         User,
@@ -59,6 +64,7 @@ pub async fn get_user_by_github_id(pool: &PgPool, github_id: i64) -> AppResult<O
     .map_err(|e| anyhow!("Unable to get user by GitHub ID ({})", e))
 }
 
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
 pub async fn get_user_by_email(pool: &PgPool, email: &str) -> AppResult<Option<User>> {
     sqlx::query_as!(
         User,
@@ -73,6 +79,7 @@ pub async fn get_user_by_email(pool: &PgPool, email: &str) -> AppResult<Option<U
     .map_err(|e| anyhow!("Unable to get user by email ({})", e))
 }
 
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
 pub async fn get_user_by_username(pool: &PgPool, username: &str) -> AppResult<Option<User>> {
     sqlx::query_as!(
         User,
@@ -87,6 +94,7 @@ pub async fn get_user_by_username(pool: &PgPool, username: &str) -> AppResult<Op
     .map_err(|e| anyhow!("Unable to get user by username ({})", e))
 }
 
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
 pub async fn get_user_by_username_or_email(
     pool: &PgPool,
     username: &str,
@@ -106,28 +114,136 @@ pub async fn get_user_by_username_or_email(
     .map_err(|e| anyhow!("Unable to get user by username or email ({})", e))
 }
 
-pub async fn get_all_users(pool: &PgPool, limit: i64, offset: i64) -> AppResult<Vec<User>> {
+/// Looks up a user by username within an organization, or among users outside any
+/// organization when `org_id` is `None`.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_user_by_username_in_org(
+    pool: &PgPool,
+    org_id: Option<Uuid>,
+    username: &str,
+) -> AppResult<Option<User>> {
+    sqlx::query_as!(
+        User,
+        r#"
+        SELECT * FROM users
+        WHERE org_id IS NOT DISTINCT FROM $1 AND username = $2
+        "#,
+        org_id,
+        username
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get user by username ({})", e))
+}
+
+/// Looks up a user by email within an organization, or among users outside any
+/// organization when `org_id` is `None`.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_user_by_email_in_org(
+    pool: &PgPool,
+    org_id: Option<Uuid>,
+    email: &str,
+) -> AppResult<Option<User>> {
+    sqlx::query_as!(
+        User,
+        r#"
+        SELECT * FROM users
+        WHERE org_id IS NOT DISTINCT FROM $1 AND email = $2
+        "#,
+        org_id,
+        email
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get user by email ({})", e))
+}
+
+/// Looks up a user by username or email within an organization, or among users outside
+/// any organization when `org_id` is `None`.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_user_by_username_or_email_in_org(
+    pool: &PgPool,
+    org_id: Option<Uuid>,
+    username: &str,
+    email: &str,
+) -> AppResult<Option<User>> {
+    sqlx::query_as!(
+        User,
+        r#"
+        SELECT * FROM users
+        WHERE org_id IS NOT DISTINCT FROM $1 AND (username = $2 OR email = $3)
+        "#,
+        org_id,
+        username,
+        email
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get user by username or email ({})", e))
+}
+
+/// Lists users, restricted to the members of `org_id` when given.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_all_users(
+    pool: &PgPool,
+    org_id: Option<Uuid>,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<User>> {
     sqlx::query_as!(
         User,
         r#"
         SELECT * FROM users
+        WHERE $3::uuid IS NULL OR org_id = $3
         ORDER BY created_at DESC
         LIMIT $1 OFFSET $2
         "#,
         limit,
-        offset
+        offset,
+        org_id
     )
     .fetch_all(pool)
     .await
     .map_err(|e| anyhow!("Unable to get all users ({})", e))
 }
 
-pub async fn update_user(pool: &PgPool, user: &User) -> AppResult<User> {
+/// Fetches users in creation order, starting after the `(created_at, id)` keyset cursor,
+/// restricted to the members of `org_id` when given.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_users_after(
+    pool: &PgPool,
+    org_id: Option<Uuid>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+    limit: i64,
+) -> AppResult<Vec<User>> {
+    let (created_at, id) = after.unzip();
+    sqlx::query_as!(
+        User,
+        r#"
+        SELECT * FROM users
+        WHERE ($1::timestamptz IS NULL OR (created_at, id) > ($1, $2))
+            AND ($4::uuid IS NULL OR org_id = $4)
+        ORDER BY created_at, id
+        LIMIT $3
+        "#,
+        created_at,
+        id,
+        limit,
+        org_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get users ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn update_user<'e>(executor: impl PgExecutor<'e>, user: &User) -> AppResult<User> {
     sqlx::query_as!(
         User,
         r#"
         UPDATE users
-        SET username = $2, email = $3, avatar_url = $4, is_admin = $5,updated_at = $6
+        SET username = $2, email = $3, avatar_url = $4, is_admin = $5, updated_at = $6,
+            password_hash = $7, github_id = $8
         WHERE id = $1
         RETURNING *
         "#,
@@ -136,23 +252,87 @@ pub async fn update_user(pool: &PgPool, user: &User) -> AppResult<User> {
         user.email,
         user.avatar_url,
         user.is_admin,
-        Utc::now()
+        Utc::now(),
+        user.password_hash,
+        user.github_id
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await
     .map_err(|e| anyhow!("Unable to update user ({})", e))
 }
 
-pub async fn delete_user(pool: &PgPool, id: Uuid) -> AppResult<()> {
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn update_user_password_hash(
+    pool: &PgPool,
+    id: Uuid,
+    password_hash: &str,
+) -> AppResult<()> {
     sqlx::query!(
         r#"
-        DELETE FROM users
+        UPDATE users
+        SET password_hash = $2
         WHERE id = $1
         "#,
-        id
+        id,
+        password_hash
     )
     .execute(pool)
     .await
-    .map_err(|e| anyhow!("Unable to delete user ({})", e))?;
+    .map_err(|e| anyhow!("Unable to update user password hash ({})", e))?;
     Ok(())
 }
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_user<'e>(executor: impl PgExecutor<'e>, id: Uuid) -> AppResult<Option<User>> {
+    sqlx::query_as!(
+        User,
+        r#"
+        DELETE FROM users
+        WHERE id = $1
+        RETURNING *
+        "#,
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| anyhow!("Unable to delete user ({})", e))
+}
+
+/// Deletes the users owned by an organization, returning them.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_users_by_org_id<'e>(
+    executor: impl PgExecutor<'e>,
+    org_id: Uuid,
+) -> AppResult<Vec<User>> {
+    sqlx::query_as!(
+        User,
+        r#"
+        DELETE FROM users
+        WHERE org_id = $1
+        RETURNING *
+        "#,
+        org_id
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(|e| anyhow!("Unable to delete organization users ({})", e))
+}
+
+/// Gets the email of a user and locks the user until the end of the transaction.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_user_email_for_update<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> AppResult<Option<String>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT email FROM users
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| anyhow!("Unable to get user email ({})", e))
+}
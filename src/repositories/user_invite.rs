@@ -0,0 +1,89 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{models::UserInvite, utils::AppResult};
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn create_user_invite<'e>(
+    executor: impl PgExecutor<'e>,
+    invite: &UserInvite,
+) -> AppResult<UserInvite> {
+    sqlx::query_as!(
+        UserInvite,
+        r#"
+        INSERT INTO user_invites (id, user_id, token_hash, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+        invite.id,
+        invite.user_id,
+        invite.token_hash,
+        invite.expires_at,
+        invite.created_at
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(|e| anyhow!("Unable to create user invite ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_user_invite_by_token_hash(
+    pool: &PgPool,
+    token_hash: &str,
+) -> AppResult<Option<UserInvite>> {
+    sqlx::query_as!(
+        UserInvite,
+        r#"
+        SELECT * FROM user_invites
+        WHERE token_hash = $1
+        "#,
+        token_hash
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get user invite ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_user_invites(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        DELETE FROM user_invites
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to delete user invites ({})", e))?;
+    Ok(())
+}
+
+/// Deletes up to `limit` invites that expired before `before`, and returns how many were
+/// deleted.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_expired_user_invites(
+    pool: &PgPool,
+    before: DateTime<Utc>,
+    limit: i64,
+) -> AppResult<u64> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM user_invites
+        WHERE id IN (
+            SELECT id FROM user_invites
+            WHERE expires_at < $1
+            LIMIT $2
+        )
+        "#,
+        before,
+        limit
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to delete expired user invites ({})", e))?;
+    Ok(result.rows_affected())
+}
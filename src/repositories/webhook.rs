@@ -0,0 +1,238 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::{WebhookDelivery, WebhookSubscription},
+    utils::AppResult,
+};
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn create_webhook_subscription(
+    pool: &PgPool,
+    subscription: &WebhookSubscription,
+) -> AppResult<WebhookSubscription> {
+    sqlx::query_as!(
+        WebhookSubscription,
+        r#"
+        INSERT INTO webhook_subscriptions (id, url, secret, event_types, is_active, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#,
+        subscription.id,
+        subscription.url,
+        subscription.secret,
+        &subscription.event_types,
+        subscription.is_active,
+        subscription.created_at
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to create webhook subscription ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_webhook_subscriptions(pool: &PgPool) -> AppResult<Vec<WebhookSubscription>> {
+    sqlx::query_as!(
+        WebhookSubscription,
+        r#"
+        SELECT * FROM webhook_subscriptions
+        ORDER BY created_at
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get webhook subscriptions ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_webhook_subscription_by_id(
+    pool: &PgPool,
+    id: Uuid,
+) -> AppResult<Option<WebhookSubscription>> {
+    sqlx::query_as!(
+        WebhookSubscription,
+        r#"
+        SELECT * FROM webhook_subscriptions
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get webhook subscription ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn update_webhook_subscription(
+    pool: &PgPool,
+    subscription: &WebhookSubscription,
+) -> AppResult<WebhookSubscription> {
+    sqlx::query_as!(
+        WebhookSubscription,
+        r#"
+        UPDATE webhook_subscriptions
+        SET url = $2, event_types = $3, is_active = $4
+        WHERE id = $1
+        RETURNING *
+        "#,
+        subscription.id,
+        subscription.url,
+        &subscription.event_types,
+        subscription.is_active
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to update webhook subscription ({})", e))
+}
+
+/// Deletes a subscription along with its deliveries, returning whether it existed.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_webhook_subscription(pool: &PgPool, id: Uuid) -> AppResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM webhook_subscriptions
+        WHERE id = $1
+        "#,
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to delete webhook subscription ({})", e))?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Claims up to `limit` pending deliveries that are due, to active subscriptions and oldest
+/// events first, by pushing
+/// their next attempt back by `lease_secs` so that no other dispatcher picks them up meanwhile.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn claim_due_webhook_deliveries(
+    pool: &PgPool,
+    limit: i64,
+    lease_secs: i64,
+) -> AppResult<Vec<WebhookDelivery>> {
+    sqlx::query_as!(
+        WebhookDelivery,
+        r#"
+        WITH claimed AS (
+            UPDATE webhook_deliveries
+            SET next_attempt_at = NOW() + make_interval(secs => $3)
+            WHERE id IN (
+                SELECT webhook_deliveries.id FROM webhook_deliveries
+                JOIN webhook_subscriptions ON webhook_subscriptions.id = webhook_deliveries.subscription_id
+                WHERE webhook_deliveries.status = $1
+                  AND webhook_deliveries.next_attempt_at <= NOW()
+                  AND webhook_subscriptions.is_active
+                ORDER BY webhook_deliveries.next_attempt_at
+                LIMIT $2
+                FOR UPDATE OF webhook_deliveries SKIP LOCKED
+            )
+            RETURNING *
+        )
+        SELECT claimed.id AS "id!", claimed.event_id AS "event_id!",
+            claimed.subscription_id AS "subscription_id!", claimed.status AS "status!",
+            claimed.attempts AS "attempts!", claimed.next_attempt_at AS "next_attempt_at!",
+            claimed.last_error, claimed.delivered_at, claimed.created_at AS "created_at!"
+        FROM claimed
+        JOIN outbox_events ON outbox_events.id = claimed.event_id
+        ORDER BY outbox_events.created_at
+        "#,
+        WebhookDelivery::PENDING,
+        limit,
+        lease_secs as f64
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to claim webhook deliveries ({})", e))
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn mark_webhook_delivered(pool: &PgPool, id: Uuid) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = $2, attempts = attempts + 1, last_error = NULL, delivered_at = NOW()
+        WHERE id = $1
+        "#,
+        id,
+        WebhookDelivery::DELIVERED
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to update webhook delivery ({})", e))?;
+    Ok(())
+}
+
+/// Records a failed attempt, to be retried at `next_attempt_at` or given up on when unset.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn mark_webhook_failed(
+    pool: &PgPool,
+    id: Uuid,
+    error: &str,
+    next_attempt_at: Option<DateTime<Utc>>,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = CASE WHEN $3::TIMESTAMPTZ IS NULL THEN $4 ELSE status END,
+            attempts = attempts + 1,
+            last_error = $2,
+            next_attempt_at = COALESCE($3, next_attempt_at)
+        WHERE id = $1
+        "#,
+        id,
+        error,
+        next_attempt_at,
+        WebhookDelivery::DEAD
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to update webhook delivery ({})", e))?;
+    Ok(())
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_webhook_deliveries_by_status(
+    pool: &PgPool,
+    status: &str,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<WebhookDelivery>> {
+    sqlx::query_as!(
+        WebhookDelivery,
+        r#"
+        SELECT * FROM webhook_deliveries
+        WHERE status = $1
+        ORDER BY created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        status,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to get webhook deliveries ({})", e))
+}
+
+/// Moves a dead delivery back to pending for a fresh round of attempts, returning whether it
+/// was dead.
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn retry_webhook_delivery(pool: &PgPool, id: Uuid) -> AppResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = $2, attempts = 0, last_error = NULL, next_attempt_at = NOW()
+        WHERE id = $1 AND status = $3
+        "#,
+        id,
+        WebhookDelivery::PENDING,
+        WebhookDelivery::DEAD
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Unable to retry webhook delivery ({})", e))?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,70 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{models::ApiKey, repositories, utils::AppResult};
+
+use super::{hash_token, resolve_permissions};
+
+/// Number of characters of a key kept in clear to tell keys apart.
+const DISPLAY_PREFIX_LEN: usize = 11;
+
+/// Issues an API key and returns it along with the key itself, which is only stored hashed.
+pub async fn create_api_key(
+    pool: &PgPool,
+    user_id: Uuid,
+    org_id: Option<Uuid>,
+    name: &str,
+    scopes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+) -> AppResult<(ApiKey, String)> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let key = format!("{}{}", ApiKey::PREFIX, URL_SAFE_NO_PAD.encode(bytes));
+
+    let api_key = repositories::create_api_key(
+        pool,
+        &ApiKey::new(
+            user_id,
+            org_id,
+            name,
+            &key[..DISPLAY_PREFIX_LEN],
+            hash_token(&key),
+            scopes,
+            expires_at,
+        ),
+    )
+    .await?;
+    Ok((api_key, key))
+}
+
+/// Looks up a key, ignoring revoked and expired ones.
+pub async fn get_active_api_key(pool: &PgPool, key: &str) -> AppResult<Option<ApiKey>> {
+    Ok(
+        repositories::get_api_key_by_key_hash(pool, &hash_token(key))
+            .await?
+            .filter(|api_key| !api_key.is_revoked && !api_key.is_expired()),
+    )
+}
+
+pub async fn get_api_keys_by_user_id(pool: &PgPool, user_id: Uuid) -> AppResult<Vec<ApiKey>> {
+    repositories::get_api_keys_by_user_id(pool, user_id).await
+}
+
+pub async fn revoke_api_key(pool: &PgPool, user_id: Uuid, id: Uuid) -> AppResult<bool> {
+    repositories::revoke_api_key(pool, user_id, id).await
+}
+
+/// Resolves the permissions a key grants: its scopes still held by its owner.
+pub async fn resolve_api_key_permissions(
+    pool: &PgPool,
+    api_key: &ApiKey,
+) -> AppResult<Vec<String>> {
+    Ok(resolve_permissions(pool, api_key.user_id, api_key.org_id)
+        .await?
+        .into_iter()
+        .filter(|permission| api_key.scopes.contains(permission))
+        .collect())
+}
@@ -0,0 +1,181 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::Context;
+use chrono::Utc;
+use sqlx::PgPool;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+
+use crate::{
+    dto::{AuditChainReportDto, AuditCheckpointDto, BrokenLinkDto},
+    models::{AuditEvent, AuditEventFilter},
+    repositories,
+    utils::AppResult,
+};
+
+/// Events fetched at a time while walking the audit chain.
+const VERIFY_PAGE_SIZE: i64 = 1000;
+
+/// Records `event` in the audit log, chained to the previous event and signed with `key` if set.
+pub async fn record_audit_event(
+    pool: &PgPool,
+    key: Option<&[u8]>,
+    event: AuditEvent,
+) -> AppResult<()> {
+    tracing::info!(
+        "Audit event {} by {:?} on {:?}",
+        event.event_type,
+        event.actor_id,
+        event.target_id
+    );
+    repositories::create_audit_event(pool, &event, key).await?;
+    Ok(())
+}
+
+pub async fn get_audit_events(
+    pool: &PgPool,
+    filter: &AuditEventFilter,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<AuditEvent>> {
+    repositories::get_audit_events(pool, filter, limit, offset).await
+}
+
+/// Walks the audit chain from the first event and stops at the first broken link.
+///
+/// ## Parameters
+/// - `pool` - The database connection pool.
+/// - `key` - The HMAC key the chain was signed with, if any.
+///
+/// ## Returns
+/// A report of the events checked and the first broken link, if any.
+pub async fn verify_audit_chain(
+    pool: &PgPool,
+    key: Option<&[u8]>,
+) -> AppResult<AuditChainReportDto> {
+    let mut report = AuditChainReportDto::default();
+    let mut after_seq = 0;
+
+    loop {
+        let events =
+            repositories::get_audit_events_after(pool, after_seq, VERIFY_PAGE_SIZE).await?;
+        let Some(last) = events.last() else {
+            return Ok(report);
+        };
+        after_seq = last.seq;
+
+        for event in events {
+            let Some(hash) = &event.hash else {
+                // Only the events recorded before chaining was introduced may lack a hash
+                if report.head.is_none() {
+                    report.unchained += 1;
+                    continue;
+                }
+                report.broken_link = Some(broken_link(&event, "Missing hash"));
+                return Ok(report);
+            };
+
+            let reason = if event.prev_hash != report.head {
+                Some("Previous hash does not match the preceding event")
+            } else if *hash != event.chain_hash(report.head.as_deref(), key) {
+                Some("Hash does not match the event")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                report.broken_link = Some(broken_link(&event, reason));
+                return Ok(report);
+            }
+
+            report.checked += 1;
+            report.head = event.hash;
+        }
+    }
+}
+
+/// Checks that every checkpoint still matches the event it pins.
+///
+/// ## Returns
+/// The first checkpoint that does not match, if any.
+pub async fn verify_audit_checkpoints(
+    pool: &PgPool,
+    checkpoints: &[AuditCheckpointDto],
+) -> AppResult<Option<BrokenLinkDto>> {
+    for checkpoint in checkpoints {
+        let reason = match repositories::get_audit_event_by_seq(pool, checkpoint.seq).await? {
+            None => "Checkpointed event is missing",
+            Some(event) if event.id != checkpoint.id => "Checkpointed event was replaced",
+            Some(event) if event.hash.as_deref() != Some(checkpoint.hash.as_str()) => {
+                "Hash does not match the checkpoint"
+            }
+            Some(_) => continue,
+        };
+        return Ok(Some(BrokenLinkDto {
+            seq: checkpoint.seq,
+            id: checkpoint.id,
+            reason: reason.to_string(),
+        }));
+    }
+    Ok(None)
+}
+
+/// Pins the latest chained event, if any.
+pub async fn create_audit_checkpoint(pool: &PgPool) -> AppResult<Option<AuditCheckpointDto>> {
+    let checkpoint = repositories::get_latest_chained_audit_event(pool)
+        .await?
+        .and_then(|event| {
+            Some(AuditCheckpointDto {
+                seq: event.seq,
+                id: event.id,
+                hash: event.hash?,
+                exported_at: Utc::now(),
+            })
+        });
+    Ok(checkpoint)
+}
+
+/// Appends a checkpoint of the latest chained event to `path` as a JSON line.
+pub async fn export_audit_checkpoint(
+    pool: &PgPool,
+    path: &Path,
+) -> AppResult<Option<AuditCheckpointDto>> {
+    let Some(checkpoint) = create_audit_checkpoint(pool).await? else {
+        return Ok(None);
+    };
+
+    let mut line = serde_json::to_string(&checkpoint)?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(line.as_bytes())
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(Some(checkpoint))
+}
+
+/// Exports a checkpoint to `path` every `interval`, for as long as the server runs.
+pub async fn run_audit_checkpoints(pool: PgPool, path: String, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        match export_audit_checkpoint(&pool, Path::new(&path)).await {
+            Ok(Some(checkpoint)) => {
+                tracing::info!("Exported audit checkpoint at event {}", checkpoint.seq)
+            }
+            Ok(None) => {}
+            Err(e) => tracing::error!("Failed to export audit checkpoint: {}", e),
+        }
+    }
+}
+
+fn broken_link(event: &AuditEvent, reason: &str) -> BrokenLinkDto {
+    BrokenLinkDto {
+        seq: event.seq,
+        id: event.id,
+        reason: reason.to_string(),
+    }
+}
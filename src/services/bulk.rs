@@ -0,0 +1,290 @@
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+use axum::body::Bytes;
+use chrono::{DateTime, Duration, Utc};
+use futures_util::{stream, Stream};
+use sqlx::PgPool;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    dto::{
+        BulkFormat, ExportUserRowDto, ImportErrorDto, ImportInviteDto, ImportReportDto,
+        ImportUserRowDto, ImportUsersQueryDto,
+    },
+    models::{Membership, User},
+    repositories,
+    utils::{is_supported_hash, AppConfig, AppResult, UNUSABLE_PASSWORD},
+};
+
+use super::{
+    create_user_invite, find_user_by_username_or_email, insert_password_history, insert_user,
+};
+
+/// Number of users fetched per query while exporting.
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Usernames, emails and GitHub IDs already seen in the current import, so that
+/// duplicates within a file are reported even during a dry run.
+#[derive(Default)]
+struct SeenRows {
+    usernames: HashSet<String>,
+    emails: HashSet<String>,
+    github_ids: HashSet<i64>,
+}
+
+/// Imports users line by line from a CSV (with a header row) or JSON Lines reader.
+///
+/// With an `org_id`, users are created in and made members of that organization. Each row is validated on its own: invalid rows are listed in the report with their
+/// line number and do not stop the import. CSV fields must not span multiple lines.
+pub async fn import_users<R>(
+    pool: &PgPool,
+    config: &AppConfig,
+    org_id: Option<Uuid>,
+    reader: R,
+    options: &ImportUsersQueryDto,
+) -> AppResult<ImportReportDto>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut report = ImportReportDto {
+        dry_run: options.dry_run,
+        ..Default::default()
+    };
+    let mut seen = SeenRows::default();
+    let mut headers = None;
+    let mut lines = reader.lines();
+    let mut line_number = 0;
+
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| anyhow!("Unable to read import ({})", e))?
+    {
+        line_number += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let row = match options.format {
+            BulkFormat::Csv => match &headers {
+                None => {
+                    headers = Some(parse_csv_record(&line)?);
+                    continue;
+                }
+                Some(headers) => parse_csv_record(&line).and_then(|record| {
+                    record
+                        .deserialize::<ImportUserRowDto>(Some(headers))
+                        .map_err(|e| anyhow!("{}", e))
+                }),
+            },
+            BulkFormat::Jsonl => {
+                serde_json::from_str::<ImportUserRowDto>(&line).map_err(|e| anyhow!("{}", e))
+            }
+        };
+
+        report.total += 1;
+        let outcome = match row {
+            Ok(row) => import_row(pool, config, org_id, row, options, &mut seen).await?,
+            Err(e) => Err(format!("Invalid row ({})", e)),
+        };
+
+        match outcome {
+            Ok(invite) => {
+                report.imported += 1;
+                report
+                    .invites
+                    .extend(invite.map(|(email, token)| ImportInviteDto {
+                        line: line_number,
+                        email,
+                        token,
+                    }));
+            }
+            Err(message) => {
+                report.failed += 1;
+                report.errors.push(ImportErrorDto {
+                    line: line_number,
+                    message,
+                });
+            }
+        }
+    }
+
+    tracing::info!(
+        "Imported {} of {} users ({} failed, dry run: {})",
+        report.imported,
+        report.total,
+        report.failed,
+        report.dry_run
+    );
+    Ok(report)
+}
+
+/// Validates and, unless this is a dry run, creates a single user.
+///
+/// The outer result carries database failures, which abort the import; the inner one
+/// carries the reason a row was rejected. An accepted row yields the email and token
+/// of the invite issued for it, if any.
+async fn import_row(
+    pool: &PgPool,
+    config: &AppConfig,
+    org_id: Option<Uuid>,
+    mut row: ImportUserRowDto,
+    options: &ImportUsersQueryDto,
+    seen: &mut SeenRows,
+) -> AppResult<Result<Option<(String, String)>, String>> {
+    row.username = row.username.trim().to_lowercase();
+    row.email = row.email.trim().to_lowercase();
+
+    if let Err(e) = row.validate() {
+        return Ok(Err(format!("{}", e)));
+    }
+
+    if !seen.usernames.insert(row.username.clone()) {
+        return Ok(Err("Duplicate username in import".to_string()));
+    }
+    if !seen.emails.insert(row.email.clone()) {
+        return Ok(Err("Duplicate email in import".to_string()));
+    }
+    if let Some(github_id) = row.github_id {
+        if !seen.github_ids.insert(github_id) {
+            return Ok(Err("Duplicate GitHub ID in import".to_string()));
+        }
+        if repositories::get_user_by_github_id(pool, github_id)
+            .await?
+            .is_some()
+        {
+            return Ok(Err("GitHub ID already exists".to_string()));
+        }
+    }
+
+    if find_user_by_username_or_email(pool, config, org_id, &row.username, &row.email)
+        .await?
+        .is_some()
+    {
+        return Ok(Err("User already exists".to_string()));
+    }
+
+    let password_hash = match row.password_hash.filter(|hash| !hash.is_empty()) {
+        Some(hash) if is_supported_hash(&hash) => Some(hash),
+        Some(_) => return Ok(Err("Unsupported password hash format".to_string())),
+        None if options.invite => None,
+        None => {
+            return Ok(Err(
+                "Missing password hash (import with invite to let the user set one)".to_string(),
+            ))
+        }
+    };
+
+    if options.dry_run {
+        return Ok(Ok(None));
+    }
+
+    let user = User::new(
+        row.github_id,
+        row.email,
+        password_hash.as_deref().unwrap_or(UNUSABLE_PASSWORD),
+        row.username,
+        row.avatar_url,
+    )
+    .with_org(org_id);
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Unable to begin transaction ({})", e))?;
+    let user = insert_user(&mut tx, &user).await?;
+    if let Some(org_id) = org_id {
+        repositories::upsert_membership(&mut *tx, &Membership::new(org_id, user.id, None)).await?;
+    }
+
+    let invite = if password_hash.is_some() {
+        insert_password_history(
+            &mut tx,
+            config.get_password_policy(),
+            user.id,
+            &user.password_hash,
+        )
+        .await?;
+        None
+    } else {
+        let duration = Duration::seconds(*config.get_user_import().get_invite_expiration_secs());
+        let token = create_user_invite(&mut *tx, user.id, duration).await?;
+        Some((user.email, token))
+    };
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Unable to commit imported user ({})", e))?;
+    Ok(Ok(invite))
+}
+
+fn parse_csv_record(line: &str) -> AppResult<csv::StringRecord> {
+    let mut record = csv::StringRecord::new();
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(line.as_bytes())
+        .read_record(&mut record)
+        .map_err(|e| anyhow!("Unable to parse CSV ({})", e))?;
+    Ok(record)
+}
+
+/// Streams all users, or only those of `org_id`, in creation order, page by page, without
+/// their password hashes.
+///
+/// Each item is one encoded page; CSV output starts with a header row.
+pub fn export_users(
+    pool: PgPool,
+    org_id: Option<Uuid>,
+    format: BulkFormat,
+) -> impl Stream<Item = AppResult<Bytes>> {
+    let cursor: Option<(DateTime<Utc>, Uuid)> = None;
+
+    stream::try_unfold(
+        (pool, cursor, true, false),
+        move |(pool, cursor, first, done)| async move {
+            if done {
+                return Ok(None);
+            }
+
+            let users =
+                repositories::get_users_after(&pool, org_id, cursor, EXPORT_PAGE_SIZE).await?;
+            let done = (users.len() as i64) < EXPORT_PAGE_SIZE;
+            let cursor = users.last().map(|user| (user.created_at, user.id));
+            let chunk = encode_users(users, format, first)?;
+
+            Ok(Some((chunk, (pool, cursor, false, done))))
+        },
+    )
+}
+
+fn encode_users(users: Vec<User>, format: BulkFormat, with_headers: bool) -> AppResult<Bytes> {
+    let rows = users.into_iter().map(ExportUserRowDto::from);
+
+    match format {
+        BulkFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(with_headers)
+                .from_writer(Vec::new());
+            for row in rows {
+                writer
+                    .serialize(row)
+                    .map_err(|e| anyhow!("Unable to encode user ({})", e))?;
+            }
+            writer
+                .into_inner()
+                .map(Bytes::from)
+                .map_err(|e| anyhow!("Unable to encode users ({})", e))
+        }
+        BulkFormat::Jsonl => {
+            let mut buffer = Vec::new();
+            for row in rows {
+                serde_json::to_writer(&mut buffer, &row)
+                    .map_err(|e| anyhow!("Unable to encode user ({})", e))?;
+                buffer.push(b'\n');
+            }
+            Ok(Bytes::from(buffer))
+        }
+    }
+}
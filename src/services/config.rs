@@ -0,0 +1,133 @@
+use std::net::IpAddr;
+
+use crate::{
+    bootstrap::{create_connection_pool, create_sqlite_store},
+    dto::ConfigCheckDto,
+    utils::{AppConfig, BreachedPasswords, DatabaseBackend, RateLimiter},
+};
+
+use super::get_migration_status;
+
+/// Length the cookie key is derived from; shorter secrets are rejected on startup.
+const MIN_COOKIE_SECRET_LENGTH: usize = 64;
+/// Length below which an HMAC secret is easier to brute-force than the hash it keys.
+const MIN_JWT_SECRET_LENGTH: usize = 32;
+
+/// Checks that the server can start with `config`: secrets are long enough, files and
+/// services it depends on can be reached, and the database schema is up to date.
+///
+/// ## Returns
+/// - `Vec<ConfigCheckDto>`: One result per section, failed or not.
+pub async fn check_config(config: &AppConfig) -> Vec<ConfigCheckDto> {
+    let mut checks = Vec::new();
+    let mut check = |name: &str, result: Result<String, String>| {
+        let (ok, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        };
+        checks.push(ConfigCheckDto {
+            name: name.to_string(),
+            ok,
+            detail,
+        });
+    };
+
+    let server = config.get_server();
+    check(
+        "server",
+        match server.get_host().parse::<IpAddr>() {
+            Ok(_) if server.get_cookie_secret().len() < MIN_COOKIE_SECRET_LENGTH => Err(format!(
+                "cookie_secret must be at least {} bytes long",
+                MIN_COOKIE_SECRET_LENGTH
+            )),
+            Ok(host) => Ok(format!("Listening on {}:{}", host, server.get_port())),
+            Err(e) => Err(format!("Invalid host {} ({})", server.get_host(), e)),
+        },
+    );
+
+    let secret = config.get_jwt().get_secret();
+    check(
+        "jwt",
+        if secret.len() < MIN_JWT_SECRET_LENGTH {
+            Err(format!(
+                "secret must be at least {} bytes long",
+                MIN_JWT_SECRET_LENGTH
+            ))
+        } else {
+            Ok("Secret set".to_string())
+        },
+    );
+
+    let policy = config.get_password_policy();
+    check(
+        "password_policy",
+        if policy.get_min_length() > policy.get_max_length() {
+            Err(format!(
+                "min_length {} exceeds max_length {}",
+                policy.get_min_length(),
+                policy.get_max_length()
+            ))
+        } else {
+            Ok(format!(
+                "Passwords of {} to {} characters",
+                policy.get_min_length(),
+                policy.get_max_length()
+            ))
+        },
+    );
+
+    check(
+        "breached_passwords",
+        match BreachedPasswords::from_config(config.get_breached_passwords()) {
+            Ok(Some(_)) => Ok("Corpus opened".to_string()),
+            Ok(None) => Ok("Disabled".to_string()),
+            Err(e) => Err(format!("{:#}", e)),
+        },
+    );
+
+    let rate_limiter = RateLimiter::from_config(config.get_rate_limit(), config.get_redis());
+    check(
+        "rate_limit",
+        match rate_limiter {
+            Ok(rate_limiter) if rate_limiter.is_shared().await => {
+                Ok("Counters shared through Redis".to_string())
+            }
+            Ok(_) => Ok("Redis unreachable, counters kept in memory".to_string()),
+            Err(e) => Err(format!("{:#}", e)),
+        },
+    );
+
+    let database = config.get_database();
+    let result = match database.get_backend() {
+        DatabaseBackend::Sqlite => create_sqlite_store(database)
+            .await
+            .map(|_| format!("SQLite database {} opened", database.get_url()))
+            .map_err(|e| format!("{:#}", e)),
+        DatabaseBackend::Postgres => match create_connection_pool(database).await {
+            Ok(pool) => match get_migration_status(&pool).await {
+                Ok(status) => {
+                    let pending = status.iter().filter(|migration| !migration.applied).count();
+                    let mismatched = status
+                        .iter()
+                        .filter(|migration| migration.checksum_mismatch)
+                        .count();
+                    if mismatched > 0 {
+                        Err(format!("{} applied migrations were modified", mismatched))
+                    } else if pending > 0 && !database.get_auto_migrate() {
+                        Err(format!(
+                            "{} pending migrations and auto_migrate is off",
+                            pending
+                        ))
+                    } else {
+                        Ok(format!("Connected, {} pending migrations", pending))
+                    }
+                }
+                Err(e) => Err(format!("{:#}", e)),
+            },
+            Err(e) => Err(format!("{:#}", e)),
+        },
+    };
+    check("database", result);
+
+    checks
+}
@@ -0,0 +1,27 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{models::Impersonation, repositories, utils::AppResult};
+
+pub async fn create_impersonation(
+    pool: &PgPool,
+    impersonation: &Impersonation,
+) -> AppResult<Impersonation> {
+    repositories::create_impersonation(pool, impersonation).await
+}
+
+pub async fn get_impersonation_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Impersonation>> {
+    repositories::get_impersonation_by_id(pool, id).await
+}
+
+pub async fn get_all_impersonations(
+    pool: &PgPool,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<Impersonation>> {
+    repositories::get_all_impersonations(pool, limit, offset).await
+}
+
+pub async fn end_impersonation(pool: &PgPool, id: Uuid) -> AppResult<()> {
+    repositories::end_impersonation(pool, id).await
+}
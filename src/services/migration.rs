@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use sqlx::{
+    migrate::{Migrate, Migrator},
+    PgConnection, PgPool,
+};
+
+use crate::{dto::MigrationStatusDto, repositories, utils::AppResult};
+
+/// The migrations under `migrations/`, embedded in the binary.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Applies every pending migration, waiting for any other instance migrating the same database
+/// to finish first.
+pub async fn run_migrations(pool: &PgPool) -> AppResult<()> {
+    let mut conn = lock_migrations(pool).await?;
+    let result = MIGRATOR
+        .run(&mut conn)
+        .await
+        .context("Failed to apply migrations");
+    repositories::unlock_migrations(&mut conn).await?;
+    result
+}
+
+/// Reverts the applied migrations newer than `target`, or the latest one when `target` is
+/// omitted.
+///
+/// ## Returns
+/// - `AppResult<Vec<i64>>`: The versions reverted, latest first.
+pub async fn revert_migrations(pool: &PgPool, target: Option<i64>) -> AppResult<Vec<i64>> {
+    let mut conn = lock_migrations(pool).await?;
+    let result = revert_locked(&mut conn, target).await;
+    repositories::unlock_migrations(&mut conn).await?;
+    result
+}
+
+/// Lists the embedded migrations, oldest first, along with whether they are applied.
+pub async fn get_migration_status(pool: &PgPool) -> AppResult<Vec<MigrationStatusDto>> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied: HashMap<_, _> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| (migration.version, migration.checksum))
+        .collect();
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| {
+            let checksum = applied.get(&migration.version);
+            MigrationStatusDto {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: checksum.is_some(),
+                checksum_mismatch: checksum.is_some_and(|checksum| *checksum != migration.checksum),
+            }
+        })
+        .collect())
+}
+
+/// Takes the migration lock on a connection of its own, detached from the pool so that the
+/// lock cannot outlive a failure: it is released when the connection drops.
+async fn lock_migrations(pool: &PgPool) -> AppResult<PgConnection> {
+    let mut conn = pool.acquire().await?.detach();
+    repositories::lock_migrations(&mut conn).await?;
+    Ok(conn)
+}
+
+async fn revert_locked(conn: &mut PgConnection, target: Option<i64>) -> AppResult<Vec<i64>> {
+    conn.ensure_migrations_table().await?;
+    let mut applied: Vec<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+    applied.sort_unstable();
+
+    let target = match target {
+        Some(target) => target,
+        None => applied.iter().rev().nth(1).copied().unwrap_or(0),
+    };
+    MIGRATOR
+        .undo(&mut *conn, target)
+        .await
+        .context("Failed to revert migrations")?;
+
+    Ok(applied
+        .into_iter()
+        .rev()
+        .take_while(|version| *version > target)
+        .collect())
+}
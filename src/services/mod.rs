@@ -1,5 +1,37 @@
+mod api_key;
+mod audit;
+mod bulk;
+mod config;
+mod impersonation;
+mod migration;
+mod organization;
+mod password;
+mod role;
 mod session;
 mod user;
+mod user_invite;
+mod webhook;
 
+pub use api_key::*;
+pub use audit::*;
+pub use bulk::*;
+pub use config::*;
+pub use impersonation::*;
+pub use migration::*;
+pub use organization::*;
+pub use password::*;
+pub use role::*;
 pub use session::*;
 pub use user::*;
+pub use user_invite::*;
+pub use webhook::*;
+
+use sha2::{Digest, Sha256};
+
+/// Hashes a bearer secret, such as an invite token or an API key, for storage and lookup.
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
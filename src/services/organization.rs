@@ -0,0 +1,100 @@
+use anyhow::anyhow;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{Membership, Organization, OutboxEvent, Permission, User},
+    repositories,
+    utils::AppResult,
+};
+
+use super::user_payload;
+
+pub async fn create_organization(
+    pool: &PgPool,
+    organization: &Organization,
+) -> AppResult<Organization> {
+    repositories::create_organization(pool, organization).await
+}
+
+pub async fn get_organization_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Organization>> {
+    repositories::get_organization_by_id(pool, id).await
+}
+
+pub async fn get_organization_by_slug(
+    pool: &PgPool,
+    slug: &str,
+) -> AppResult<Option<Organization>> {
+    repositories::get_organization_by_slug(pool, slug).await
+}
+
+pub async fn get_all_organizations(
+    pool: &PgPool,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<Organization>> {
+    repositories::get_all_organizations(pool, limit, offset).await
+}
+
+/// Deletes an organization along with its users, recording `user.deleted` in the outbox for
+/// each of them, in a single transaction.
+///
+/// ## Returns
+/// - `AppResult<Vec<User>>`: The users deleted with the organization.
+pub async fn delete_organization(pool: &PgPool, id: Uuid) -> AppResult<Vec<User>> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Unable to begin transaction ({})", e))?;
+
+    let users = repositories::delete_users_by_org_id(&mut *tx, id).await?;
+    for user in &users {
+        let event = OutboxEvent::new(OutboxEvent::USER_DELETED, user_payload(user));
+        repositories::create_outbox_event(&mut *tx, &event).await?;
+    }
+    repositories::delete_organization(&mut *tx, id).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Unable to commit organization deletion ({})", e))?;
+    Ok(users)
+}
+
+pub async fn get_membership(
+    pool: &PgPool,
+    org_id: Uuid,
+    user_id: Uuid,
+) -> AppResult<Option<Membership>> {
+    repositories::get_membership(pool, org_id, user_id).await
+}
+
+pub async fn get_memberships_by_org_id(pool: &PgPool, org_id: Uuid) -> AppResult<Vec<Membership>> {
+    repositories::get_memberships_by_org_id(pool, org_id).await
+}
+
+pub async fn upsert_membership(pool: &PgPool, membership: &Membership) -> AppResult<Membership> {
+    repositories::upsert_membership(pool, membership).await
+}
+
+pub async fn delete_membership(pool: &PgPool, org_id: Uuid, user_id: Uuid) -> AppResult<()> {
+    repositories::delete_membership(pool, org_id, user_id).await
+}
+
+/// Resolves the permissions to put in a token: the user's global roles outside an
+/// organization, or their membership role inside one, limited to [`Permission::ORG_SCOPED`].
+pub async fn resolve_permissions(
+    pool: &PgPool,
+    user_id: Uuid,
+    org_id: Option<Uuid>,
+) -> AppResult<Vec<String>> {
+    match org_id {
+        None => repositories::get_user_permissions(pool, user_id).await,
+        Some(org_id) => Ok(
+            repositories::get_membership_permissions(pool, org_id, user_id)
+                .await?
+                .into_iter()
+                .filter(|permission| Permission::ORG_SCOPED.contains(&permission.as_str()))
+                .collect(),
+        ),
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::anyhow;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    models::PasswordHistory,
+    repositories,
+    stores::UserStore,
+    utils::{
+        check_password, AppConfig, AppResult, BreachedPasswords, PasswordPolicy, PasswordRule,
+        PasswordViolation,
+    },
+};
+
+/// Evaluates a candidate password against the policy and the breach corpus, including
+/// reuse of the user's recent passwords when `user_id` refers to an existing account.
+pub async fn validate_password(
+    users: &dyn UserStore,
+    config: &AppConfig,
+    breached_passwords: Option<&BreachedPasswords>,
+    password: &str,
+    user_inputs: &[&str],
+    user_id: Option<Uuid>,
+) -> AppResult<Vec<PasswordViolation>> {
+    let policy = config.get_password_policy();
+    let mut violations = policy.check(password, user_inputs);
+
+    if let Some(breached_passwords) = breached_passwords {
+        if let Some(occurrences) = breached_passwords.check(password)? {
+            violations.push(PasswordViolation::breached(occurrences));
+        }
+    }
+
+    let history_size = *policy.get_history_size();
+    if let (Some(user_id), true) = (user_id, history_size > 0) {
+        for entry in users.get_password_history(user_id, history_size).await? {
+            if check_password(
+                password,
+                &entry.password_hash,
+                config.get_password_hashing(),
+            )? {
+                violations.push(PasswordViolation::new(
+                    PasswordRule::History,
+                    format!(
+                        "Password must not match any of your last {} passwords",
+                        history_size
+                    ),
+                ));
+                break;
+            }
+        }
+    }
+
+    Ok(violations)
+}
+
+/// Records a newly set password hash and trims the history to the configured size.
+pub async fn record_password_history(
+    pool: &PgPool,
+    policy: &PasswordPolicy,
+    user_id: Uuid,
+    password_hash: &str,
+) -> AppResult<()> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| anyhow!("Unable to acquire connection ({})", e))?;
+    insert_password_history(&mut conn, policy, user_id, password_hash).await
+}
+
+/// Records a newly set password hash through `conn`, as [`record_password_history`] does.
+pub async fn insert_password_history(
+    conn: &mut PgConnection,
+    policy: &PasswordPolicy,
+    user_id: Uuid,
+    password_hash: &str,
+) -> AppResult<()> {
+    let history_size = *policy.get_history_size();
+    if history_size > 0 {
+        repositories::create_password_history(
+            &mut *conn,
+            &PasswordHistory::new(user_id, password_hash),
+        )
+        .await?;
+    }
+    repositories::prune_password_history(&mut *conn, user_id, history_size).await
+}
@@ -0,0 +1,152 @@
+use anyhow::{anyhow, bail};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{Permission, Role, User},
+    repositories,
+    utils::{hash_password, AppConfig, AppResult},
+};
+
+use super::{create_user, record_password_history};
+
+pub async fn create_role(pool: &PgPool, role: &Role) -> AppResult<Role> {
+    repositories::create_role(pool, role).await
+}
+
+pub async fn get_role_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Role>> {
+    repositories::get_role_by_id(pool, id).await
+}
+
+pub async fn get_role_by_name(pool: &PgPool, name: &str) -> AppResult<Option<Role>> {
+    repositories::get_role_by_name(pool, name).await
+}
+
+pub async fn get_all_roles(pool: &PgPool) -> AppResult<Vec<Role>> {
+    repositories::get_all_roles(pool).await
+}
+
+pub async fn update_role(pool: &PgPool, role: &Role) -> AppResult<Role> {
+    repositories::update_role(pool, role).await
+}
+
+pub async fn delete_role(pool: &PgPool, id: Uuid) -> AppResult<()> {
+    repositories::delete_role(pool, id).await
+}
+
+pub async fn get_all_permissions(pool: &PgPool) -> AppResult<Vec<Permission>> {
+    repositories::get_all_permissions(pool).await
+}
+
+pub async fn get_role_permissions(pool: &PgPool, role_id: Uuid) -> AppResult<Vec<String>> {
+    repositories::get_role_permissions(pool, role_id).await
+}
+
+pub async fn set_role_permissions(
+    pool: &PgPool,
+    role_id: Uuid,
+    permissions: &[String],
+) -> AppResult<()> {
+    repositories::set_role_permissions(pool, role_id, permissions).await
+}
+
+pub async fn get_user_permissions(pool: &PgPool, user_id: Uuid) -> AppResult<Vec<String>> {
+    repositories::get_user_permissions(pool, user_id).await
+}
+
+pub async fn get_user_roles(pool: &PgPool, user_id: Uuid) -> AppResult<Vec<Role>> {
+    repositories::get_user_roles(pool, user_id).await
+}
+
+/// Grants a role to a user, keeping `users.is_admin` in sync for the admin role.
+pub async fn assign_user_role(pool: &PgPool, user_id: Uuid, role: &Role) -> AppResult<()> {
+    repositories::assign_user_role(pool, user_id, role.id).await?;
+    if role.is_admin() {
+        repositories::sync_user_is_admin(pool, user_id).await?;
+    }
+    Ok(())
+}
+
+/// Removes a role from a user. The admin role is never removed from its last holder,
+/// in which case `false` is returned.
+pub async fn revoke_user_role(pool: &PgPool, user_id: Uuid, role: &Role) -> AppResult<bool> {
+    let revoked = repositories::revoke_user_role(pool, user_id, role.id, role.is_admin()).await?;
+    if revoked && role.is_admin() {
+        repositories::sync_user_is_admin(pool, user_id).await?;
+    }
+    Ok(revoked)
+}
+
+/// Checks whether the user is the only holder of the admin role.
+pub async fn is_last_admin(pool: &PgPool, user_id: Uuid) -> AppResult<bool> {
+    let Some(admin) = repositories::get_role_by_name(pool, Role::ADMIN).await? else {
+        return Ok(false);
+    };
+
+    let holds_admin = repositories::get_user_roles(pool, user_id)
+        .await?
+        .iter()
+        .any(Role::is_admin);
+    Ok(holds_admin && repositories::count_users_with_role(pool, admin.id).await? == 1)
+}
+
+/// Creates or promotes the configured initial admin when no user holds the admin role yet.
+///
+/// Does nothing unless `bootstrap.admin_email` is set. An existing user with that email is
+/// promoted; otherwise a new user is created with `bootstrap.admin_password`, which must
+/// satisfy the password policy.
+pub async fn bootstrap_admin(pool: &PgPool, config: &AppConfig) -> AppResult<()> {
+    let bootstrap = config.get_bootstrap();
+    let email = bootstrap.get_admin_email().to_lowercase();
+    if email.is_empty() {
+        return Ok(());
+    }
+
+    let admin = repositories::get_role_by_name(pool, Role::ADMIN)
+        .await?
+        .ok_or_else(|| anyhow!("The built-in admin role is missing"))?;
+    if repositories::count_users_with_role(pool, admin.id).await? > 0 {
+        tracing::debug!("Skipping admin bootstrap: an admin already exists");
+        return Ok(());
+    }
+
+    let user = match repositories::get_user_by_email_in_org(pool, None, &email).await? {
+        Some(user) => user,
+        None => {
+            let username = match bootstrap.get_admin_username().to_lowercase() {
+                username if username.is_empty() => Role::ADMIN.to_string(),
+                username => username,
+            };
+            let password = bootstrap.get_admin_password();
+            let violations = config
+                .get_password_policy()
+                .check(password, &[&username, &email]);
+            if !violations.is_empty() {
+                bail!(
+                    "Bootstrap admin password does not meet the password policy: {}",
+                    violations
+                        .iter()
+                        .map(|violation| violation.message.as_str())
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                );
+            }
+
+            let password_hash = hash_password(password, config.get_password_hashing())?;
+            let user = User::new(None, email, password_hash, username, None);
+            let user = create_user(pool, &user).await?;
+            record_password_history(
+                pool,
+                config.get_password_policy(),
+                user.id,
+                &user.password_hash,
+            )
+            .await?;
+            user
+        }
+    };
+
+    assign_user_role(pool, user.id, &admin).await?;
+    tracing::info!("Granted the admin role to bootstrap user {}", user.username);
+    Ok(())
+}
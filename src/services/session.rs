@@ -1,7 +1,16 @@
+use std::{future::Future, sync::Arc, time::Duration as StdDuration};
+
+use chrono::{DateTime, Duration, Utc};
 use sqlx::PgPool;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-use crate::{models::Session, repositories, utils::AppResult};
+use crate::{
+    models::Session,
+    repositories,
+    stores::SessionStore,
+    utils::{AppResult, SessionReaperConfig},
+};
 
 pub async fn create_session(pool: &PgPool, session: &Session) -> AppResult<Session> {
     repositories::create_session(pool, session).await
@@ -15,6 +24,153 @@ pub async fn revoke_session(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
     repositories::revoke_session(pool, user_id).await
 }
 
+pub async fn touch_session(pool: &PgPool, session: &Session) -> AppResult<()> {
+    repositories::touch_session(pool, session).await
+}
+
+pub async fn revoke_org_sessions(pool: &PgPool, org_id: Uuid) -> AppResult<()> {
+    repositories::revoke_org_sessions(pool, org_id).await
+}
+
+pub async fn revoke_all_sessions(pool: &PgPool) -> AppResult<()> {
+    repositories::revoke_all_sessions(pool).await
+}
+
 pub async fn delete_session_by_user_id(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
     repositories::delete_session_by_user_id(pool, user_id).await
 }
+
+/// Deletes, in batches of `batch_size`, the sessions that have expired, were revoked or
+/// have gone unused for longer than `idle_timeout`, returning how many were deleted.
+pub async fn purge_expired_sessions(
+    pool: &PgPool,
+    idle_timeout: Duration,
+    batch_size: i64,
+) -> AppResult<u64> {
+    let now = Utc::now();
+    delete_in_batches(batch_size, &CancellationToken::new(), |limit| {
+        repositories::delete_stale_sessions(pool, now, idle_before(now, idle_timeout), limit)
+    })
+    .await
+}
+
+/// Deletes, in batches of `batch_size`, the sessions that expired, were revoked or went idle
+/// for longer than `idle_timeout` before `before`, until none are left or `shutdown` is
+/// cancelled.
+///
+/// ## Returns
+/// - `AppResult<u64>`: The number of sessions deleted.
+pub async fn reap_sessions(
+    sessions: &dyn SessionStore,
+    before: DateTime<Utc>,
+    idle_timeout: Duration,
+    batch_size: i64,
+    shutdown: &CancellationToken,
+) -> AppResult<u64> {
+    delete_in_batches(batch_size, shutdown, |limit| {
+        sessions.delete_stale_sessions(before, idle_before(before, idle_timeout), limit)
+    })
+    .await
+}
+
+/// The time before which a session must have last been used to have gone idle by `before`;
+/// none while the idle timeout is disabled.
+fn idle_before(before: DateTime<Utc>, idle_timeout: Duration) -> Option<DateTime<Utc>> {
+    (idle_timeout > Duration::zero()).then(|| before - idle_timeout)
+}
+
+/// Deletes stale sessions every interval, along with expired invites and API keys and ended
+/// impersonations when `pool` is given, until `shutdown` is cancelled.
+///
+/// ## Parameters
+/// - `sessions`: Where sessions are kept.
+/// - `pool`: The Postgres pool invites, API keys and impersonations are kept in, unless
+///   users are kept elsewhere.
+/// - `config`: The interval, batch size and retention window.
+/// - `idle_timeout`: How long sessions may go unused; zero disables the timeout.
+/// - `shutdown`: Cancelled when the server shuts down; a batch in flight is completed.
+pub async fn run_session_reaper(
+    sessions: Arc<dyn SessionStore>,
+    pool: Option<PgPool>,
+    config: SessionReaperConfig,
+    idle_timeout: Duration,
+    shutdown: CancellationToken,
+) {
+    let retention = Duration::seconds(*config.get_retention_secs());
+    let batch_size = *config.get_batch_size();
+    let interval = StdDuration::from_secs((*config.get_interval_secs()).max(1));
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+
+        let before = Utc::now() - retention;
+        match reap_sessions(
+            sessions.as_ref(),
+            before,
+            idle_timeout,
+            batch_size,
+            &shutdown,
+        )
+        .await
+        {
+            Ok(0) => {}
+            Ok(deleted) => tracing::info!("Reaped {} stale sessions", deleted),
+            Err(e) => tracing::error!("Failed to reap stale sessions: {}", e),
+        }
+
+        if let Some(pool) = &pool {
+            let reaped = delete_in_batches(batch_size, &shutdown, |limit| {
+                repositories::delete_expired_user_invites(pool, before, limit)
+            })
+            .await;
+            log_reaped(reaped, "expired invites");
+
+            let reaped = delete_in_batches(batch_size, &shutdown, |limit| {
+                repositories::delete_expired_api_keys(pool, before, limit)
+            })
+            .await;
+            log_reaped(reaped, "expired API keys");
+
+            let reaped = delete_in_batches(batch_size, &shutdown, |limit| {
+                repositories::delete_ended_impersonations(pool, before, limit)
+            })
+            .await;
+            log_reaped(reaped, "ended impersonations");
+        }
+    }
+    tracing::info!("Session reaper stopped");
+}
+
+fn log_reaped(reaped: AppResult<u64>, what: &str) {
+    match reaped {
+        Ok(0) => {}
+        Ok(deleted) => tracing::info!("Reaped {} {}", deleted, what),
+        Err(e) => tracing::error!("Failed to reap {}: {}", what, e),
+    }
+}
+
+/// Runs `delete` with a limit of `batch_size` until it deletes fewer rows than that or
+/// `shutdown` is cancelled, and returns the total deleted.
+async fn delete_in_batches<F, Fut>(
+    batch_size: i64,
+    shutdown: &CancellationToken,
+    mut delete: F,
+) -> AppResult<u64>
+where
+    F: FnMut(i64) -> Fut,
+    Fut: Future<Output = AppResult<u64>>,
+{
+    let batch_size = batch_size.max(1);
+    let mut deleted = 0;
+    while !shutdown.is_cancelled() {
+        let batch = delete(batch_size).await?;
+        deleted += batch;
+        if batch < batch_size as u64 {
+            break;
+        }
+    }
+    Ok(deleted)
+}
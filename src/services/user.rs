@@ -1,10 +1,36 @@
-use sqlx::PgPool;
+use anyhow::anyhow;
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
-use crate::{models::User, repositories, utils::AppResult};
+use crate::{
+    models::{OutboxEvent, User},
+    repositories,
+    utils::{AppConfig, AppResult},
+};
 
+/// Creates a user and records `user.created` in the outbox, in a single transaction.
 pub async fn create_user(pool: &PgPool, user: &User) -> AppResult<User> {
-    repositories::create_user(pool, user).await
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Unable to begin transaction ({})", e))?;
+
+    let user = insert_user(&mut tx, user).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Unable to commit user ({})", e))?;
+    Ok(user)
+}
+
+/// Creates a user and records `user.created` in the outbox through `conn`, which should
+/// be a transaction.
+pub async fn insert_user(conn: &mut PgConnection, user: &User) -> AppResult<User> {
+    let user = repositories::create_user(&mut *conn, user).await?;
+    let event = OutboxEvent::new(OutboxEvent::USER_CREATED, user_payload(&user));
+    repositories::create_outbox_event(&mut *conn, &event).await?;
+    Ok(user)
 }
 
 pub async fn get_user_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<User>> {
@@ -27,18 +53,119 @@ pub async fn get_user_by_username_or_email(
     repositories::get_user_by_username_or_email(pool, username, email).await
 }
 
-pub async fn get_all_users(pool: &PgPool, limit: i64, offset: i64) -> AppResult<Vec<User>> {
-    repositories::get_all_users(pool, limit, offset).await
+pub async fn get_all_users(
+    pool: &PgPool,
+    org_id: Option<Uuid>,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<User>> {
+    repositories::get_all_users(pool, org_id, limit, offset).await
+}
+
+/// Looks up a user by username in the namespace where usernames must be unique: the
+/// organization when `organizations.unique_per_org` is set, otherwise the whole deployment.
+pub async fn find_user_by_username(
+    pool: &PgPool,
+    config: &AppConfig,
+    org_id: Option<Uuid>,
+    username: &str,
+) -> AppResult<Option<User>> {
+    if *config.get_organizations().get_unique_per_org() {
+        repositories::get_user_by_username_in_org(pool, org_id, username).await
+    } else {
+        repositories::get_user_by_username(pool, username).await
+    }
+}
+
+/// Looks up a user by email in the namespace where emails must be unique; see
+/// [`find_user_by_username`].
+pub async fn find_user_by_email(
+    pool: &PgPool,
+    config: &AppConfig,
+    org_id: Option<Uuid>,
+    email: &str,
+) -> AppResult<Option<User>> {
+    if *config.get_organizations().get_unique_per_org() {
+        repositories::get_user_by_email_in_org(pool, org_id, email).await
+    } else {
+        repositories::get_user_by_email(pool, email).await
+    }
+}
+
+/// Looks up a user by username or email in the namespace where both must be unique; see
+/// [`find_user_by_username`].
+pub async fn find_user_by_username_or_email(
+    pool: &PgPool,
+    config: &AppConfig,
+    org_id: Option<Uuid>,
+    username: &str,
+    email: &str,
+) -> AppResult<Option<User>> {
+    if *config.get_organizations().get_unique_per_org() {
+        repositories::get_user_by_username_or_email_in_org(pool, org_id, username, email).await
+    } else {
+        repositories::get_user_by_username_or_email(pool, username, email).await
+    }
 }
 
+/// Updates a user and, if the email changed, records `user.email_changed` in the outbox, in a
+/// single transaction.
 pub async fn update_user(pool: &PgPool, user: &User) -> AppResult<User> {
-    repositories::update_user(pool, user).await
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Unable to begin transaction ({})", e))?;
+
+    let previous_email = repositories::get_user_email_for_update(&mut *tx, user.id).await?;
+    let user = repositories::update_user(&mut *tx, user).await?;
+    if let Some(previous_email) = previous_email.filter(|email| *email != user.email) {
+        let mut payload = user_payload(&user);
+        payload["previousEmail"] = json!(previous_email);
+        let event = OutboxEvent::new(OutboxEvent::USER_EMAIL_CHANGED, payload);
+        repositories::create_outbox_event(&mut *tx, &event).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Unable to commit user ({})", e))?;
+    Ok(user)
 }
 
+pub async fn update_user_password_hash(
+    pool: &PgPool,
+    id: Uuid,
+    password_hash: &str,
+) -> AppResult<()> {
+    repositories::update_user_password_hash(pool, id, password_hash).await
+}
+
+/// Deletes a user and records `user.deleted` in the outbox, in a single transaction.
 pub async fn delete_user(pool: &PgPool, id: Uuid) -> AppResult<()> {
-    repositories::delete_user(pool, id).await
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Unable to begin transaction ({})", e))?;
+
+    if let Some(user) = repositories::delete_user(&mut *tx, id).await? {
+        let event = OutboxEvent::new(OutboxEvent::USER_DELETED, user_payload(&user));
+        repositories::create_outbox_event(&mut *tx, &event).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Unable to commit user deletion ({})", e))
 }
 
 pub async fn get_user_by_github_id(pool: &PgPool, github_id: i64) -> AppResult<Option<User>> {
     repositories::get_user_by_github_id(pool, github_id).await
 }
+
+/// The user data sent to webhook subscribers, without any credential.
+pub(super) fn user_payload(user: &User) -> serde_json::Value {
+    json!({
+        "userId": user.id,
+        "username": user.username,
+        "email": user.email,
+        "orgId": user.org_id,
+    })
+}
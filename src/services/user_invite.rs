@@ -0,0 +1,35 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Duration;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::{models::UserInvite, repositories, utils::AppResult};
+
+use super::hash_token;
+
+/// Issues an invite for `user_id` and returns the token, which is only stored hashed.
+pub async fn create_user_invite<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    duration: Duration,
+) -> AppResult<String> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = URL_SAFE_NO_PAD.encode(bytes);
+
+    repositories::create_user_invite(
+        executor,
+        &UserInvite::new(user_id, hash_token(&token), duration),
+    )
+    .await?;
+    Ok(token)
+}
+
+pub async fn get_user_invite_by_token(pool: &PgPool, token: &str) -> AppResult<Option<UserInvite>> {
+    repositories::get_user_invite_by_token_hash(pool, &hash_token(token)).await
+}
+
+pub async fn delete_user_invites(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
+    repositories::delete_user_invites(pool, user_id).await
+}
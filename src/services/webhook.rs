@@ -0,0 +1,228 @@
+use std::time::Duration as StdDuration;
+
+use anyhow::{anyhow, Context};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{WebhookDelivery, WebhookSubscription},
+    repositories,
+    utils::{AppResult, WebhooksConfig},
+};
+
+/// Events fanned out, and deliveries attempted, per dispatcher pass.
+const BATCH_SIZE: i64 = 100;
+
+/// Deliveries claimed at a time. They are attempted one after another, so the lease of a
+/// claim must outlast this many request timeouts.
+const CLAIM_SIZE: i64 = 10;
+
+/// Creates a subscription with a freshly generated signing secret.
+pub async fn create_webhook_subscription(
+    pool: &PgPool,
+    url: &str,
+    event_types: Vec<String>,
+) -> AppResult<WebhookSubscription> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let secret = format!(
+        "{}{}",
+        WebhookSubscription::SECRET_PREFIX,
+        URL_SAFE_NO_PAD.encode(bytes)
+    );
+
+    repositories::create_webhook_subscription(
+        pool,
+        &WebhookSubscription::new(url, secret, event_types),
+    )
+    .await
+}
+
+pub async fn get_webhook_subscriptions(pool: &PgPool) -> AppResult<Vec<WebhookSubscription>> {
+    repositories::get_webhook_subscriptions(pool).await
+}
+
+pub async fn get_webhook_subscription_by_id(
+    pool: &PgPool,
+    id: Uuid,
+) -> AppResult<Option<WebhookSubscription>> {
+    repositories::get_webhook_subscription_by_id(pool, id).await
+}
+
+pub async fn update_webhook_subscription(
+    pool: &PgPool,
+    subscription: &WebhookSubscription,
+) -> AppResult<WebhookSubscription> {
+    repositories::update_webhook_subscription(pool, subscription).await
+}
+
+pub async fn delete_webhook_subscription(pool: &PgPool, id: Uuid) -> AppResult<bool> {
+    repositories::delete_webhook_subscription(pool, id).await
+}
+
+pub async fn get_dead_webhook_deliveries(
+    pool: &PgPool,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<WebhookDelivery>> {
+    repositories::get_webhook_deliveries_by_status(pool, WebhookDelivery::DEAD, limit, offset).await
+}
+
+pub async fn retry_webhook_delivery(pool: &PgPool, id: Uuid) -> AppResult<bool> {
+    repositories::retry_webhook_delivery(pool, id).await
+}
+
+/// Signs a webhook body for `secret`, binding it to `timestamp` so that a captured delivery
+/// cannot be replayed later.
+///
+/// ## Returns
+/// The value of the `X-Webhook-Signature` header: `sha256=` followed by the hex-encoded
+/// HMAC-SHA256 of `{timestamp}.{body}`.
+pub fn sign_webhook(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    let signature: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256={}", signature)
+}
+
+/// Fans new outbox events out to the subscriptions, then attempts the deliveries that are due.
+///
+/// ## Returns
+/// The number of deliveries attempted.
+pub async fn dispatch_webhooks(
+    pool: &PgPool,
+    client: &reqwest::Client,
+    config: &WebhooksConfig,
+) -> AppResult<usize> {
+    repositories::dispatch_outbox_events(pool, BATCH_SIZE).await?;
+
+    // Claimed deliveries are left alone by other dispatchers until every attempt of the
+    // claim has timed out, with one timeout to spare
+    let lease_secs = *config.get_timeout_secs() as i64 * (CLAIM_SIZE + 1);
+    let mut attempted = 0;
+    while attempted < BATCH_SIZE {
+        let deliveries =
+            repositories::claim_due_webhook_deliveries(pool, CLAIM_SIZE, lease_secs).await?;
+        for delivery in &deliveries {
+            attempt(pool, client, config, delivery).await?;
+        }
+        attempted += deliveries.len() as i64;
+        if (deliveries.len() as i64) < CLAIM_SIZE {
+            break;
+        }
+    }
+    Ok(attempted as usize)
+}
+
+/// Attempts a claimed delivery, then records it as delivered or schedules its retry.
+async fn attempt(
+    pool: &PgPool,
+    client: &reqwest::Client,
+    config: &WebhooksConfig,
+    delivery: &WebhookDelivery,
+) -> AppResult<()> {
+    match deliver(pool, client, delivery).await {
+        Ok(()) => repositories::mark_webhook_delivered(pool, delivery.id).await,
+        Err(e) => {
+            let attempts = delivery.attempts + 1;
+            let next_attempt_at = (attempts < *config.get_max_attempts())
+                .then(|| Utc::now() + backoff(config, attempts));
+            tracing::warn!(
+                "Webhook delivery {} failed (attempt {}): {}",
+                delivery.id,
+                attempts,
+                e
+            );
+            repositories::mark_webhook_failed(pool, delivery.id, &e.to_string(), next_attempt_at)
+                .await
+        }
+    }
+}
+
+/// Dispatches webhooks every poll interval, for as long as the server runs.
+pub async fn run_webhook_dispatcher(pool: PgPool, config: WebhooksConfig) {
+    let client = match reqwest::Client::builder()
+        .timeout(StdDuration::from_secs(*config.get_timeout_secs()))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Failed to start the webhook dispatcher: {}", e);
+            return;
+        }
+    };
+
+    let mut ticker =
+        tokio::time::interval(StdDuration::from_secs(*config.get_poll_interval_secs()));
+    loop {
+        ticker.tick().await;
+        if let Err(e) = dispatch_webhooks(&pool, &client, &config).await {
+            tracing::error!("Failed to dispatch webhooks: {}", e);
+        }
+    }
+}
+
+async fn deliver(
+    pool: &PgPool,
+    client: &reqwest::Client,
+    delivery: &WebhookDelivery,
+) -> AppResult<()> {
+    let subscription = repositories::get_webhook_subscription_by_id(pool, delivery.subscription_id)
+        .await?
+        .ok_or_else(|| anyhow!("Subscription not found"))?;
+    let event = repositories::get_outbox_event_by_id(pool, delivery.event_id)
+        .await?
+        .ok_or_else(|| anyhow!("Event not found"))?;
+
+    let body = json!({
+        "id": event.id,
+        "type": event.event_type,
+        "createdAt": event.created_at,
+        "data": event.payload,
+    })
+    .to_string();
+    let timestamp = Utc::now().timestamp();
+
+    let res = client
+        .post(&subscription.url)
+        .header("Content-Type", "application/json")
+        .header("X-Webhook-Id", event.id.to_string())
+        .header("X-Webhook-Event", &event.event_type)
+        .header("X-Webhook-Timestamp", timestamp.to_string())
+        .header(
+            "X-Webhook-Signature",
+            sign_webhook(&subscription.secret, timestamp, &body),
+        )
+        .body(body)
+        .send()
+        .await
+        .context("Request failed")?;
+
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(anyhow!("Endpoint responded with {}", res.status()))
+    }
+}
+
+/// The delay before the next attempt after `attempts` failed ones.
+fn backoff(config: &WebhooksConfig, attempts: i32) -> Duration {
+    let exponent = (attempts - 1).clamp(0, 30) as u32;
+    let secs = config
+        .get_backoff_base_secs()
+        .saturating_mul(1 << exponent)
+        .min(*config.get_backoff_max_secs());
+    Duration::seconds(secs)
+}
@@ -0,0 +1,483 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use anyhow::anyhow;
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    models::{AuditEvent, PasswordHistory, Permission, Role, Session, User},
+    utils::{AppConfig, AppResult, PasswordPolicy},
+};
+
+use super::{AuditStore, SessionStore, UserStore};
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Stores keeping everything in memory, for tests and demos that run without a database.
+///
+/// Nothing survives a restart. Only the built-in `admin` and `org_admin` roles exist, seeded
+/// as the migrations seed them, and are granted with [`MemoryStore::assign_user_role`] and
+/// [`MemoryStore::assign_member_role`]. Audit events are neither chained nor published to
+/// webhooks.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    data: RwLock<MemoryData>,
+}
+
+#[derive(Debug)]
+struct MemoryData {
+    users: HashMap<Uuid, User>,
+    sessions: HashMap<Uuid, Session>,
+    password_history: Vec<PasswordHistory>,
+    audit_events: Vec<AuditEvent>,
+    /// The permissions of each role, by role name.
+    roles: HashMap<&'static str, &'static [&'static str]>,
+    /// The roles held by each user outside any organization.
+    user_roles: HashMap<Uuid, HashSet<&'static str>>,
+    /// The role of each member inside an organization, by organization and user.
+    member_roles: HashMap<(Uuid, Uuid), &'static str>,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grants `role`, which must be a built-in role, to a user outside any organization.
+    pub fn assign_user_role(&self, user_id: Uuid, role: &str) -> AppResult<()> {
+        let mut data = self.write();
+        let role = data.find_role(role)?;
+        if !data.users.contains_key(&user_id) {
+            return Err(anyhow!("Unable to assign role (no user {})", user_id));
+        }
+        data.user_roles.entry(user_id).or_default().insert(role);
+        Ok(())
+    }
+
+    /// Grants `role`, which must be a built-in role, to a user inside `org_id`, replacing the
+    /// role they held there.
+    pub fn assign_member_role(&self, org_id: Uuid, user_id: Uuid, role: &str) -> AppResult<()> {
+        let mut data = self.write();
+        let role = data.find_role(role)?;
+        if !data.users.contains_key(&user_id) {
+            return Err(anyhow!("Unable to assign role (no user {})", user_id));
+        }
+        data.member_roles.insert((org_id, user_id), role);
+        Ok(())
+    }
+
+    /// Returns the recorded audit events, oldest first.
+    pub fn audit_events(&self) -> Vec<AuditEvent> {
+        self.read().audit_events.clone()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, MemoryData> {
+        self.data.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, MemoryData> {
+        self.data.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for MemoryData {
+    fn default() -> Self {
+        Self {
+            users: HashMap::new(),
+            sessions: HashMap::new(),
+            password_history: Vec::new(),
+            audit_events: Vec::new(),
+            roles: HashMap::from([
+                (Role::ADMIN, Permission::ALL),
+                (Role::ORG_ADMIN, Permission::ORG_SCOPED),
+            ]),
+            user_roles: HashMap::new(),
+            member_roles: HashMap::new(),
+        }
+    }
+}
+
+impl MemoryData {
+    fn find_role(&self, name: &str) -> AppResult<&'static str> {
+        self.roles
+            .get_key_value(name)
+            .map(|(name, _)| *name)
+            .ok_or_else(|| anyhow!("Unable to assign role (no role {})", name))
+    }
+
+    fn holds_admin(&self, user_id: &Uuid) -> bool {
+        self.user_roles
+            .get(user_id)
+            .is_some_and(|roles| roles.contains(Role::ADMIN))
+    }
+
+    /// Looks up a user in the namespace of `org_id` when usernames and emails are unique per
+    /// organization, otherwise among every user.
+    fn find_user(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        matches: impl Fn(&User) -> bool,
+    ) -> Option<User> {
+        let unique_per_org = *config.get_organizations().get_unique_per_org();
+        self.users
+            .values()
+            .find(|user| (!unique_per_org || user.org_id == org_id) && matches(user))
+            .cloned()
+    }
+
+    /// Enforces the uniqueness the database enforces with its indexes.
+    fn ensure_unique(&self, user: &User) -> AppResult<()> {
+        let conflict = self.users.values().any(|other| {
+            other.id != user.id
+                && other.org_id == user.org_id
+                && (other.username == user.username
+                    || other.email == user.email
+                    || (user.github_id.is_some() && other.github_id == user.github_id))
+        });
+        if conflict {
+            return Err(anyhow!("User {} already exists", user.username));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserStore for MemoryStore {
+    async fn create_user(&self, user: &User) -> AppResult<User> {
+        let mut data = self.write();
+        if data.users.contains_key(&user.id) {
+            return Err(anyhow!(
+                "Unable to create user ({} already exists)",
+                user.id
+            ));
+        }
+        data.ensure_unique(user)
+            .map_err(|e| anyhow!("Unable to create user ({})", e))?;
+        data.users.insert(user.id, user.clone());
+        Ok(user.clone())
+    }
+
+    async fn get_user_by_id(&self, id: Uuid) -> AppResult<Option<User>> {
+        Ok(self.read().users.get(&id).cloned())
+    }
+
+    async fn get_user_by_github_id(&self, github_id: i64) -> AppResult<Option<User>> {
+        Ok(self
+            .read()
+            .users
+            .values()
+            .find(|user| user.github_id == Some(github_id))
+            .cloned())
+    }
+
+    async fn find_user_by_username(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        username: &str,
+    ) -> AppResult<Option<User>> {
+        Ok(self
+            .read()
+            .find_user(config, org_id, |user| user.username == username))
+    }
+
+    async fn find_user_by_email(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        email: &str,
+    ) -> AppResult<Option<User>> {
+        Ok(self
+            .read()
+            .find_user(config, org_id, |user| user.email == email))
+    }
+
+    async fn find_user_by_username_or_email(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        username: &str,
+        email: &str,
+    ) -> AppResult<Option<User>> {
+        Ok(self.read().find_user(config, org_id, |user| {
+            user.username == username || user.email == email
+        }))
+    }
+
+    async fn get_all_users(
+        &self,
+        org_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<User>> {
+        let data = self.read();
+        let mut users: Vec<&User> = data
+            .users
+            .values()
+            .filter(|user| org_id.is_none() || user.org_id == org_id)
+            .collect();
+        users.sort_by_key(|user| Reverse(user.created_at));
+        Ok(users
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn update_user(&self, user: &User) -> AppResult<User> {
+        let mut data = self.write();
+        if !data.users.contains_key(&user.id) {
+            return Err(anyhow!("Unable to update user (no user {})", user.id));
+        }
+        data.ensure_unique(user)
+            .map_err(|e| anyhow!("Unable to update user ({})", e))?;
+
+        let mut user = user.clone();
+        user.updated_at = Utc::now();
+        data.users.insert(user.id, user.clone());
+        Ok(user)
+    }
+
+    async fn update_user_password_hash(&self, id: Uuid, password_hash: &str) -> AppResult<()> {
+        if let Some(user) = self.write().users.get_mut(&id) {
+            user.password_hash = password_hash.to_string();
+            user.updated_at = Utc::now();
+        }
+        Ok(())
+    }
+
+    async fn delete_user(&self, id: Uuid) -> AppResult<()> {
+        let mut data = self.write();
+        data.users.remove(&id);
+        data.sessions.retain(|_, session| session.user_id != id);
+        data.password_history.retain(|entry| entry.user_id != id);
+        data.user_roles.remove(&id);
+        data.member_roles.retain(|(_, user_id), _| *user_id != id);
+        Ok(())
+    }
+
+    async fn get_password_history(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> AppResult<Vec<PasswordHistory>> {
+        let data = self.read();
+        Ok(data
+            .password_history
+            .iter()
+            .rev()
+            .filter(|entry| entry.user_id == user_id)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn record_password_history(
+        &self,
+        policy: &PasswordPolicy,
+        user_id: Uuid,
+        password_hash: &str,
+    ) -> AppResult<()> {
+        let history_size = (*policy.get_history_size()).max(0) as usize;
+        let mut data = self.write();
+        if history_size > 0 {
+            data.password_history
+                .push(PasswordHistory::new(user_id, password_hash));
+        }
+
+        // Entries are kept oldest first, so the excess is at the front
+        let count = data
+            .password_history
+            .iter()
+            .filter(|entry| entry.user_id == user_id)
+            .count();
+        let mut excess = count.saturating_sub(history_size);
+        data.password_history.retain(|entry| {
+            if entry.user_id == user_id && excess > 0 {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+        Ok(())
+    }
+
+    async fn resolve_permissions(
+        &self,
+        user_id: Uuid,
+        org_id: Option<Uuid>,
+    ) -> AppResult<Vec<String>> {
+        let data = self.read();
+        let roles: Vec<&str> = match org_id {
+            None => data
+                .user_roles
+                .get(&user_id)
+                .map(|roles| roles.iter().copied().collect())
+                .unwrap_or_default(),
+            Some(org_id) => data
+                .member_roles
+                .get(&(org_id, user_id))
+                .copied()
+                .into_iter()
+                .collect(),
+        };
+        let permissions: BTreeSet<&str> = roles
+            .iter()
+            .flat_map(|role| data.roles[role].iter().copied())
+            .filter(|permission| org_id.is_none() || Permission::ORG_SCOPED.contains(permission))
+            .collect();
+        Ok(permissions.into_iter().map(str::to_string).collect())
+    }
+
+    async fn is_last_admin(&self, user_id: Uuid) -> AppResult<bool> {
+        let data = self.read();
+        if !data.holds_admin(&user_id) {
+            return Ok(false);
+        }
+        Ok(data
+            .user_roles
+            .keys()
+            .filter(|id| data.holds_admin(id))
+            .count()
+            == 1)
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemoryStore {
+    async fn create_session(&self, session: &Session) -> AppResult<Session> {
+        let mut data = self.write();
+        if !data.users.contains_key(&session.user_id) {
+            return Err(anyhow!(
+                "Unable to create session (no user {})",
+                session.user_id
+            ));
+        }
+        data.sessions.insert(session.id, session.clone());
+        Ok(session.clone())
+    }
+
+    async fn get_session_by_user_id(&self, user_id: Uuid) -> AppResult<Option<Session>> {
+        Ok(self
+            .read()
+            .sessions
+            .values()
+            .find(|session| session.user_id == user_id)
+            .cloned())
+    }
+
+    async fn revoke_session(&self, user_id: Uuid) -> AppResult<()> {
+        let now = Utc::now();
+        for session in self.write().sessions.values_mut() {
+            if session.user_id == user_id {
+                session.is_revoked = true;
+                session.updated_at = now;
+            }
+        }
+        Ok(())
+    }
+
+    async fn touch_session(&self, session: &Session) -> AppResult<()> {
+        if let Some(stored) = self.write().sessions.get_mut(&session.id) {
+            stored.last_used_at = session.last_used_at;
+            stored.expires_at = session.expires_at;
+            stored.updated_at = session.updated_at;
+        }
+        Ok(())
+    }
+
+    async fn revoke_org_sessions(&self, org_id: Uuid) -> AppResult<()> {
+        let now = Utc::now();
+        let mut data = self.write();
+        let MemoryData {
+            users, sessions, ..
+        } = &mut *data;
+        for session in sessions.values_mut() {
+            let in_org = users
+                .get(&session.user_id)
+                .is_some_and(|user| user.org_id == Some(org_id));
+            if in_org {
+                session.is_revoked = true;
+                session.updated_at = now;
+            }
+        }
+        Ok(())
+    }
+
+    async fn revoke_all_sessions(&self) -> AppResult<()> {
+        let now = Utc::now();
+        for session in self.write().sessions.values_mut() {
+            if !session.is_revoked {
+                session.is_revoked = true;
+                session.updated_at = now;
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_session_by_user_id(&self, user_id: Uuid) -> AppResult<()> {
+        self.write()
+            .sessions
+            .retain(|_, session| session.user_id != user_id);
+        Ok(())
+    }
+
+    async fn count_active_sessions(&self) -> AppResult<i64> {
+        let now = Utc::now();
+        Ok(self
+            .read()
+            .sessions
+            .values()
+            .filter(|session| !session.is_revoked && session.expires_at > now)
+            .count() as i64)
+    }
+
+    async fn delete_stale_sessions(
+        &self,
+        before: DateTime<Utc>,
+        idle_before: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> AppResult<u64> {
+        let mut remaining = limit.max(0) as u64;
+        self.write().sessions.retain(|_, session| {
+            let stale = session.expires_at < before
+                || (session.is_revoked && session.updated_at < before)
+                || idle_before.is_some_and(|idle_before| session.last_used_at < idle_before);
+            if stale && remaining > 0 {
+                remaining -= 1;
+                false
+            } else {
+                true
+            }
+        });
+        Ok(limit.max(0) as u64 - remaining)
+    }
+}
+
+#[async_trait]
+impl AuditStore for MemoryStore {
+    async fn record_audit_event(&self, _key: Option<&[u8]>, event: AuditEvent) -> AppResult<()> {
+        tracing::info!(
+            "Audit event {} by {:?} on {:?}",
+            event.event_type,
+            event.actor_id,
+            event.target_id
+        );
+        self.write().audit_events.push(event);
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+mod memory;
+mod postgres;
+mod sqlite;
+
+pub use memory::*;
+pub use postgres::*;
+pub use sqlite::*;
+
+use std::{fmt::Debug, sync::Arc};
+
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{AuditEvent, PasswordHistory, Session, User},
+    utils::{AppConfig, AppResult, PasswordPolicy},
+};
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Storage of users, along with their password history and the permissions they hold.
+///
+/// Lookups mirror the functions of `services`: `find_*` search the namespace where usernames
+/// and emails must be unique, as configured under `organizations.unique_per_org`.
+#[async_trait]
+pub trait UserStore: Debug + Send + Sync {
+    /// Creates a user and announces it to webhook subscribers where supported.
+    async fn create_user(&self, user: &User) -> AppResult<User>;
+
+    async fn get_user_by_id(&self, id: Uuid) -> AppResult<Option<User>>;
+
+    async fn get_user_by_github_id(&self, github_id: i64) -> AppResult<Option<User>>;
+
+    async fn find_user_by_username(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        username: &str,
+    ) -> AppResult<Option<User>>;
+
+    async fn find_user_by_email(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        email: &str,
+    ) -> AppResult<Option<User>>;
+
+    async fn find_user_by_username_or_email(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        username: &str,
+        email: &str,
+    ) -> AppResult<Option<User>>;
+
+    /// Lists users, latest first, restricted to the members of `org_id` when given.
+    async fn get_all_users(
+        &self,
+        org_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<User>>;
+
+    async fn update_user(&self, user: &User) -> AppResult<User>;
+
+    async fn update_user_password_hash(&self, id: Uuid, password_hash: &str) -> AppResult<()>;
+
+    /// Deletes a user along with their sessions and password history.
+    async fn delete_user(&self, id: Uuid) -> AppResult<()>;
+
+    /// Returns the latest `limit` passwords of a user, latest first.
+    async fn get_password_history(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> AppResult<Vec<PasswordHistory>>;
+
+    /// Records a newly set password hash and trims the history to the configured size.
+    async fn record_password_history(
+        &self,
+        policy: &PasswordPolicy,
+        user_id: Uuid,
+        password_hash: &str,
+    ) -> AppResult<()>;
+
+    /// Resolves the permissions a user holds, inside `org_id` when given.
+    async fn resolve_permissions(
+        &self,
+        user_id: Uuid,
+        org_id: Option<Uuid>,
+    ) -> AppResult<Vec<String>>;
+
+    /// Whether the user is the only one holding the admin role.
+    async fn is_last_admin(&self, user_id: Uuid) -> AppResult<bool>;
+}
+
+/// Storage of sessions, one per user.
+#[async_trait]
+pub trait SessionStore: Debug + Send + Sync {
+    async fn create_session(&self, session: &Session) -> AppResult<Session>;
+
+    async fn get_session_by_user_id(&self, user_id: Uuid) -> AppResult<Option<Session>>;
+
+    async fn revoke_session(&self, user_id: Uuid) -> AppResult<()>;
+
+    /// Saves when `session` was last used and when it expires.
+    async fn touch_session(&self, session: &Session) -> AppResult<()>;
+
+    /// Revokes the sessions of every user that belongs to an organization.
+    async fn revoke_org_sessions(&self, org_id: Uuid) -> AppResult<()>;
+
+    /// Revokes the sessions of every user.
+    async fn revoke_all_sessions(&self) -> AppResult<()>;
+
+    async fn delete_session_by_user_id(&self, user_id: Uuid) -> AppResult<()>;
+
+    /// Counts the sessions neither expired nor revoked.
+    async fn count_active_sessions(&self) -> AppResult<i64>;
+
+    /// Deletes up to `limit` sessions that expired, or were revoked, before `before`, or were
+    /// last used before `idle_before` when given, and returns how many were deleted.
+    async fn delete_stale_sessions(
+        &self,
+        before: DateTime<Utc>,
+        idle_before: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> AppResult<u64>;
+}
+
+/// Storage of the audit log, which every change to users and sessions is recorded in.
+#[async_trait]
+pub trait AuditStore: Debug + Send + Sync {
+    /// Records `event`, chained to the previous event and signed with `key` where supported.
+    async fn record_audit_event(&self, key: Option<&[u8]>, event: AuditEvent) -> AppResult<()>;
+}
+
+/// The stores the application state is built with.
+///
+/// ## Fields
+/// - `users`: Where users are kept.
+/// - `sessions`: Where sessions are kept.
+/// - `audit`: Where the audit log is kept.
+/// - `in_postgres`: Whether they are kept in Postgres, where the features that have no store,
+///   such as roles and organizations, find the same users.
+#[derive(Debug, Clone)]
+pub struct Stores {
+    pub users: Arc<dyn UserStore>,
+    pub sessions: Arc<dyn SessionStore>,
+    pub audit: Arc<dyn AuditStore>,
+    pub in_postgres: bool,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl Stores {
+    /// Stores keeping everything in `store`.
+    pub fn new<S>(store: Arc<S>) -> Self
+    where
+        S: UserStore + SessionStore + AuditStore + 'static,
+    {
+        Self {
+            users: store.clone(),
+            sessions: store.clone(),
+            audit: store,
+            in_postgres: false,
+        }
+    }
+
+    /// Stores keeping everything in the Postgres database of `db_pool`.
+    pub fn postgres(db_pool: PgPool) -> Self {
+        Self {
+            in_postgres: true,
+            ..Self::new(Arc::new(PgStore::new(db_pool)))
+        }
+    }
+}
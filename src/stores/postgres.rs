@@ -0,0 +1,178 @@
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{AuditEvent, PasswordHistory, Session, User},
+    repositories, services,
+    utils::{AppConfig, AppResult, PasswordPolicy},
+};
+
+use super::{AuditStore, SessionStore, UserStore};
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Stores backed by Postgres, through the functions of `services`.
+///
+/// Changes to users are written along with their outbox events, in a single transaction.
+#[derive(Debug, Clone)]
+pub struct PgStore {
+    pool: PgPool,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl PgStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl UserStore for PgStore {
+    async fn create_user(&self, user: &User) -> AppResult<User> {
+        services::create_user(&self.pool, user).await
+    }
+
+    async fn get_user_by_id(&self, id: Uuid) -> AppResult<Option<User>> {
+        services::get_user_by_id(&self.pool, id).await
+    }
+
+    async fn get_user_by_github_id(&self, github_id: i64) -> AppResult<Option<User>> {
+        services::get_user_by_github_id(&self.pool, github_id).await
+    }
+
+    async fn find_user_by_username(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        username: &str,
+    ) -> AppResult<Option<User>> {
+        services::find_user_by_username(&self.pool, config, org_id, username).await
+    }
+
+    async fn find_user_by_email(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        email: &str,
+    ) -> AppResult<Option<User>> {
+        services::find_user_by_email(&self.pool, config, org_id, email).await
+    }
+
+    async fn find_user_by_username_or_email(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        username: &str,
+        email: &str,
+    ) -> AppResult<Option<User>> {
+        services::find_user_by_username_or_email(&self.pool, config, org_id, username, email).await
+    }
+
+    async fn get_all_users(
+        &self,
+        org_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<User>> {
+        services::get_all_users(&self.pool, org_id, limit, offset).await
+    }
+
+    async fn update_user(&self, user: &User) -> AppResult<User> {
+        services::update_user(&self.pool, user).await
+    }
+
+    async fn update_user_password_hash(&self, id: Uuid, password_hash: &str) -> AppResult<()> {
+        services::update_user_password_hash(&self.pool, id, password_hash).await
+    }
+
+    async fn delete_user(&self, id: Uuid) -> AppResult<()> {
+        services::delete_user(&self.pool, id).await
+    }
+
+    async fn get_password_history(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> AppResult<Vec<PasswordHistory>> {
+        repositories::get_password_history(&self.pool, user_id, limit).await
+    }
+
+    async fn record_password_history(
+        &self,
+        policy: &PasswordPolicy,
+        user_id: Uuid,
+        password_hash: &str,
+    ) -> AppResult<()> {
+        services::record_password_history(&self.pool, policy, user_id, password_hash).await
+    }
+
+    async fn resolve_permissions(
+        &self,
+        user_id: Uuid,
+        org_id: Option<Uuid>,
+    ) -> AppResult<Vec<String>> {
+        services::resolve_permissions(&self.pool, user_id, org_id).await
+    }
+
+    async fn is_last_admin(&self, user_id: Uuid) -> AppResult<bool> {
+        services::is_last_admin(&self.pool, user_id).await
+    }
+}
+
+#[async_trait]
+impl SessionStore for PgStore {
+    async fn create_session(&self, session: &Session) -> AppResult<Session> {
+        services::create_session(&self.pool, session).await
+    }
+
+    async fn get_session_by_user_id(&self, user_id: Uuid) -> AppResult<Option<Session>> {
+        services::get_session_by_user_id(&self.pool, user_id).await
+    }
+
+    async fn revoke_session(&self, user_id: Uuid) -> AppResult<()> {
+        services::revoke_session(&self.pool, user_id).await
+    }
+
+    async fn touch_session(&self, session: &Session) -> AppResult<()> {
+        services::touch_session(&self.pool, session).await
+    }
+
+    async fn revoke_org_sessions(&self, org_id: Uuid) -> AppResult<()> {
+        services::revoke_org_sessions(&self.pool, org_id).await
+    }
+
+    async fn revoke_all_sessions(&self) -> AppResult<()> {
+        services::revoke_all_sessions(&self.pool).await
+    }
+
+    async fn delete_session_by_user_id(&self, user_id: Uuid) -> AppResult<()> {
+        services::delete_session_by_user_id(&self.pool, user_id).await
+    }
+
+    async fn count_active_sessions(&self) -> AppResult<i64> {
+        repositories::count_active_sessions(&self.pool, Utc::now()).await
+    }
+
+    async fn delete_stale_sessions(
+        &self,
+        before: DateTime<Utc>,
+        idle_before: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> AppResult<u64> {
+        repositories::delete_stale_sessions(&self.pool, before, idle_before, limit).await
+    }
+}
+
+#[async_trait]
+impl AuditStore for PgStore {
+    async fn record_audit_event(&self, key: Option<&[u8]>, event: AuditEvent) -> AppResult<()> {
+        services::record_audit_event(&self.pool, key, event).await
+    }
+}
@@ -0,0 +1,452 @@
+use anyhow::anyhow;
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{types::Json, SqlitePool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::{AuditEvent, PasswordHistory, Session, User},
+    utils::{AppConfig, AppResult, PasswordPolicy},
+};
+
+use super::{AuditStore, SessionStore, UserStore};
+
+//----------------------------------------------------------------------
+// Types
+//----------------------------------------------------------------------
+
+/// Stores backed by SQLite, migrated with the scripts under `migrations/sqlite`.
+///
+/// Only users, sessions, password history and the audit log are kept. Roles and organizations
+/// require Postgres, so users hold no permissions, and audit events are neither chained nor
+/// published to webhooks.
+#[derive(Debug, Clone)]
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+//----------------------------------------------------------------------
+// Implementations
+//----------------------------------------------------------------------
+
+impl SqliteStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Looks up a user matching `condition`, in the namespace of `org_id` when usernames and
+    /// emails are unique per organization, otherwise among every user.
+    async fn find_user(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        condition: &str,
+        values: &[&str],
+    ) -> AppResult<Option<User>> {
+        let unique_per_org = *config.get_organizations().get_unique_per_org();
+        let sql = if unique_per_org {
+            format!("SELECT * FROM users WHERE org_id IS ? AND ({})", condition)
+        } else {
+            format!("SELECT * FROM users WHERE {}", condition)
+        };
+        let mut query = sqlx::query_as::<_, User>(&sql);
+        if unique_per_org {
+            query = query.bind(org_id);
+        }
+        for value in values {
+            query = query.bind(*value);
+        }
+        query
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Unable to get user ({})", e))
+    }
+}
+
+#[async_trait]
+impl UserStore for SqliteStore {
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn create_user(&self, user: &User) -> AppResult<User> {
+        sqlx::query_as::<_, User>(
+            r#"
+            INSERT INTO users (id, github_id, username, email, is_admin, password_hash, avatar_url, org_id, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            RETURNING *
+            "#,
+        )
+        .bind(user.id)
+        .bind(user.github_id)
+        .bind(&user.username)
+        .bind(&user.email)
+        .bind(user.is_admin)
+        .bind(&user.password_hash)
+        .bind(&user.avatar_url)
+        .bind(user.org_id)
+        .bind(user.created_at)
+        .bind(user.updated_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Unable to create user ({})", e))
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn get_user_by_id(&self, id: Uuid) -> AppResult<Option<User>> {
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Unable to get user by id ({})", e))
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn get_user_by_github_id(&self, github_id: i64) -> AppResult<Option<User>> {
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE github_id = ?1")
+            .bind(github_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Unable to get user by github id ({})", e))
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn find_user_by_username(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        username: &str,
+    ) -> AppResult<Option<User>> {
+        self.find_user(config, org_id, "username = ?", &[username])
+            .await
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn find_user_by_email(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        email: &str,
+    ) -> AppResult<Option<User>> {
+        self.find_user(config, org_id, "email = ?", &[email]).await
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn find_user_by_username_or_email(
+        &self,
+        config: &AppConfig,
+        org_id: Option<Uuid>,
+        username: &str,
+        email: &str,
+    ) -> AppResult<Option<User>> {
+        self.find_user(
+            config,
+            org_id,
+            "username = ? OR email = ?",
+            &[username, email],
+        )
+        .await
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn get_all_users(
+        &self,
+        org_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<User>> {
+        sqlx::query_as::<_, User>(
+            r#"
+            SELECT * FROM users
+            WHERE ?1 IS NULL OR org_id = ?1
+            ORDER BY created_at DESC
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(org_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Unable to get all users ({})", e))
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn update_user(&self, user: &User) -> AppResult<User> {
+        sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET github_id = ?2, username = ?3, email = ?4, is_admin = ?5, password_hash = ?6, avatar_url = ?7, org_id = ?8, updated_at = ?9
+            WHERE id = ?1
+            RETURNING *
+            "#,
+        )
+        .bind(user.id)
+        .bind(user.github_id)
+        .bind(&user.username)
+        .bind(&user.email)
+        .bind(user.is_admin)
+        .bind(&user.password_hash)
+        .bind(&user.avatar_url)
+        .bind(user.org_id)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Unable to update user ({})", e))
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn update_user_password_hash(&self, id: Uuid, password_hash: &str) -> AppResult<()> {
+        sqlx::query("UPDATE users SET password_hash = ?2, updated_at = ?3 WHERE id = ?1")
+            .bind(id)
+            .bind(password_hash)
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Unable to update user password ({})", e))?;
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn delete_user(&self, id: Uuid) -> AppResult<()> {
+        // Sessions and password history go with the user through their foreign keys
+        sqlx::query("DELETE FROM users WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Unable to delete user ({})", e))?;
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn get_password_history(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> AppResult<Vec<PasswordHistory>> {
+        sqlx::query_as::<_, PasswordHistory>(
+            r#"
+            SELECT * FROM password_history
+            WHERE user_id = ?1
+            ORDER BY created_at DESC
+            LIMIT ?2
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Unable to get password history ({})", e))
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn record_password_history(
+        &self,
+        policy: &PasswordPolicy,
+        user_id: Uuid,
+        password_hash: &str,
+    ) -> AppResult<()> {
+        let history_size = *policy.get_history_size();
+        if history_size > 0 {
+            let entry = PasswordHistory::new(user_id, password_hash);
+            sqlx::query(
+                r#"
+                INSERT INTO password_history (id, user_id, password_hash, created_at)
+                VALUES (?1, ?2, ?3, ?4)
+                "#,
+            )
+            .bind(entry.id)
+            .bind(entry.user_id)
+            .bind(&entry.password_hash)
+            .bind(entry.created_at)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Unable to create password history ({})", e))?;
+        }
+
+        sqlx::query(
+            r#"
+            DELETE FROM password_history
+            WHERE user_id = ?1 AND id NOT IN (
+                SELECT id FROM password_history
+                WHERE user_id = ?1
+                ORDER BY created_at DESC
+                LIMIT ?2
+            )
+            "#,
+        )
+        .bind(user_id)
+        .bind(history_size)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Unable to prune password history ({})", e))?;
+        Ok(())
+    }
+
+    async fn resolve_permissions(
+        &self,
+        _user_id: Uuid,
+        _org_id: Option<Uuid>,
+    ) -> AppResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    async fn is_last_admin(&self, _user_id: Uuid) -> AppResult<bool> {
+        Ok(false)
+    }
+}
+
+#[async_trait]
+impl SessionStore for SqliteStore {
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn create_session(&self, session: &Session) -> AppResult<Session> {
+        sqlx::query_as::<_, Session>(
+            r#"
+            INSERT INTO sessions (id, user_id, refresh_token, is_revoked, expires_at, created_at, updated_at, last_used_at, is_persistent)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            RETURNING *
+            "#,
+        )
+        .bind(session.id)
+        .bind(session.user_id)
+        .bind(&session.refresh_token)
+        .bind(session.is_revoked)
+        .bind(session.expires_at)
+        .bind(session.created_at)
+        .bind(session.updated_at)
+        .bind(session.last_used_at)
+        .bind(session.is_persistent)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Unable to create session ({})", e))
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn get_session_by_user_id(&self, user_id: Uuid) -> AppResult<Option<Session>> {
+        sqlx::query_as::<_, Session>("SELECT * FROM sessions WHERE user_id = ?1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Unable to get session by user id ({})", e))
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn revoke_session(&self, user_id: Uuid) -> AppResult<()> {
+        sqlx::query("UPDATE sessions SET is_revoked = TRUE, updated_at = ?2 WHERE user_id = ?1")
+            .bind(user_id)
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Unable to revoke session ({})", e))?;
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn touch_session(&self, session: &Session) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE sessions SET last_used_at = ?2, expires_at = ?3, updated_at = ?4 WHERE id = ?1",
+        )
+        .bind(session.id)
+        .bind(session.last_used_at)
+        .bind(session.expires_at)
+        .bind(session.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Unable to touch session ({})", e))?;
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn revoke_org_sessions(&self, org_id: Uuid) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE sessions SET is_revoked = TRUE, updated_at = ?2
+            WHERE user_id IN (SELECT id FROM users WHERE org_id = ?1)
+            "#,
+        )
+        .bind(org_id)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Unable to revoke organization sessions ({})", e))?;
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn revoke_all_sessions(&self) -> AppResult<()> {
+        sqlx::query("UPDATE sessions SET is_revoked = TRUE, updated_at = ?1 WHERE NOT is_revoked")
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Unable to revoke all sessions ({})", e))?;
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn delete_session_by_user_id(&self, user_id: Uuid) -> AppResult<()> {
+        sqlx::query("DELETE FROM sessions WHERE user_id = ?1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Unable to delete session ({})", e))?;
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn count_active_sessions(&self) -> AppResult<i64> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE NOT is_revoked AND expires_at > ?1")
+            .bind(Utc::now())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Unable to count active sessions ({})", e))
+    }
+
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn delete_stale_sessions(
+        &self,
+        before: DateTime<Utc>,
+        idle_before: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> AppResult<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM sessions
+            WHERE id IN (
+                SELECT id FROM sessions
+                WHERE expires_at < ?1 OR (is_revoked AND updated_at < ?1) OR last_used_at < ?3
+                LIMIT ?2
+            )
+            "#,
+        )
+        .bind(before)
+        .bind(limit)
+        .bind(idle_before)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Unable to delete stale sessions ({})", e))?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait]
+impl AuditStore for SqliteStore {
+    #[instrument(skip_all, fields(otel.kind = "client", db.system = "sqlite"))]
+    async fn record_audit_event(&self, _key: Option<&[u8]>, event: AuditEvent) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_events (id, event_type, actor_id, target_id, ip, user_agent, details, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+        )
+        .bind(event.id)
+        .bind(&event.event_type)
+        .bind(event.actor_id)
+        .bind(event.target_id)
+        .bind(&event.ip)
+        .bind(&event.user_agent)
+        .bind(event.details.map(Json))
+        .bind(event.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Unable to create audit event ({})", e))?;
+        Ok(())
+    }
+}
@@ -3,11 +3,11 @@
 
 use chrono::{Duration, Utc};
 use getset::Getters;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use uuid::Uuid;
 
 /// The type of token represented by these claims.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Typ {
     /// An access token that grants access to protected resources.
     Access,
@@ -15,8 +15,31 @@ pub enum Typ {
     Refresh,
 }
 
+/// What a token grants its bearer, on top of identifying the user.
+#[derive(Debug, Clone, Default)]
+pub struct Grant {
+    /// The permissions granted to the user through their roles.
+    pub permissions: Vec<String>,
+    /// The organization the token is scoped to, if any.
+    pub org_id: Option<Uuid>,
+    /// The scopes the token was requested with, limiting the routes it can reach.
+    pub scope: Vec<String>,
+    /// The admin acting as the user, for impersonation tokens.
+    pub act: Option<Actor>,
+}
+
+/// Identifies the admin behind an impersonation token, as the `act` claim of RFC 8693.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Actor {
+    /// The admin's unique identifier.
+    pub sub: Uuid,
+    /// The impersonation the token was issued for, so it can be stopped.
+    pub impersonation_id: Uuid,
+}
+
 /// Represents the JWT claims included in a token.
-#[derive(Debug, Serialize, Deserialize, Getters)]
+#[derive(Debug, Clone, Serialize, Deserialize, Getters)]
 #[serde(rename_all = "camelCase")]
 pub struct Claims {
     /// A unique identifier for the token (often the user ID).
@@ -29,9 +52,26 @@ pub struct Claims {
     aud: String,
     /// The issuer of the token.
     iss: String,
-    /// Indicates whether the user is an administrator.
+    /// The permissions granted to the user through their roles.
     #[getset(get = "pub with_prefix")]
-    is_admin: bool,
+    #[serde(default)]
+    permissions: Vec<String>,
+    /// The organization the token is scoped to, if any.
+    #[getset(get = "pub with_prefix")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    org_id: Option<Uuid>,
+    /// The scopes of the token, serialized as a space-delimited list as in OAuth 2.0.
+    #[getset(get = "pub with_prefix")]
+    #[serde(
+        default,
+        serialize_with = "serialize_scope",
+        deserialize_with = "deserialize_scope"
+    )]
+    scope: Vec<String>,
+    /// The admin impersonating the user, if any.
+    #[getset(get = "pub with_prefix")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    act: Option<Actor>,
     /// The time at which the token was issued, in Unix timestamp format.
     #[getset(get = "pub with_prefix")]
     iat: i64,
@@ -41,6 +81,10 @@ pub struct Claims {
     /// The type of token (Access or Refresh).
     #[getset(get = "pub with_prefix")]
     typ: Typ,
+    /// The API key the request was authenticated with, if it did not use a JWT.
+    #[getset(get = "pub with_prefix")]
+    #[serde(skip)]
+    api_key_id: Option<Uuid>,
 }
 
 impl Claims {
@@ -50,13 +94,13 @@ impl Claims {
     ///
     /// * `user_id` - The user's unique identifier.
     /// * `email` - The user's email (used as `sub`).
-    /// * `is_admin` - Whether the user has admin privileges.
+    /// * `grant` - The permissions, organization and scopes granted by the token.
     /// * `exp` - The duration until the token expires.
     /// * `typ` - The type of token (Access or Refresh).
     pub fn new(
         user_id: Uuid,
         email: impl Into<String>,
-        is_admin: bool,
+        grant: Grant,
         exp: Duration,
         typ: Typ,
     ) -> Self {
@@ -66,10 +110,39 @@ impl Claims {
             sub: email.into(),
             aud: "auth-rs_client".to_string(),
             iss: "auth-rs_auth".to_string(),
-            is_admin,
+            permissions: grant.permissions,
+            org_id: grant.org_id,
+            scope: grant.scope,
+            act: grant.act,
             iat: now.timestamp(),
             exp: (now + exp).timestamp(),
             typ,
+            api_key_id: None,
         }
     }
+
+    /// Marks the claims as resolved from the API key `api_key_id` rather than a JWT.
+    pub fn with_api_key(mut self, api_key_id: Uuid) -> Self {
+        self.api_key_id = Some(api_key_id);
+        self
+    }
+
+    /// Checks whether the claims grant `permission`.
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+
+    /// Checks whether the token was issued with `scope`.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scope.iter().any(|s| s == scope)
+    }
+}
+
+fn serialize_scope<S: Serializer>(scope: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&scope.join(" "))
+}
+
+fn deserialize_scope<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let scope = String::deserialize(deserializer)?;
+    Ok(scope.split_whitespace().map(str::to_string).collect())
 }
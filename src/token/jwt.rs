@@ -6,8 +6,8 @@ use chrono::Duration;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use uuid::Uuid;
 
-use super::{Claims, Typ};
-use crate::utils::AppResult;
+use super::{Claims, Grant, Typ};
+use crate::utils::{record_token_issued, AppResult};
 
 /// Manages encoding and decoding of JWT tokens.
 pub struct TokenManager<'a> {
@@ -31,7 +31,7 @@ impl<'a> TokenManager<'a> {
         &self,
         user_id: Uuid,
         email: &str,
-        is_admin: bool,
+        grant: Grant,
         exp: Duration,
         typ: Typ,
     ) -> AppResult<(String, Claims)> {
@@ -40,7 +40,7 @@ impl<'a> TokenManager<'a> {
             header.kid = Some(kid.to_owned());
         }
 
-        let claims = Claims::new(user_id, email, is_admin, exp, typ);
+        let claims = Claims::new(user_id, email, grant, exp, typ);
         let token = encode(&header, &claims, &EncodingKey::from_secret(self.secret))?;
 
         Ok((token, claims))
@@ -72,16 +72,17 @@ impl<'a> TokenManager<'a> {
     ///
     /// * `user_id` - The user's unique identifier.
     /// * `email` - The user's email (subject claim).
-    /// * `is_admin` - Whether the user has admin privileges.
+    /// * `grant` - The permissions, organization and scopes granted by the token.
     /// * `duration` - The validity duration of the token.
     pub fn create_access_token(
         &self,
         user_id: Uuid,
         email: &str,
-        is_admin: bool,
+        grant: Grant,
         duration: Duration,
     ) -> AppResult<(String, Claims)> {
-        self.encode(user_id, email, is_admin, duration, Typ::Access)
+        record_token_issued("access");
+        self.encode(user_id, email, grant, duration, Typ::Access)
     }
 
     /// Creates a refresh token for the given user with the specified duration.
@@ -90,16 +91,17 @@ impl<'a> TokenManager<'a> {
     ///
     /// * `user_id` - The user's unique identifier.
     /// * `email` - The user's email (subject claim).
-    /// * `is_admin` - Whether the user has admin privileges.
+    /// * `grant` - The permissions, organization and scopes granted by the token.
     /// * `duration` - The validity duration of the token.
     pub fn create_refresh_token(
         &self,
         user_id: Uuid,
         email: &str,
-        is_admin: bool,
+        grant: Grant,
         duration: Duration,
     ) -> AppResult<(String, Claims)> {
-        self.encode(user_id, email, is_admin, duration, Typ::Refresh)
+        record_token_issued("refresh");
+        self.encode(user_id, email, grant, duration, Typ::Refresh)
     }
 
     /// Validates an access token and returns the decoded claims if valid.
@@ -0,0 +1,163 @@
+#![deny(missing_docs)]
+//! Offline lookup of breached passwords in a local copy of the
+//! [Pwned Passwords](https://haveibeenpwned.com/Passwords) SHA-1 corpus.
+//!
+//! Two layouts of the corpus are supported:
+//! - A single file of `HASH:COUNT` lines ordered by hash, as produced by the
+//!   official downloader. The file is memory-mapped and binary searched, so
+//!   lookups cost a handful of page reads and nothing is loaded up front.
+//! - A directory of range files named after the 5-character hash prefix
+//!   (optionally with a `.txt` extension), each holding `SUFFIX:COUNT` lines
+//!   exactly as returned by the range API.
+
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use memmap2::Mmap;
+use sha1::{Digest, Sha1};
+
+use super::{AppResult, BreachedPasswordsConfig};
+
+/// Length of a hex-encoded SHA-1 hash.
+const HASH_LENGTH: usize = 40;
+
+/// Length of the hash prefix used to name range files.
+const PREFIX_LENGTH: usize = 5;
+
+/// A local, read-only index of breached password hashes.
+#[derive(Debug)]
+pub struct BreachedPasswords {
+    corpus: Corpus,
+    /// Passwords seen fewer times than this are not considered breached.
+    min_occurrences: u64,
+}
+
+#[derive(Debug)]
+enum Corpus {
+    /// A memory-mapped file of `HASH:COUNT` lines sorted by hash.
+    Sorted(Mmap),
+    /// A directory of per-prefix range files.
+    Ranges(PathBuf),
+}
+
+impl BreachedPasswords {
+    /// Opens the configured corpus, or returns `None` when the check is disabled.
+    pub fn from_config(config: &BreachedPasswordsConfig) -> AppResult<Option<Self>> {
+        if !config.get_enabled() {
+            return Ok(None);
+        }
+        Self::open(config.get_path(), *config.get_min_occurrences()).map(Some)
+    }
+
+    /// Opens the corpus at `path`, which may be a sorted hash file or a directory of range files.
+    pub fn open(path: impl AsRef<Path>, min_occurrences: u64) -> AppResult<Self> {
+        let path = path.as_ref();
+        let corpus = if path.is_dir() {
+            Corpus::Ranges(path.to_path_buf())
+        } else {
+            let file = File::open(path)
+                .with_context(|| format!("Failed to open breach corpus {}", path.display()))?;
+            // SAFETY: the corpus is opened read-only and is expected to stay unmodified
+            // while the service runs; concurrent truncation would only affect lookups.
+            let map = unsafe { Mmap::map(&file) }
+                .with_context(|| format!("Failed to map breach corpus {}", path.display()))?;
+            Corpus::Sorted(map)
+        };
+
+        Ok(Self {
+            corpus,
+            min_occurrences,
+        })
+    }
+
+    /// Returns how many times `password` appears in the corpus if it counts as breached,
+    /// or `None` if it is absent or seen fewer than `min_occurrences` times.
+    pub fn check(&self, password: &str) -> AppResult<Option<u64>> {
+        Ok(self
+            .occurrences(password)?
+            .filter(|&count| count >= self.min_occurrences))
+    }
+
+    /// Returns how many times `password` appears in the corpus, or `None` if it is absent.
+    pub fn occurrences(&self, password: &str) -> AppResult<Option<u64>> {
+        let hash: String = Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect();
+
+        match &self.corpus {
+            Corpus::Sorted(map) => Ok(search_sorted(map, hash.as_bytes())),
+            Corpus::Ranges(dir) => search_range(dir, &hash),
+        }
+    }
+}
+
+/// Binary searches a byte slice of newline-separated `HASH:COUNT` lines sorted by hash.
+fn search_sorted(corpus: &[u8], hash: &[u8]) -> Option<u64> {
+    let (mut low, mut high) = (0, corpus.len());
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let start = corpus[..mid]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let end = corpus[mid..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map(|i| mid + i)
+            .unwrap_or(corpus.len());
+
+        let line = &corpus[start..end];
+        let key = &line[..line.len().min(HASH_LENGTH)];
+        match compare_hex(key, hash) {
+            Ordering::Less => low = end + 1,
+            Ordering::Greater => high = start,
+            Ordering::Equal => return Some(parse_count(line)),
+        }
+    }
+
+    None
+}
+
+/// Scans the range file for the hash prefix and matches the remaining suffix.
+fn search_range(dir: &Path, hash: &str) -> AppResult<Option<u64>> {
+    let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+
+    let candidates = [dir.join(prefix), dir.join(format!("{}.txt", prefix))];
+    let Some(path) = candidates.iter().find(|p| p.is_file()) else {
+        return Ok(None);
+    };
+
+    let contents = fs::read(path)
+        .with_context(|| format!("Failed to read breach range file {}", path.display()))?;
+
+    Ok(contents
+        .split(|&b| b == b'\n')
+        .find(|line| {
+            line.len() >= suffix.len()
+                && compare_hex(&line[..suffix.len()], suffix.as_bytes()) == Ordering::Equal
+        })
+        .map(parse_count))
+}
+
+/// Compares hex strings case-insensitively.
+fn compare_hex(a: &[u8], b: &[u8]) -> Ordering {
+    a.iter()
+        .map(u8::to_ascii_uppercase)
+        .cmp(b.iter().map(u8::to_ascii_uppercase))
+}
+
+/// Parses the count after the `:` separator, defaulting to one occurrence when absent.
+fn parse_count(line: &[u8]) -> u64 {
+    line.iter()
+        .position(|&b| b == b':')
+        .and_then(|i| std::str::from_utf8(&line[i + 1..]).ok())
+        .and_then(|count| count.trim().parse().ok())
+        .unwrap_or(1)
+}
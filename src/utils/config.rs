@@ -2,7 +2,10 @@ use anyhow::Context;
 use config::{Config, Environment};
 use getset::{Getters, Setters};
 use serde::Deserialize;
-use sqlx::postgres::{PgConnectOptions, PgSslMode};
+use sqlx::{
+    postgres::{PgConnectOptions, PgSslMode},
+    sqlite::SqliteConnectOptions,
+};
 use std::{str::FromStr, sync::LazyLock};
 
 use crate::utils::AppResult;
@@ -18,26 +21,103 @@ pub struct AppConfig {
     pub environment: AppEnvironment,
     #[getset(get = "pub with_prefix")]
     jwt: JwtConfig,
+    #[getset(get = "pub with_prefix")]
+    password_policy: PasswordPolicy,
+    #[getset(get = "pub with_prefix")]
+    breached_passwords: BreachedPasswordsConfig,
+    #[getset(get = "pub with_prefix")]
+    password_hashing: PasswordHashingConfig,
+    #[getset(get = "pub with_prefix")]
+    user_import: UserImportConfig,
+    #[getset(get = "pub with_prefix")]
+    bootstrap: BootstrapConfig,
+    #[getset(get = "pub with_prefix")]
+    organizations: OrganizationsConfig,
+    #[getset(get = "pub with_prefix")]
+    impersonation: ImpersonationConfig,
+    #[getset(get = "pub with_prefix")]
+    audit: AuditConfig,
+    #[getset(get = "pub with_prefix")]
+    webhooks: WebhooksConfig,
+    #[getset(get = "pub with_prefix")]
+    session: SessionConfig,
+    #[getset(get = "pub with_prefix")]
+    session_reaper: SessionReaperConfig,
+    #[getset(get = "pub with_prefix")]
+    redis: RedisConfig,
+    #[getset(get = "pub with_prefix")]
+    rate_limit: RateLimitConfig,
+    #[getset(get = "pub with_prefix")]
+    metrics: MetricsConfig,
+    #[getset(get = "pub with_prefix")]
+    telemetry: TelemetryConfig,
 }
 
 impl AppConfig {
     pub fn new() -> AppResult<Self> {
-        Config::builder()
+        Self::with_overrides(&[])
+    }
+
+    /// Loads the configuration as [`AppConfig::new`] does, with `overrides` taking precedence
+    /// over the environment, e.g. `AppConfig::with_overrides(&[("session.idle_timeout_secs", "600")])`.
+    pub fn with_overrides(overrides: &[(&str, &str)]) -> AppResult<Self> {
+        let mut builder = Config::builder()
             .set_default("server.host", "127.0.0.1")?
             .set_default("server.port", 8000)?
             .set_default("server.timeout_in_secs", 10)?
             .set_default("server.origins", "localhost")?
-            .set_default("server.rate_limit_per_secs", 100)?
-            .set_default("server.rate_limit_burst", 10)?
+            .set_default("server.trust_forwarded_for", false)?
             .set_default("database.host", "127.0.0.1")?
             .set_default("database.port", 5432)?
             .set_default("database.ssl_mode", "prefer")?
             .set_default("database.max_connections", 10)?
             .set_default("database.min_connections", 1)?
             .set_default("database.acquire_timeout_secs", 5)?
+            .set_default("database.auto_migrate", false)?
+            .set_default("database.url", "")?
             .set_default("environment", "local")?
             .set_default("jwt.access_token_expiration_secs", 900)?
             .set_default("jwt.refresh_token_expiration_secs", 86400)?
+            .set_default("password_policy.min_length", 8)?
+            .set_default("password_policy.max_length", 128)?
+            .set_default("password_policy.require_uppercase", false)?
+            .set_default("password_policy.require_lowercase", false)?
+            .set_default("password_policy.require_digit", false)?
+            .set_default("password_policy.require_symbol", false)?
+            .set_default("password_policy.min_strength_score", 2)?
+            .set_default("password_policy.reject_user_info", true)?
+            .set_default("password_policy.history_size", 5)?
+            .set_default("breached_passwords.enabled", false)?
+            .set_default("breached_passwords.path", "")?
+            .set_default("breached_passwords.min_occurrences", 1)?
+            .set_default("password_hashing.algorithm", "argon2id")?
+            .set_default("password_hashing.memory_cost", 19456)?
+            .set_default("password_hashing.time_cost", 2)?
+            .set_default("password_hashing.parallelism", 1)?
+            .set_default("password_hashing.pepper", "")?
+            .set_default("password_hashing.previous_peppers", "")?
+            .set_default("user_import.invite_expiration_secs", 604800)?
+            .set_default("bootstrap.admin_username", "")?
+            .set_default("bootstrap.admin_email", "")?
+            .set_default("bootstrap.admin_password", "")?
+            .set_default("organizations.unique_per_org", false)?
+            .set_default("impersonation.token_expiration_secs", 900)?
+            .set_default("audit.hmac_key", "")?
+            .set_default("audit.checkpoint_path", "")?
+            .set_default("audit.checkpoint_interval_secs", 3600)?
+            .set_default("webhooks.poll_interval_secs", 5)?
+            .set_default("webhooks.timeout_secs", 10)?
+            .set_default("webhooks.max_attempts", 8)?
+            .set_default("webhooks.backoff_base_secs", 30)?
+            .set_default("webhooks.backoff_max_secs", 3600)?
+            .set_default("session.idle_timeout_secs", 0)?
+            .set_default("session.sliding_expiration", false)?
+            .set_default("session.max_lifetime_secs", 2592000)?
+            .set_default("session.remember_me_lifetime_secs", 2592000)?
+            .set_default("session_reaper.enabled", true)?
+            .set_default("session_reaper.interval_secs", 300)?
+            .set_default("session_reaper.batch_size", 1000)?
+            .set_default("session_reaper.retention_secs", 86400)?
             .set_default("redis.port", 6379)?
             .set_default("redis.host", "127.0.0.1")?
             .set_default("redis.db", 0)?
@@ -46,7 +126,23 @@ impl AppConfig {
             .set_default("rate_limit.window_size", 60)?
             .set_default("rate_limit.redis_uri", "redis://127.0.0.1")?
             .set_default("rate_limit.key_strategy", "token")?
-            .add_source(Environment::with_prefix("APP").separator("__"))
+            .set_default("rate_limit.read.requests_per_window", 300)?
+            .set_default("rate_limit.read.window_size", 60)?
+            .set_default("rate_limit.auth.requests_per_window", 20)?
+            .set_default("rate_limit.auth.window_size", 60)?
+            .set_default("rate_limit.identifier.requests_per_window", 10)?
+            .set_default("rate_limit.identifier.window_size", 300)?
+            .set_default("metrics.enabled", true)?
+            .set_default("metrics.host", "127.0.0.1")?
+            .set_default("metrics.port", 9464)?
+            .set_default("telemetry.otlp_endpoint", "")?
+            .set_default("telemetry.service_name", "auth-rs")?
+            .set_default("telemetry.sample_ratio", 1.0)?
+            .add_source(Environment::with_prefix("APP").separator("__"));
+        for (key, value) in overrides {
+            builder = builder.set_override(*key, *value)?;
+        }
+        builder
             .build()?
             .try_deserialize()
             .context("Failed to deserialize configuration")
@@ -64,11 +160,10 @@ pub struct ServerConfig {
     #[getset(get = "pub with_prefix")]
     origins: String,
     #[getset(get = "pub with_prefix")]
-    rate_limit_per_secs: u64,
-    #[getset(get = "pub with_prefix")]
-    rate_limit_burst: u64,
-    #[getset(get = "pub with_prefix")]
     cookie_secret: String,
+    /// Take the client IP from `X-Forwarded-For`, when running behind a trusted proxy.
+    #[getset(get = "pub with_prefix")]
+    trust_forwarded_for: bool,
 }
 
 #[derive(Debug, Deserialize, Getters, Setters, Clone)]
@@ -96,9 +191,38 @@ pub struct DatabaseConfig {
     min_connections: u32,
     #[getset(get = "pub with_prefix")]
     acquire_timeout_secs: u64,
+    /// Whether the server applies pending migrations on startup.
+    #[getset(get = "pub with_prefix")]
+    auto_migrate: bool,
+    /// A `sqlite:` URL selects the SQLite backend; Postgres is configured with the settings
+    /// above otherwise.
+    #[getset(get = "pub with_prefix")]
+    url: String,
+}
+
+/// Where users and sessions are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseBackend {
+    Postgres,
+    Sqlite,
 }
 
 impl DatabaseConfig {
+    pub fn get_backend(&self) -> DatabaseBackend {
+        if self.url.starts_with("sqlite:") {
+            DatabaseBackend::Sqlite
+        } else {
+            DatabaseBackend::Postgres
+        }
+    }
+
+    /// Options to open the SQLite database at `url`, creating it if missing.
+    pub fn to_sqlite_connect_options(&self) -> AppResult<SqliteConnectOptions> {
+        Ok(SqliteConnectOptions::from_str(&self.url)
+            .with_context(|| format!("Invalid SQLite URL {}", self.url))?
+            .create_if_missing(true))
+    }
+
     pub fn to_pg_connect_options(&self) -> PgConnectOptions {
         PgConnectOptions::new()
             .username(&self.username)
@@ -147,3 +271,260 @@ pub struct JwtConfig {
     #[getset(get = "pub with_prefix")]
     refresh_token_expiration_secs: i64,
 }
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct PasswordPolicy {
+    #[getset(get = "pub with_prefix")]
+    min_length: usize,
+    #[getset(get = "pub with_prefix")]
+    max_length: usize,
+    #[getset(get = "pub with_prefix")]
+    require_uppercase: bool,
+    #[getset(get = "pub with_prefix")]
+    require_lowercase: bool,
+    #[getset(get = "pub with_prefix")]
+    require_digit: bool,
+    #[getset(get = "pub with_prefix")]
+    require_symbol: bool,
+    #[getset(get = "pub with_prefix")]
+    min_strength_score: u8,
+    #[getset(get = "pub with_prefix")]
+    reject_user_info: bool,
+    #[getset(get = "pub with_prefix")]
+    history_size: i64,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct BreachedPasswordsConfig {
+    #[getset(get = "pub with_prefix")]
+    enabled: bool,
+    #[getset(get = "pub with_prefix")]
+    path: String,
+    #[getset(get = "pub with_prefix")]
+    min_occurrences: u64,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct PasswordHashingConfig {
+    #[getset(get = "pub with_prefix")]
+    algorithm: PasswordHashAlgorithm,
+    #[getset(get = "pub with_prefix")]
+    memory_cost: u32,
+    #[getset(get = "pub with_prefix")]
+    time_cost: u32,
+    #[getset(get = "pub with_prefix")]
+    parallelism: u32,
+    #[getset(get = "pub with_prefix")]
+    pepper: String,
+    /// Peppers used before the current one, separated by commas, so that passwords hashed
+    /// with them still verify and are re-hashed with the current pepper on login.
+    #[getset(get = "pub with_prefix")]
+    previous_peppers: String,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct UserImportConfig {
+    #[getset(get = "pub with_prefix")]
+    invite_expiration_secs: i64,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct BootstrapConfig {
+    #[getset(get = "pub with_prefix")]
+    admin_username: String,
+    #[getset(get = "pub with_prefix")]
+    admin_email: String,
+    #[getset(get = "pub with_prefix")]
+    admin_password: String,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct OrganizationsConfig {
+    /// Scope username and email uniqueness to each organization instead of the whole deployment.
+    #[getset(get = "pub with_prefix")]
+    unique_per_org: bool,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct ImpersonationConfig {
+    /// Lifetime of impersonation tokens, which cannot be refreshed.
+    #[getset(get = "pub with_prefix")]
+    token_expiration_secs: i64,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct AuditConfig {
+    /// Signs the audit chain with HMAC-SHA256 when set; plain SHA-256 otherwise.
+    hmac_key: String,
+    /// File that checkpoints of the audit chain are appended to; disabled when empty.
+    #[getset(get = "pub with_prefix")]
+    checkpoint_path: String,
+    #[getset(get = "pub with_prefix")]
+    checkpoint_interval_secs: u64,
+}
+
+impl AuditConfig {
+    pub fn get_hmac_key(&self) -> Option<&[u8]> {
+        Some(self.hmac_key.as_bytes()).filter(|key| !key.is_empty())
+    }
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct WebhooksConfig {
+    /// How often the dispatcher looks for new events and due deliveries.
+    #[getset(get = "pub with_prefix")]
+    poll_interval_secs: u64,
+    /// How long an endpoint has to answer before the attempt fails.
+    #[getset(get = "pub with_prefix")]
+    timeout_secs: u64,
+    /// Attempts made before a delivery becomes a dead letter.
+    #[getset(get = "pub with_prefix")]
+    max_attempts: i32,
+    /// Delay before the first retry, doubled after every failed attempt.
+    #[getset(get = "pub with_prefix")]
+    backoff_base_secs: i64,
+    /// Longest delay between two attempts.
+    #[getset(get = "pub with_prefix")]
+    backoff_max_secs: i64,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct SessionConfig {
+    /// How long a session may go unused before it expires; 0 disables the timeout.
+    #[getset(get = "pub with_prefix")]
+    idle_timeout_secs: i64,
+    /// Whether every refresh extends the session by its lifetime.
+    #[getset(get = "pub with_prefix")]
+    sliding_expiration: bool,
+    /// Longest a session may live from login, however active or long its lifetime.
+    #[getset(get = "pub with_prefix")]
+    max_lifetime_secs: i64,
+    /// Lifetime of persistent sessions, opened with "remember me", in place of
+    /// `jwt.refresh_token_expiration_secs`.
+    #[getset(get = "pub with_prefix")]
+    remember_me_lifetime_secs: i64,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct SessionReaperConfig {
+    #[getset(get = "pub with_prefix")]
+    enabled: bool,
+    /// How often expired and revoked sessions, invites, API keys and impersonations are
+    /// looked for.
+    #[getset(get = "pub with_prefix")]
+    interval_secs: u64,
+    /// Rows deleted per statement, so that a large backlog does not hold long locks.
+    #[getset(get = "pub with_prefix")]
+    batch_size: i64,
+    /// How long sessions, invites, API keys and impersonations are kept after they expire,
+    /// end or are revoked.
+    #[getset(get = "pub with_prefix")]
+    retention_secs: i64,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct RedisConfig {
+    #[getset(get = "pub with_prefix")]
+    host: String,
+    #[getset(get = "pub with_prefix")]
+    port: u16,
+    #[getset(get = "pub with_prefix")]
+    db: i64,
+    #[getset(get = "pub with_prefix")]
+    tls_mode: bool,
+}
+
+impl RedisConfig {
+    /// Builds the connection URL from the individual settings.
+    pub fn get_url(&self) -> String {
+        let scheme = if self.tls_mode { "rediss" } else { "redis" };
+        format!("{}://{}:{}/{}", scheme, self.host, self.port, self.db)
+    }
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct MetricsConfig {
+    /// Whether `/metrics` is served in the Prometheus text format.
+    #[getset(get = "pub with_prefix")]
+    enabled: bool,
+    /// Address the separate admin listener binds to, loopback by default so that it stays
+    /// unreachable from the outside even when the API listens on every interface.
+    #[getset(get = "pub with_prefix")]
+    host: String,
+    /// Port of a separate admin listener serving `/metrics`; 0 serves it alongside the API
+    /// instead, to admins only.
+    #[getset(get = "pub with_prefix")]
+    port: u16,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct TelemetryConfig {
+    /// Base URL of an OTLP/HTTP collector traces are exported to, such as
+    /// `http://127.0.0.1:4318`; empty disables the export.
+    #[getset(get = "pub with_prefix")]
+    otlp_endpoint: String,
+    /// Name the service reports in its traces.
+    #[getset(get = "pub with_prefix")]
+    service_name: String,
+    /// Share of the traces started here that are exported; traces continued from a caller
+    /// follow its sampling decision.
+    #[getset(get = "pub with_prefix")]
+    sample_ratio: f64,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct RateLimitConfig {
+    /// Requests allowed per client over a sliding window.
+    #[getset(get = "pub with_prefix")]
+    requests_per_window: u64,
+    /// Length of the window, in seconds.
+    #[getset(get = "pub with_prefix")]
+    window_size: u64,
+    /// Redis holding the counters shared by every instance; built from `redis.*` when empty.
+    /// Counters are kept in memory while Redis is unreachable.
+    #[getset(get = "pub with_prefix")]
+    redis_uri: String,
+    #[getset(get = "pub with_prefix")]
+    key_strategy: KeyStrategy,
+    /// Budget of each client for `GET` requests, instead of the one above.
+    #[getset(get = "pub with_prefix")]
+    read: RateLimitPolicyConfig,
+    /// Budget of each IP address across the login, registration and refresh endpoints.
+    #[getset(get = "pub with_prefix")]
+    auth: RateLimitPolicyConfig,
+    /// Budget of each username, email or refresh token on those same endpoints.
+    #[getset(get = "pub with_prefix")]
+    identifier: RateLimitPolicyConfig,
+}
+
+#[derive(Debug, Deserialize, Getters, Clone)]
+pub struct RateLimitPolicyConfig {
+    /// Requests allowed over a sliding window.
+    #[getset(get = "pub with_prefix")]
+    requests_per_window: u64,
+    /// Length of the window, in seconds.
+    #[getset(get = "pub with_prefix")]
+    window_size: u64,
+}
+
+/// What identifies a client to the rate limiter.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyStrategy {
+    /// The client IP address.
+    Ip,
+    /// The user ID of a valid access token, falling back to a valid API key, then the IP
+    /// address.
+    User,
+    /// The presented access token or API key once found valid, falling back to the IP
+    /// address.
+    Token,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordHashAlgorithm {
+    Argon2id,
+    Argon2i,
+    Argon2d,
+}
@@ -0,0 +1,291 @@
+#![deny(missing_docs)]
+//! Verification of password hashes imported from other systems.
+//!
+//! These formats are only ever verified, never produced: after a successful
+//! login the hash is replaced with a current Argon2 hash.
+//!
+//! Supported formats:
+//! - bcrypt modular crypt strings (`$2a$`, `$2b$`, `$2x$`, `$2y$`).
+//! - PHC strings for scrypt (`$scrypt$`) and PBKDF2 (`$pbkdf2$`, `$pbkdf2-sha256$`, `$pbkdf2-sha512$`).
+//! - Django's `pbkdf2_sha256$`, `pbkdf2_sha1$`, `bcrypt$` and `bcrypt_sha256$` formats.
+//!
+//! Hashes are fully parsed before use, and rejected when their digest does not have the
+//! length the algorithm produces or their cost lies outside [`BCRYPT_COSTS`],
+//! [`PBKDF2_ITERATIONS`] or the scrypt bounds, so that a crafted hash can neither verify
+//! any password nor tie up the server on login.
+
+use std::ops::RangeInclusive;
+
+use anyhow::anyhow;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use pbkdf2::{pbkdf2_hmac, Pbkdf2};
+use scrypt::{
+    password_hash::{PasswordHash, PasswordVerifier},
+    Scrypt,
+};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+use super::AppResult;
+
+/// Bcrypt costs accepted; each step doubles the work of a verification.
+pub const BCRYPT_COSTS: RangeInclusive<u32> = 4..=16;
+
+/// PBKDF2 iteration counts accepted, in PHC and Django hashes alike.
+pub const PBKDF2_ITERATIONS: RangeInclusive<u32> = 1_000..=2_000_000;
+
+/// Largest scrypt `ln`, the log2 of its cost, accepted.
+const SCRYPT_MAX_LOG_N: u32 = 20;
+
+/// Largest scrypt block size `r` accepted.
+const SCRYPT_MAX_R: u32 = 32;
+
+/// Largest scrypt parallelism `p` accepted.
+const SCRYPT_MAX_P: u32 = 16;
+
+/// Largest memory an scrypt verification may take, 128 × r × 2^ln bytes.
+const SCRYPT_MAX_MEMORY: u64 = 256 * 1024 * 1024;
+
+/// Shortest scrypt digest accepted.
+const SCRYPT_MIN_OUTPUT_LEN: usize = 16;
+
+/// The legacy hash formats recognized by [`verify_legacy_password`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LegacyFormat {
+    Bcrypt,
+    Scrypt,
+    Pbkdf2,
+    DjangoPbkdf2Sha256,
+    DjangoPbkdf2Sha1,
+    DjangoBcrypt,
+    DjangoBcryptSha256,
+}
+
+impl LegacyFormat {
+    fn detect(password_hash: &str) -> Option<Self> {
+        const PREFIXES: &[(&str, LegacyFormat)] = &[
+            ("$2a$", LegacyFormat::Bcrypt),
+            ("$2b$", LegacyFormat::Bcrypt),
+            ("$2x$", LegacyFormat::Bcrypt),
+            ("$2y$", LegacyFormat::Bcrypt),
+            ("$scrypt$", LegacyFormat::Scrypt),
+            ("$pbkdf2$", LegacyFormat::Pbkdf2),
+            ("$pbkdf2-sha256$", LegacyFormat::Pbkdf2),
+            ("$pbkdf2-sha512$", LegacyFormat::Pbkdf2),
+            ("pbkdf2_sha256$", LegacyFormat::DjangoPbkdf2Sha256),
+            ("pbkdf2_sha1$", LegacyFormat::DjangoPbkdf2Sha1),
+            ("bcrypt$", LegacyFormat::DjangoBcrypt),
+            ("bcrypt_sha256$", LegacyFormat::DjangoBcryptSha256),
+        ];
+
+        PREFIXES
+            .iter()
+            .find(|(prefix, _)| password_hash.starts_with(prefix))
+            .map(|(_, format)| *format)
+    }
+}
+
+/// Returns whether `password_hash` is in one of the supported legacy formats.
+///
+/// Only the prefix is looked at; see [`validate_legacy_hash`] to check the rest.
+pub fn is_legacy_hash(password_hash: &str) -> bool {
+    LegacyFormat::detect(password_hash).is_some()
+}
+
+/// Fully parses a legacy hash, checking its digest length and cost parameters.
+///
+/// ## Returns
+/// - `AppResult<()>`: An error describing why the hash is unusable, including when it is
+///   in no supported legacy format.
+pub fn validate_legacy_hash(password_hash: &str) -> AppResult<()> {
+    let format = LegacyFormat::detect(password_hash)
+        .ok_or_else(|| anyhow!("Unsupported password hash format"))?;
+
+    match format {
+        LegacyFormat::Bcrypt => validate_bcrypt(password_hash),
+        LegacyFormat::Scrypt => validate_scrypt(password_hash),
+        LegacyFormat::Pbkdf2 => validate_pbkdf2(password_hash),
+        LegacyFormat::DjangoPbkdf2Sha256 | LegacyFormat::DjangoPbkdf2Sha1 => {
+            parse_django_pbkdf2(password_hash, django_pbkdf2_len(format)).map(|_| ())
+        }
+        LegacyFormat::DjangoBcrypt => validate_bcrypt(&password_hash["bcrypt$".len()..]),
+        LegacyFormat::DjangoBcryptSha256 => {
+            validate_bcrypt(&password_hash["bcrypt_sha256$".len()..])
+        }
+    }
+}
+
+/// Verifies `password` against a legacy hash.
+///
+/// Returns `None` if `password_hash` is not in a recognized legacy format, so
+/// the caller can fall back to the native hash format.
+pub fn verify_legacy_password(password: &str, password_hash: &str) -> Option<AppResult<bool>> {
+    let format = LegacyFormat::detect(password_hash)?;
+    if let Err(e) = validate_legacy_hash(password_hash) {
+        return Some(Err(e));
+    }
+
+    Some(match format {
+        LegacyFormat::Bcrypt => verify_bcrypt(password.as_bytes(), password_hash),
+        LegacyFormat::Scrypt => verify_phc(&Scrypt, password, password_hash),
+        LegacyFormat::Pbkdf2 => verify_phc(&Pbkdf2, password, password_hash),
+        LegacyFormat::DjangoPbkdf2Sha256 => verify_django_pbkdf2(
+            password,
+            password_hash,
+            django_pbkdf2_len(format),
+            pbkdf2_hmac::<Sha256>,
+        ),
+        LegacyFormat::DjangoPbkdf2Sha1 => verify_django_pbkdf2(
+            password,
+            password_hash,
+            django_pbkdf2_len(format),
+            pbkdf2_hmac::<Sha1>,
+        ),
+        LegacyFormat::DjangoBcrypt => {
+            verify_bcrypt(password.as_bytes(), &password_hash["bcrypt$".len()..])
+        }
+        LegacyFormat::DjangoBcryptSha256 => {
+            // Django pre-hashes with SHA-256 to lift bcrypt's 72-byte limit
+            let digest: String = Sha256::digest(password.as_bytes())
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            verify_bcrypt(digest.as_bytes(), &password_hash["bcrypt_sha256$".len()..])
+        }
+    })
+}
+
+fn validate_bcrypt(password_hash: &str) -> AppResult<()> {
+    let parts = bcrypt::HashParts::from_str(password_hash)
+        .map_err(|e| anyhow!("Unable to parse bcrypt password ({})", e))?;
+    if !BCRYPT_COSTS.contains(&parts.get_cost()) {
+        return Err(anyhow!("Bcrypt cost {} is out of range", parts.get_cost()));
+    }
+    Ok(())
+}
+
+fn validate_scrypt(password_hash: &str) -> AppResult<()> {
+    let hash = parse_phc(password_hash)?;
+    let params = scrypt::Params::try_from(&hash)
+        .map_err(|e| anyhow!("Unable to parse scrypt parameters ({})", e))?;
+    let (log_n, r, p) = (u32::from(params.log_n()), params.r(), params.p());
+    if log_n == 0 || log_n > SCRYPT_MAX_LOG_N || r > SCRYPT_MAX_R || p > SCRYPT_MAX_P {
+        return Err(anyhow!("Scrypt parameters are out of range"));
+    }
+    if (128 * u64::from(r)) << log_n > SCRYPT_MAX_MEMORY {
+        return Err(anyhow!("Scrypt parameters take too much memory"));
+    }
+    match &hash.hash {
+        Some(output) if output.len() >= SCRYPT_MIN_OUTPUT_LEN => Ok(()),
+        _ => Err(anyhow!("Scrypt hash is missing or too short")),
+    }
+}
+
+fn validate_pbkdf2(password_hash: &str) -> AppResult<()> {
+    let hash = parse_phc(password_hash)?;
+    let output_len = match hash.algorithm.as_str() {
+        "pbkdf2" => 20,
+        "pbkdf2-sha256" => 32,
+        "pbkdf2-sha512" => 64,
+        algorithm => return Err(anyhow!("Unsupported PBKDF2 algorithm {}", algorithm)),
+    };
+    let params = pbkdf2::Params::try_from(&hash)
+        .map_err(|e| anyhow!("Unable to parse PBKDF2 parameters ({})", e))?;
+    if !PBKDF2_ITERATIONS.contains(&params.rounds) {
+        return Err(anyhow!(
+            "PBKDF2 iterations {} are out of range",
+            params.rounds
+        ));
+    }
+    match &hash.hash {
+        Some(output) if output.len() == output_len && params.output_length == output_len => Ok(()),
+        _ => Err(anyhow!("PBKDF2 hash is missing or of the wrong length")),
+    }
+}
+
+fn parse_phc(password_hash: &str) -> AppResult<PasswordHash<'_>> {
+    let hash = PasswordHash::new(password_hash)
+        .map_err(|e| anyhow!("Unable to parse password ({})", e))?;
+    if hash.salt.is_none() {
+        return Err(anyhow!("Password hash has no salt"));
+    }
+    Ok(hash)
+}
+
+fn verify_bcrypt(password: &[u8], password_hash: &str) -> AppResult<bool> {
+    bcrypt::verify(password, password_hash)
+        .map_err(|e| anyhow!("Unable to parse bcrypt password ({})", e))
+}
+
+fn verify_phc(
+    verifier: &impl PasswordVerifier,
+    password: &str,
+    password_hash: &str,
+) -> AppResult<bool> {
+    let hash = PasswordHash::new(password_hash)
+        .map_err(|e| anyhow!("Unable to parse password ({})", e))?;
+    Ok(verifier.verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+/// The digest length of Django's PBKDF2 formats, that of their PRF.
+fn django_pbkdf2_len(format: LegacyFormat) -> usize {
+    match format {
+        LegacyFormat::DjangoPbkdf2Sha1 => 20,
+        _ => 32,
+    }
+}
+
+/// Parses Django's `<algorithm>$<iterations>$<salt>$<base64 hash>` format into the
+/// iterations, salt and digest, which must be `output_len` bytes long.
+fn parse_django_pbkdf2(password_hash: &str, output_len: usize) -> AppResult<(u32, &str, Vec<u8>)> {
+    let mut parts = password_hash.splitn(4, '$').skip(1);
+    let (Some(iterations), Some(salt), Some(expected)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(anyhow!("Unable to parse Django PBKDF2 password"));
+    };
+
+    let iterations: u32 = iterations
+        .parse()
+        .map_err(|e| anyhow!("Unable to parse Django PBKDF2 iterations ({})", e))?;
+    if !PBKDF2_ITERATIONS.contains(&iterations) {
+        return Err(anyhow!(
+            "Django PBKDF2 iterations {} are out of range",
+            iterations
+        ));
+    }
+    if salt.is_empty() {
+        return Err(anyhow!("Django PBKDF2 password has no salt"));
+    }
+    let expected = STANDARD
+        .decode(expected)
+        .map_err(|e| anyhow!("Unable to decode Django PBKDF2 hash ({})", e))?;
+    if expected.len() != output_len {
+        return Err(anyhow!("Django PBKDF2 hash has the wrong length"));
+    }
+    Ok((iterations, salt, expected))
+}
+
+/// Verifies Django's `<algorithm>$<iterations>$<salt>$<base64 hash>` format.
+fn verify_django_pbkdf2(
+    password: &str,
+    password_hash: &str,
+    output_len: usize,
+    derive: fn(&[u8], &[u8], u32, &mut [u8]),
+) -> AppResult<bool> {
+    let (iterations, salt, expected) = parse_django_pbkdf2(password_hash, output_len)?;
+
+    let mut derived = vec![0u8; output_len];
+    derive(
+        password.as_bytes(),
+        salt.as_bytes(),
+        iterations,
+        &mut derived,
+    );
+
+    Ok(constant_time_eq(&derived, &expected))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,125 @@
+#![deny(missing_docs)]
+//! Prometheus metrics of the service.
+//!
+//! Metrics are recorded through the `metrics` facade into a process-wide Prometheus
+//! recorder, installed by [`install_metrics`] when `metrics.enabled` is set. Until then,
+//! recording is a no-op. Gauges reflecting the state of the service, such as active
+//! sessions and database connections, are sampled when the metrics are scraped.
+
+use std::{sync::OnceLock, time::Duration};
+
+use anyhow::Context;
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::PgPool;
+
+use super::AppResult;
+
+/// Buckets of the request latency histogram, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The recorder installed for the process, shared by every router.
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Installs the Prometheus recorder on first use and returns a handle rendering its metrics.
+pub fn install_metrics() -> AppResult<PrometheusHandle> {
+    if let Some(handle) = HANDLE.get() {
+        return Ok(handle.clone());
+    }
+
+    let recorder = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full("http_request_duration_seconds".to_string()),
+            LATENCY_BUCKETS,
+        )
+        .context("Failed to configure metrics")?
+        .build_recorder();
+    let handle = recorder.handle();
+    // Losing a race to install leaves the winner in place, whose handle is then returned
+    if HANDLE.set(handle).is_ok() {
+        metrics::set_global_recorder(recorder).context("Failed to install metrics recorder")?;
+        describe_metrics();
+    }
+    HANDLE.get().cloned().context("Metrics recorder missing")
+}
+
+/// Describes every metric, so that the first scrape lists them with their help text.
+fn describe_metrics() {
+    describe_counter!("http_requests_total", "HTTP requests by route and status");
+    describe_histogram!(
+        "http_request_duration_seconds",
+        metrics::Unit::Seconds,
+        "HTTP request latency by route and status"
+    );
+    describe_counter!("auth_logins_total", "Login attempts by outcome");
+    describe_counter!("auth_tokens_issued_total", "Tokens issued by kind");
+    describe_counter!("auth_session_refreshes_total", "Sessions refreshed");
+    describe_gauge!(
+        "auth_active_sessions",
+        "Sessions neither expired nor revoked"
+    );
+    describe_gauge!("db_pool_connections", "Database connections by state");
+    describe_gauge!(
+        "db_pool_max_connections",
+        "Largest size of the database pool"
+    );
+    describe_counter!(
+        "rate_limit_rejections_total",
+        "Requests rejected by rate limit policy"
+    );
+}
+
+/// Records a handled HTTP request.
+///
+/// # Arguments
+///
+/// * `method` - The request method.
+/// * `route` - The route template matched, such as `/users/:id`, keeping labels bounded.
+/// * `status` - The response status code.
+/// * `latency` - How long the request took.
+pub fn record_request(method: &str, route: &str, status: u16, latency: Duration) {
+    let labels = [
+        ("method", method.to_string()),
+        ("route", route.to_string()),
+        ("status", status.to_string()),
+    ];
+    counter!("http_requests_total", &labels).increment(1);
+    histogram!("http_request_duration_seconds", &labels).record(latency.as_secs_f64());
+}
+
+/// Records a login attempt, whether it `succeeded` or was rejected.
+pub fn record_login(succeeded: bool) {
+    let outcome = if succeeded { "success" } else { "failure" };
+    counter!("auth_logins_total", "outcome" => outcome).increment(1);
+}
+
+/// Records a token issued, of `kind` `access` or `refresh`.
+pub fn record_token_issued(kind: &'static str) {
+    counter!("auth_tokens_issued_total", "kind" => kind).increment(1);
+}
+
+/// Records a session refreshed.
+pub fn record_session_refresh() {
+    counter!("auth_session_refreshes_total").increment(1);
+}
+
+/// Records a request rejected by the rate limit policy named `policy`.
+pub fn record_rate_limit_rejection(policy: &str) {
+    counter!("rate_limit_rejections_total", "policy" => policy.to_string()).increment(1);
+}
+
+/// Samples the number of active sessions.
+pub fn record_active_sessions(count: i64) {
+    gauge!("auth_active_sessions").set(count as f64);
+}
+
+/// Samples the utilisation of the database pool.
+pub fn record_db_pool(pool: &PgPool) {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+    gauge!("db_pool_connections", "state" => "idle").set(idle);
+    gauge!("db_pool_connections", "state" => "in_use").set(size.saturating_sub(idle));
+    gauge!("db_pool_max_connections").set(pool.options().get_max_connections());
+}
@@ -1,7 +1,17 @@
+mod breached_passwords;
 mod config;
+mod legacy_password;
+mod metrics;
 mod password;
+mod password_policy;
+mod rate_limiter;
 mod response;
 
+pub use breached_passwords::*;
 pub use config::*;
+pub use legacy_password::*;
+pub use metrics::*;
 pub use password::*;
+pub use password_policy::*;
+pub use rate_limiter::*;
 pub use response::*;
@@ -1,27 +1,179 @@
 use anyhow::anyhow;
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
-    Argon2, PasswordHash, PasswordVerifier,
+    password_hash::{rand_core::OsRng, Encoding, PasswordHasher, SaltString},
+    Algorithm, Argon2, KeyId, Params, ParamsBuilder, PasswordHash, PasswordVerifier, Version,
 };
+use sha1::{Digest, Sha1};
 
-use super::AppResult;
+use super::{
+    is_legacy_hash, validate_legacy_hash, verify_legacy_password, AppResult, PasswordHashAlgorithm,
+    PasswordHashingConfig,
+};
+
+/// Length of the key identifier derived from the pepper and stored in peppered hashes.
+const PEPPER_KEYID_LEN: usize = 6;
+
+/// Stored in place of a hash for accounts that have no password yet, such as invited
+/// users. It never parses as a hash, so no password verifies against it.
+pub const UNUSABLE_PASSWORD: &str = "!";
 
-pub fn hash_password(password: &str) -> AppResult<String> {
+pub fn hash_password(password: &str, config: &PasswordHashingConfig) -> AppResult<String> {
     let salt = SaltString::generate(&mut OsRng);
-    Argon2::default()
+    hasher(config)?
         .hash_password(password.as_bytes(), &salt)
         .map_err(|e| anyhow!("Unable to hash password ({})", e))
         .map(|hash| hash.to_string())
 }
 
-pub fn check_password(password: &str, password_hash: &str) -> AppResult<bool> {
-    let argon2 = Argon2::default();
+pub fn check_password(
+    password: &str,
+    password_hash: &str,
+    config: &PasswordHashingConfig,
+) -> AppResult<bool> {
+    if password_hash.starts_with(UNUSABLE_PASSWORD) {
+        return Ok(false);
+    }
+
+    if let Some(verified) = verify_legacy_password(password, password_hash) {
+        return verified;
+    }
 
     let hash = PasswordHash::new(password_hash)
         .map_err(|e| anyhow!("Unable to parse password ({})", e))?;
 
+    // Hashes created with a pepper carry its key id, verified with the current or a previous
+    // pepper; the rest are verified without one so that introducing a pepper does not lock
+    // out existing users.
+    let argon2 = match hash.params.get_str("keyid") {
+        Some(keyid) => {
+            let pepper = find_pepper(config, keyid).ok_or_else(|| {
+                anyhow!(
+                    "Password hash was created with an unknown pepper (keyid {}), missing from previous peppers",
+                    keyid
+                )
+            })?;
+            Argon2::new_with_secret(
+                pepper.as_bytes(),
+                Algorithm::default(),
+                Version::default(),
+                Default::default(),
+            )
+            .map_err(|e| anyhow!("Invalid password pepper ({})", e))?
+        }
+        None => Argon2::default(),
+    };
+
     match argon2.verify_password(password.as_bytes(), &hash) {
         Ok(_) => Ok(true),
         Err(_) => Ok(false),
     }
 }
+
+/// Reports whether `password_hash` is an Argon2 PHC string or a valid hash in a supported
+/// legacy format, i.e. whether it can be stored as-is for a user imported from another system.
+pub fn is_supported_hash(password_hash: &str) -> bool {
+    if is_legacy_hash(password_hash) {
+        return validate_legacy_hash(password_hash).is_ok();
+    }
+
+    PasswordHash::new(password_hash).is_ok_and(|hash| {
+        [Algorithm::Argon2id, Algorithm::Argon2i, Algorithm::Argon2d]
+            .iter()
+            .any(|algorithm| hash.algorithm == algorithm.ident())
+            && hash.salt.is_some()
+            && hash.hash.is_some()
+            && Params::try_from(&hash).is_ok()
+    })
+}
+
+/// Reports whether a stored hash is in a legacy format or was produced with a different
+/// algorithm, cost parameters or pepper than currently configured, and should be re-hashed.
+pub fn needs_rehash(password_hash: &str, config: &PasswordHashingConfig) -> bool {
+    if is_legacy_hash(password_hash) {
+        return true;
+    }
+
+    let Ok(hash) = PasswordHash::new(password_hash) else {
+        return true;
+    };
+
+    let algorithm: Algorithm = (*config.get_algorithm()).into();
+    let param = |name: &str| hash.params.get_decimal(name);
+
+    hash.algorithm != algorithm.ident()
+        || hash.version != Some(Version::default().into())
+        || param("m") != Some(*config.get_memory_cost())
+        || param("t") != Some(*config.get_time_cost())
+        || param("p") != Some(*config.get_parallelism())
+        || hash.params.get_str("keyid") != pepper_keyid(config).as_deref()
+}
+
+fn hasher(config: &PasswordHashingConfig) -> AppResult<Argon2<'_>> {
+    let mut builder = ParamsBuilder::new();
+    builder
+        .m_cost(*config.get_memory_cost())
+        .t_cost(*config.get_time_cost())
+        .p_cost(*config.get_parallelism());
+
+    let keyid = pepper_keyid(config);
+    if let Some(keyid) = &keyid {
+        builder.keyid(
+            keyid
+                .parse::<KeyId>()
+                .map_err(|e| anyhow!("Invalid pepper key id ({})", e))?,
+        );
+    }
+
+    let params = builder
+        .build()
+        .map_err(|e| anyhow!("Invalid password hashing parameters ({})", e))?;
+    let algorithm = (*config.get_algorithm()).into();
+
+    match keyid {
+        Some(_) => Argon2::new_with_secret(
+            config.get_pepper().as_bytes(),
+            algorithm,
+            Version::default(),
+            params,
+        )
+        .map_err(|e| anyhow!("Invalid password pepper ({})", e)),
+        None => Ok(Argon2::new(algorithm, Version::default(), params)),
+    }
+}
+
+/// Derives the B64-encoded key id identifying the configured pepper, if any.
+fn pepper_keyid(config: &PasswordHashingConfig) -> Option<String> {
+    keyid_of(config.get_pepper())
+}
+
+/// Finds the current or previous pepper identified by `keyid`, so that hashes made before
+/// a rotation still verify until they are re-hashed on login.
+fn find_pepper<'a>(config: &'a PasswordHashingConfig, keyid: &str) -> Option<&'a str> {
+    std::iter::once(config.get_pepper().as_str())
+        .chain(config.get_previous_peppers().split(',').map(str::trim))
+        .find(|pepper| keyid_of(pepper).as_deref() == Some(keyid))
+}
+
+/// Derives the B64-encoded key id identifying `pepper`, unless it is empty.
+fn keyid_of(pepper: &str) -> Option<String> {
+    if pepper.is_empty() {
+        return None;
+    }
+
+    let digest = Sha1::digest(pepper.as_bytes());
+    let mut buffer = [0u8; 16];
+    Encoding::B64
+        .encode(&digest[..PEPPER_KEYID_LEN], &mut buffer)
+        .ok()
+        .map(str::to_owned)
+}
+
+impl From<PasswordHashAlgorithm> for Algorithm {
+    fn from(algorithm: PasswordHashAlgorithm) -> Self {
+        match algorithm {
+            PasswordHashAlgorithm::Argon2id => Algorithm::Argon2id,
+            PasswordHashAlgorithm::Argon2i => Algorithm::Argon2i,
+            PasswordHashAlgorithm::Argon2d => Algorithm::Argon2d,
+        }
+    }
+}
@@ -0,0 +1,325 @@
+#![deny(missing_docs)]
+//! Password policy evaluation: length, character classes, strength scoring
+//! and rejection of passwords derived from the user's own identifiers.
+
+use axum::http::StatusCode;
+use serde::Serialize;
+
+use super::{AppError, PasswordPolicy};
+
+/// Upper bound on any password we are willing to hash, regardless of policy.
+pub const MAX_PASSWORD_LENGTH: usize = 1024;
+
+/// The minimum length of a user identifier before it is matched against passwords.
+const MIN_USER_INFO_LENGTH: usize = 3;
+
+/// Weight given to characters that repeat or continue a sequence (e.g. `aaa`, `abc`, `321`).
+const PATTERN_CHAR_WEIGHT: f64 = 0.25;
+
+/// A short list of the most common passwords, matched after lowercasing and un-leeting.
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456",
+    "123456789",
+    "12345678",
+    "password",
+    "qwerty",
+    "qwerty123",
+    "1234567",
+    "111111",
+    "1234567890",
+    "123123",
+    "abc123",
+    "000000",
+    "iloveyou",
+    "password1",
+    "qwertyuiop",
+    "654321",
+    "123321",
+    "666666",
+    "dragon",
+    "monkey",
+    "letmein",
+    "football",
+    "baseball",
+    "welcome",
+    "sunshine",
+    "princess",
+    "admin",
+    "administrator",
+    "master",
+    "shadow",
+    "superman",
+    "trustno1",
+    "passw0rd",
+    "starwars",
+    "whatever",
+    "freedom",
+    "charlie",
+    "michael",
+    "zaq12wsx",
+    "1q2w3e4r",
+    "asdfghjkl",
+    "changeme",
+    "secret",
+    "login",
+    "hello",
+    "access",
+    "mustang",
+    "computer",
+];
+
+/// The rule a password failed to satisfy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordRule {
+    /// The password is shorter than the configured minimum.
+    MinLength,
+    /// The password is longer than the configured maximum.
+    MaxLength,
+    /// The password has no uppercase letter.
+    Uppercase,
+    /// The password has no lowercase letter.
+    Lowercase,
+    /// The password has no digit.
+    Digit,
+    /// The password has no symbol.
+    Symbol,
+    /// The estimated strength score is below the configured minimum.
+    Strength,
+    /// The password contains the user's username or email.
+    UserInfo,
+    /// The password matches one of the user's recent passwords.
+    History,
+    /// The password appears in a known data breach.
+    Breached,
+}
+
+/// A single failed password rule with a user-facing explanation.
+#[derive(Debug, Clone, Serialize)]
+pub struct PasswordViolation {
+    /// The rule that was violated.
+    pub rule: PasswordRule,
+    /// A user-readable description of the violation.
+    pub message: String,
+    /// How many times the password was seen in breach data, for `Breached` violations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub occurrences: Option<u64>,
+}
+
+impl PasswordViolation {
+    /// Creates a new `PasswordViolation`.
+    pub fn new(rule: PasswordRule, message: impl Into<String>) -> Self {
+        Self {
+            rule,
+            message: message.into(),
+            occurrences: None,
+        }
+    }
+
+    /// Creates a `Breached` violation for a password seen `occurrences` times.
+    pub fn breached(occurrences: u64) -> Self {
+        Self {
+            occurrences: Some(occurrences),
+            ..Self::new(
+                PasswordRule::Breached,
+                format!(
+                    "Password has appeared {} {} in known data breaches",
+                    occurrences,
+                    if occurrences == 1 { "time" } else { "times" }
+                ),
+            )
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Checks a candidate password against every stateless rule of the policy.
+    ///
+    /// `user_inputs` are identifiers of the account (username, email) that the
+    /// password must not contain when `reject_user_info` is enabled.
+    ///
+    /// Returns every violated rule, or an empty list if the password is acceptable.
+    pub fn check(&self, password: &str, user_inputs: &[&str]) -> Vec<PasswordViolation> {
+        let mut violations = Vec::new();
+        let length = password.chars().count();
+
+        if length < *self.get_min_length() {
+            violations.push(PasswordViolation::new(
+                PasswordRule::MinLength,
+                format!(
+                    "Password must be at least {} characters long",
+                    self.get_min_length()
+                ),
+            ));
+        }
+
+        let max_length = (*self.get_max_length()).min(MAX_PASSWORD_LENGTH);
+        if length > max_length {
+            violations.push(PasswordViolation::new(
+                PasswordRule::MaxLength,
+                format!("Password must be at most {} characters long", max_length),
+            ));
+        }
+
+        let classes = [
+            (
+                *self.get_require_uppercase(),
+                password.chars().any(char::is_uppercase),
+                PasswordRule::Uppercase,
+                "Password must contain an uppercase letter",
+            ),
+            (
+                *self.get_require_lowercase(),
+                password.chars().any(char::is_lowercase),
+                PasswordRule::Lowercase,
+                "Password must contain a lowercase letter",
+            ),
+            (
+                *self.get_require_digit(),
+                password.chars().any(|c| c.is_ascii_digit()),
+                PasswordRule::Digit,
+                "Password must contain a digit",
+            ),
+            (
+                *self.get_require_symbol(),
+                password.chars().any(|c| !c.is_alphanumeric()),
+                PasswordRule::Symbol,
+                "Password must contain a symbol",
+            ),
+        ];
+        for (required, present, rule, message) in classes {
+            if required && !present {
+                violations.push(PasswordViolation::new(rule, message));
+            }
+        }
+
+        let score = estimate_strength(password);
+        if score < *self.get_min_strength_score() {
+            violations.push(PasswordViolation::new(
+                PasswordRule::Strength,
+                format!(
+                    "Password is too weak (score {} of 4, at least {} required)",
+                    score,
+                    self.get_min_strength_score()
+                ),
+            ));
+        }
+
+        if *self.get_reject_user_info() && contains_user_info(password, user_inputs) {
+            violations.push(PasswordViolation::new(
+                PasswordRule::UserInfo,
+                "Password must not contain your username or email",
+            ));
+        }
+
+        violations
+    }
+}
+
+/// Builds the `UNPROCESSABLE_ENTITY` error returned when a password violates the policy.
+pub fn password_policy_error(violations: Vec<PasswordViolation>) -> AppError {
+    AppError::new(
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "Password does not meet policy requirements",
+    )
+    .with_details(violations)
+}
+
+/// Estimates the strength of a password on a `0..=4` scale, in the spirit of zxcvbn.
+///
+/// The estimate is the base-10 logarithm of the number of guesses needed to
+/// brute-force the password, derived from its character pool and an effective
+/// length that discounts repeated and sequential characters. Common passwords
+/// and their trivial variants are capped at the lowest scores.
+pub fn estimate_strength(password: &str) -> u8 {
+    let lowered = password.to_lowercase();
+    if is_common_password(&lowered) {
+        return 0;
+    }
+
+    let log10_guesses = effective_length(password) * (character_pool(password) as f64).log10();
+    let score = match log10_guesses {
+        g if g < 3.0 => 0,
+        g if g < 6.0 => 1,
+        g if g < 8.0 => 2,
+        g if g < 10.0 => 3,
+        _ => 4,
+    };
+
+    // A common password with a few digits or symbols tacked on is barely stronger.
+    let stem = lowered.trim_end_matches(|c: char| !c.is_alphabetic());
+    if is_common_password(stem) {
+        return score.min(1);
+    }
+
+    score
+}
+
+fn is_common_password(lowered: &str) -> bool {
+    COMMON_PASSWORDS.contains(&lowered) || COMMON_PASSWORDS.contains(&unleet(lowered).as_str())
+}
+
+fn character_pool(password: &str) -> u32 {
+    let mut pool = 0;
+    if password.chars().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if password
+        .chars()
+        .any(|c| c.is_ascii_punctuation() || c == ' ')
+    {
+        pool += 33;
+    }
+    if !password.is_ascii() {
+        pool += 100;
+    }
+    pool.max(1)
+}
+
+fn effective_length(password: &str) -> f64 {
+    let mut length = 0.0;
+    let mut previous: Option<u32> = None;
+    for c in password.chars().map(u32::from) {
+        let is_pattern = previous
+            .map(|p| p == c || p.abs_diff(c) == 1)
+            .unwrap_or(false);
+        length += if is_pattern { PATTERN_CHAR_WEIGHT } else { 1.0 };
+        previous = Some(c);
+    }
+    length
+}
+
+fn unleet(password: &str) -> String {
+    password
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | '!' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .collect()
+}
+
+fn contains_user_info(password: &str, user_inputs: &[&str]) -> bool {
+    let password = password.to_lowercase();
+    user_inputs
+        .iter()
+        .flat_map(|input| {
+            let input = input.trim().to_lowercase();
+            let local_part = input.split('@').next().map(str::to_owned);
+            [Some(input), local_part]
+        })
+        .flatten()
+        .filter(|input| input.chars().count() >= MIN_USER_INFO_LENGTH)
+        .any(|input| password.contains(&input))
+}
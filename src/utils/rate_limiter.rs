@@ -0,0 +1,537 @@
+#![deny(missing_docs)]
+//! Sliding-window rate limiting shared across instances through Redis.
+//!
+//! Each policy counts the requests of a client in fixed windows and estimates the
+//! sliding window from the current and previous ones: a client that sent `previous`
+//! requests in the last window and `current` so far in this one is charged
+//! `previous * (1 - elapsed / window) + current`. Rejected requests are not counted.
+//!
+//! Counters live in Redis so that every instance enforces the same budget. While Redis is
+//! unreachable they are kept in memory, per instance, and Redis is tried again periodically.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use redis::{
+    aio::{ConnectionManager, ConnectionManagerConfig},
+    AsyncCommands, Script,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{AppResult, RateLimitConfig, RateLimitPolicyConfig, RedisConfig};
+
+/// How long to keep using memory after failing to connect to Redis.
+const REDIS_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long Redis may take to connect or answer before the request is counted in memory.
+const REDIS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Checks the budget and counts the request only when it is allowed.
+///
+/// `KEYS[1]` and `KEYS[2]` are the current and previous window counters, `ARGV[1]` the
+/// weight of the previous window in thousandths, `ARGV[2]` the limit and `ARGV[3]` the
+/// counter lifetime in milliseconds.
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+local current = tonumber(redis.call('GET', KEYS[1]) or '0')
+local previous = tonumber(redis.call('GET', KEYS[2]) or '0')
+if math.floor(previous * tonumber(ARGV[1]) / 1000) + current + 1 > tonumber(ARGV[2]) then
+    return {0, current, previous}
+end
+current = redis.call('INCR', KEYS[1])
+redis.call('PEXPIRE', KEYS[1], ARGV[3])
+return {1, current, previous}
+"#;
+
+/// A request budget: `requests` per sliding window of `window`.
+#[derive(Debug, Clone)]
+pub struct RateLimitPolicy {
+    /// Name of the policy, keeping its counters apart from other policies.
+    pub name: String,
+    /// Requests allowed per window.
+    pub requests: u64,
+    /// Length of the window.
+    pub window: Duration,
+}
+
+/// The outcome of checking a request against a policy.
+#[derive(Debug, Clone)]
+pub struct RateLimitDecision {
+    /// Whether the request may proceed.
+    pub allowed: bool,
+    /// Requests allowed per window.
+    pub limit: u64,
+    /// Requests left in the sliding window.
+    pub remaining: u64,
+    /// Seconds until the current window ends.
+    pub reset_secs: u64,
+    /// The policy, as `requests;w=seconds`.
+    pub policy: String,
+}
+
+/// The usage of every client of a policy, as reported by [`RateLimiter::state`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitPolicyState {
+    /// Name of the policy.
+    pub name: String,
+    /// Requests allowed per window.
+    pub requests: u64,
+    /// Length of the window, in seconds.
+    pub window_secs: u64,
+    /// Clients with requests in the sliding window, the busiest first.
+    pub clients: Vec<RateLimitClientState>,
+}
+
+/// The usage of one client of a policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitClientState {
+    /// What identifies the client, such as `ip:127.0.0.1`.
+    pub key: String,
+    /// Requests counted in the sliding window.
+    pub used: u64,
+    /// Requests left in the sliding window.
+    pub remaining: u64,
+}
+
+/// Rate limiter holding the counters of every policy and client.
+pub struct RateLimiter {
+    redis: Option<RedisBackend>,
+    memory: Mutex<MemoryCounters>,
+    policies: Mutex<Vec<RateLimitPolicy>>,
+}
+
+/// Position of the current time in the windows of a policy.
+#[derive(Debug, Clone, Copy)]
+struct WindowPosition {
+    window: u64,
+    window_ms: u64,
+    elapsed_ms: u64,
+    /// Share of the previous window still inside the sliding window, in thousandths.
+    weight: u64,
+}
+
+struct RedisBackend {
+    client: redis::Client,
+    connection: tokio::sync::Mutex<Option<ConnectionManager>>,
+    retry_at: Mutex<Option<Instant>>,
+    script: Script,
+}
+
+/// In-memory counters of one client for the current and previous windows.
+#[derive(Debug, Clone, Copy)]
+struct Counter {
+    window: u64,
+    window_ms: u64,
+    current: u64,
+    previous: u64,
+}
+
+/// The counters kept while Redis is unreachable, bounded by
+/// [`RateLimiter::MAX_MEMORY_COUNTERS`].
+#[derive(Debug, Default)]
+struct MemoryCounters {
+    counters: HashMap<String, Counter>,
+    /// When stale counters were last pruned, in milliseconds since the Unix epoch.
+    pruned_at_ms: u64,
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("redis", &self.redis.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RateLimitPolicy {
+    /// Creates a policy allowing `requests` per `window`.
+    pub fn new(name: impl Into<String>, requests: u64, window: Duration) -> Self {
+        Self {
+            name: name.into(),
+            requests,
+            window,
+        }
+    }
+
+    /// The policy configured under `rate_limit.*`.
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        Self::new(
+            "default",
+            *config.get_requests_per_window(),
+            Duration::from_secs(*config.get_window_size()),
+        )
+    }
+
+    /// A policy named `name` configured under a `rate_limit` section, such as `rate_limit.auth`.
+    pub fn from_policy_config(name: impl Into<String>, config: &RateLimitPolicyConfig) -> Self {
+        Self::new(
+            name,
+            *config.get_requests_per_window(),
+            Duration::from_secs(*config.get_window_size()),
+        )
+    }
+
+    fn position(&self) -> WindowPosition {
+        let window_ms = self.window.as_millis().max(1) as u64;
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let elapsed_ms = now_ms % window_ms;
+        WindowPosition {
+            window: now_ms / window_ms,
+            window_ms,
+            elapsed_ms,
+            weight: (window_ms - elapsed_ms) * 1000 / window_ms,
+        }
+    }
+}
+
+impl WindowPosition {
+    /// The current time, in milliseconds since the Unix epoch.
+    fn now_ms(&self) -> u64 {
+        self.window * self.window_ms + self.elapsed_ms
+    }
+
+    /// Fresh counters of a client in the current window.
+    fn counter(&self) -> Counter {
+        Counter {
+            window: self.window,
+            window_ms: self.window_ms,
+            current: 0,
+            previous: 0,
+        }
+    }
+
+    /// Requests charged in the sliding window given the counters of a client.
+    fn used(&self, counter: &Counter) -> u64 {
+        if counter.window == self.window {
+            counter.previous * self.weight / 1000 + counter.current
+        } else if counter.window + 1 == self.window {
+            counter.current * self.weight / 1000
+        } else {
+            0
+        }
+    }
+}
+
+impl Counter {
+    /// Whether the counter no longer charges anything, its windows having both passed.
+    fn is_stale(&self, now_ms: u64) -> bool {
+        (self.window + 2) * self.window_ms <= now_ms
+    }
+}
+
+impl RateLimiter {
+    /// Most clients counted in memory across policies while Redis is unreachable. Once
+    /// reached, requests of clients not counted yet are rejected until stale counters are
+    /// pruned, so that a flood of distinct clients cannot exhaust memory.
+    pub const MAX_MEMORY_COUNTERS: usize = 100_000;
+
+    /// Creates a limiter using the configured Redis, without connecting to it yet.
+    ///
+    /// The Redis URL is `rate_limit.redis_uri`, or built from `redis.*` when empty.
+    pub fn from_config(config: &RateLimitConfig, redis: &RedisConfig) -> AppResult<Self> {
+        let url = match config.get_redis_uri().as_str() {
+            "" => redis.get_url(),
+            uri => uri.to_string(),
+        };
+        Ok(Self::new(Some(redis::Client::open(url)?)))
+    }
+
+    /// Creates a limiter keeping its counters in memory only.
+    pub fn in_memory() -> Self {
+        Self::new(None)
+    }
+
+    fn new(client: Option<redis::Client>) -> Self {
+        Self {
+            redis: client.map(|client| RedisBackend {
+                client,
+                connection: tokio::sync::Mutex::new(None),
+                retry_at: Mutex::new(None),
+                script: Script::new(SLIDING_WINDOW_SCRIPT),
+            }),
+            memory: Mutex::new(MemoryCounters::default()),
+            policies: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Registers `policy` so that [`RateLimiter::state`] reports it. Registering a policy of
+    /// the same name again replaces it.
+    pub fn register(&self, policy: &RateLimitPolicy) {
+        let mut policies = self.policies.lock().unwrap_or_else(|e| e.into_inner());
+        policies.retain(|registered| registered.name != policy.name);
+        policies.push(policy.clone());
+    }
+
+    /// Checks a request of the client identified by `key` against `policy`, counting it if
+    /// it is allowed.
+    pub async fn check(&self, policy: &RateLimitPolicy, key: &str) -> RateLimitDecision {
+        let position = policy.position();
+
+        let counted = match &self.redis {
+            Some(redis) => redis.count(policy, key, &position).await,
+            None => None,
+        };
+        let (allowed, current, previous) =
+            counted.unwrap_or_else(|| self.count_in_memory(policy, key, &position));
+
+        let used = previous * position.weight / 1000 + current;
+        RateLimitDecision {
+            allowed,
+            limit: policy.requests,
+            remaining: policy.requests.saturating_sub(used),
+            reset_secs: (position.window_ms - position.elapsed_ms).div_ceil(1000),
+            policy: format!("{};w={}", policy.requests, policy.window.as_secs()),
+        }
+    }
+
+    /// Whether the counters are currently kept in Redis rather than in memory.
+    pub async fn is_shared(&self) -> bool {
+        match &self.redis {
+            Some(redis) => redis.connection().await.is_some(),
+            None => false,
+        }
+    }
+
+    /// Reports the usage of every registered policy, listing at most `max_clients` of the
+    /// busiest clients of each.
+    pub async fn state(&self, max_clients: usize) -> Vec<RateLimitPolicyState> {
+        let policies = self
+            .policies
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+
+        let mut states = Vec::with_capacity(policies.len());
+        for policy in policies {
+            let position = policy.position();
+            let usage = match &self.redis {
+                Some(redis) => redis.usage(&policy, &position).await,
+                None => None,
+            };
+            let mut clients: Vec<RateLimitClientState> = usage
+                .unwrap_or_else(|| self.usage_in_memory(&policy, &position))
+                .into_iter()
+                .filter(|(_, used)| *used > 0)
+                .map(|(key, used)| RateLimitClientState {
+                    key,
+                    used,
+                    remaining: policy.requests.saturating_sub(used),
+                })
+                .collect();
+            clients.sort_by(|a, b| b.used.cmp(&a.used).then_with(|| a.key.cmp(&b.key)));
+            clients.truncate(max_clients);
+
+            states.push(RateLimitPolicyState {
+                name: policy.name,
+                requests: policy.requests,
+                window_secs: policy.window.as_secs(),
+                clients,
+            });
+        }
+        states
+    }
+
+    fn usage_in_memory(
+        &self,
+        policy: &RateLimitPolicy,
+        position: &WindowPosition,
+    ) -> Vec<(String, u64)> {
+        let prefix = format!("{}:", policy.name);
+        self.memory
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .counters
+            .iter()
+            .filter_map(|(key, counter)| {
+                let key = key.strip_prefix(&prefix)?;
+                Some((key.to_string(), position.used(counter)))
+            })
+            .collect()
+    }
+
+    fn count_in_memory(
+        &self,
+        policy: &RateLimitPolicy,
+        key: &str,
+        position: &WindowPosition,
+    ) -> (bool, u64, u64) {
+        let WindowPosition { window, weight, .. } = *position;
+        let key = format!("{}:{}", policy.name, key);
+        let mut memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+
+        if memory.counters.len() >= Self::MAX_MEMORY_COUNTERS && !memory.counters.contains_key(&key)
+        {
+            // Pruning scans every counter, so it happens at most once per window
+            let now_ms = position.now_ms();
+            if now_ms >= memory.pruned_at_ms + position.window_ms {
+                memory
+                    .counters
+                    .retain(|_, counter| !counter.is_stale(now_ms));
+                memory.pruned_at_ms = now_ms;
+            }
+            if memory.counters.len() >= Self::MAX_MEMORY_COUNTERS {
+                return (false, policy.requests, 0);
+            }
+        }
+
+        let counter = memory
+            .counters
+            .entry(key)
+            .or_insert_with(|| position.counter());
+        if counter.window != window {
+            // Slide to the current window, dropping anything older than the previous one
+            counter.previous = if counter.window + 1 == window {
+                counter.current
+            } else {
+                0
+            };
+            counter.current = 0;
+            counter.window = window;
+        }
+
+        let allowed = counter.previous * weight / 1000 + counter.current < policy.requests;
+        if allowed {
+            counter.current += 1;
+        }
+        (allowed, counter.current, counter.previous)
+    }
+}
+
+impl RedisBackend {
+    /// Counts the request in Redis, or returns `None` when Redis is unavailable.
+    async fn count(
+        &self,
+        policy: &RateLimitPolicy,
+        key: &str,
+        position: &WindowPosition,
+    ) -> Option<(bool, u64, u64)> {
+        let mut connection = self.connection().await?;
+        let prefix = format!("ratelimit:{}:{}", policy.name, key);
+
+        let result: redis::RedisResult<(u64, u64, u64)> = self
+            .script
+            .key(format!("{}:{}", prefix, position.window))
+            .key(format!("{}:{}", prefix, position.window.saturating_sub(1)))
+            .arg(position.weight)
+            .arg(policy.requests)
+            .arg(position.window_ms * 2)
+            .invoke_async(&mut connection)
+            .await;
+        match result {
+            Ok((allowed, current, previous)) => Some((allowed == 1, current, previous)),
+            Err(e) => {
+                tracing::warn!("Rate limiting in memory, Redis failed: {}", e);
+                self.back_off().await;
+                None
+            }
+        }
+    }
+
+    /// Reads the usage of every client of `policy` from Redis, or returns `None` when Redis
+    /// is unavailable.
+    async fn usage(
+        &self,
+        policy: &RateLimitPolicy,
+        position: &WindowPosition,
+    ) -> Option<Vec<(String, u64)>> {
+        let mut connection = self.connection().await?;
+        let prefix = format!("ratelimit:{}:", policy.name);
+
+        let result: redis::RedisResult<Vec<(String, u64)>> = async {
+            let mut keys = Vec::new();
+            {
+                let mut iter = connection
+                    .scan_match::<_, String>(format!("{}*", prefix))
+                    .await?;
+                while let Some(key) = iter.next_item().await {
+                    keys.push(key);
+                }
+            }
+            if keys.is_empty() {
+                return Ok(Vec::new());
+            }
+            let values: Vec<Option<u64>> = connection.mget(&keys).await?;
+
+            // Keys are `ratelimit:<policy>:<client>:<window>`; fold both windows of a client
+            let mut counters: HashMap<String, Counter> = HashMap::new();
+            for (key, value) in keys.iter().zip(values) {
+                let Some((client, window)) = key
+                    .strip_prefix(&prefix)
+                    .and_then(|key| key.rsplit_once(':'))
+                else {
+                    continue;
+                };
+                let Ok(window) = window.parse::<u64>() else {
+                    continue;
+                };
+                let counter = counters
+                    .entry(client.to_string())
+                    .or_insert_with(|| position.counter());
+                if window == position.window {
+                    counter.current = value.unwrap_or(0);
+                } else if window + 1 == position.window {
+                    counter.previous = value.unwrap_or(0);
+                }
+            }
+            Ok(counters
+                .into_iter()
+                .map(|(client, counter)| (client, position.used(&counter)))
+                .collect())
+        }
+        .await;
+        match result {
+            Ok(usage) => Some(usage),
+            Err(e) => {
+                tracing::warn!("Reporting rate limits from memory, Redis failed: {}", e);
+                self.back_off().await;
+                None
+            }
+        }
+    }
+
+    /// Returns the shared connection, connecting first if needed and not recently failed.
+    async fn connection(&self) -> Option<ConnectionManager> {
+        let mut connection = self.connection.lock().await;
+        if let Some(connection) = connection.as_ref() {
+            return Some(connection.clone());
+        }
+
+        let retry_at = *self.retry_at.lock().unwrap_or_else(|e| e.into_inner());
+        if retry_at.is_some_and(|retry_at| Instant::now() < retry_at) {
+            return None;
+        }
+
+        let config = ConnectionManagerConfig::new()
+            .set_connection_timeout(REDIS_TIMEOUT)
+            .set_response_timeout(REDIS_TIMEOUT)
+            .set_number_of_retries(1);
+        match ConnectionManager::new_with_config(self.client.clone(), config).await {
+            Ok(manager) => {
+                tracing::info!("Rate limiting through Redis");
+                *connection = Some(manager.clone());
+                Some(manager)
+            }
+            Err(e) => {
+                tracing::warn!("Rate limiting in memory, unable to connect to Redis: {}", e);
+                *self.retry_at.lock().unwrap_or_else(|e| e.into_inner()) =
+                    Some(Instant::now() + REDIS_RETRY_INTERVAL);
+                None
+            }
+        }
+    }
+
+    /// Drops the shared connection after a failed command, so that requests fall back to
+    /// memory without waiting on Redis until `REDIS_RETRY_INTERVAL` has passed.
+    async fn back_off(&self) {
+        *self.connection.lock().await = None;
+        *self.retry_at.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some(Instant::now() + REDIS_RETRY_INTERVAL);
+    }
+}
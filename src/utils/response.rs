@@ -17,6 +17,8 @@ pub struct ErrorDetails {
     pub status: StatusCode,
     /// A user-facing error message.
     pub message: String,
+    /// Optional structured context, such as per-rule validation failures.
+    pub details: Option<serde_json::Value>,
 }
 
 impl ErrorDetails {
@@ -25,6 +27,7 @@ impl ErrorDetails {
         Self {
             status,
             message: message.into(),
+            details: None,
         }
     }
 }
@@ -49,11 +52,18 @@ impl AppError {
         Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
     }
 
+    /// Attaches structured details to the error, serialized alongside the message.
+    pub fn with_details(mut self, details: impl Serialize) -> Self {
+        self.details.details = serde_json::to_value(details).ok();
+        self
+    }
+
     /// Converts `AppError` into a `ErrorResponse`.
     pub fn into_error_response(&self) -> ErrorResponse {
         ErrorResponse {
             status: self.details.status.as_u16(),
             message: self.details.message.clone(),
+            details: self.details.details.clone(),
         }
     }
 
@@ -113,6 +123,9 @@ pub struct ErrorResponse {
     pub status: u16,
     /// A user-readable error message.
     pub message: String,
+    /// Optional structured context about the error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 fn add_security_headers(mut response: Response, status: StatusCode) -> Response {
@@ -0,0 +1,135 @@
+use auth::{
+    dto::{ApiKeyReqDto, CreateApiKeyResDto, GetAllApiKeysResDto},
+    models::{Role, User},
+    services,
+    utils::{hash_password, AppConfig, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+
+use common::{ctx, login};
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+const PASSWORD: &str = "Scr1pts-Need-Keys";
+
+fn create_key(token: &str, scopes: &[&str]) -> AppResult<Request<Body>> {
+    let api_key_req_dto = ApiKeyReqDto {
+        name: "ci".to_string(),
+        scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+        expires_at: None,
+    };
+    Ok(Request::builder()
+        .uri("/users/me/api-keys")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::from(serde_json::to_string(&api_key_req_dto)?))?)
+}
+
+#[sqlx::test]
+async fn test_api_keys(db_pool: PgPool) -> AppResult<()> {
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+
+    // Arrange: An admin and a regular user
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    for username in ["root", "member"] {
+        let user = User::new(
+            None,
+            format!("{}@example.com", username),
+            &password_hash,
+            username,
+            None,
+        );
+        services::create_user(&db_pool, &user).await?;
+        if username == "root" {
+            let admin = services::get_role_by_name(&db_pool, Role::ADMIN)
+                .await?
+                .expect("admin role should be seeded");
+            services::assign_user_role(&db_pool, user.id, &admin).await?;
+        }
+    }
+    let admin_token = login(&app, "root", PASSWORD).await?;
+    let member_token = login(&app, "member", PASSWORD).await?;
+
+    // Act & Assert: Keys cannot be scoped beyond the owner's permissions
+    let res = app
+        .clone()
+        .oneshot(create_key(&member_token, &["users:read"])?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Act: Create a read-only key
+    let res = app
+        .clone()
+        .oneshot(create_key(&admin_token, &["users:read"])?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let created: SuccessResponse<CreateApiKeyResDto> = serde_json::from_slice(&body)?;
+    let key = created.body.key;
+    assert!(key.starts_with("ak_"));
+    assert!(key.starts_with(&created.body.api_key.prefix));
+
+    // Assert: The key authenticates as a bearer token or through X-API-Key
+    let bearer = |uri: &str, method: &str| {
+        Request::builder()
+            .uri(uri)
+            .method(method)
+            .header("Authorization", format!("Bearer {}", key))
+            .body(Body::empty())
+    };
+    let res = app.clone().oneshot(bearer("/users", "GET")?).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/users")
+                .header("X-API-Key", &key)
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // Assert: Only the key's scopes apply, and keys cannot manage keys
+    let res = app.clone().oneshot(bearer("/users/export", "GET")?).await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = app.clone().oneshot(create_key(&key, &[])?).await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // Assert: The key is listed without its secret
+    let res = app
+        .clone()
+        .oneshot(bearer("/users/me/api-keys", "GET")?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    assert!(!String::from_utf8_lossy(&body).contains(&key));
+    let listed: SuccessResponse<GetAllApiKeysResDto> = serde_json::from_slice(&body)?;
+    assert_eq!(listed.body.api_keys.len(), 1);
+
+    // Act: Revoke the key
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/users/me/api-keys/{}", created.body.api_key.id))
+                .method("DELETE")
+                .header("Authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    // Assert: The key no longer authenticates
+    let res = app.clone().oneshot(bearer("/users", "GET")?).await?;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
@@ -0,0 +1,372 @@
+use auth::{
+    dto::{AuditChainReportDto, GetAllAuditEventsResDto},
+    models::{AuditEvent, Organization, Role, User},
+    services,
+    utils::{hash_password, AppConfig, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+
+use common::{ctx, login, login_request, request};
+use serde_json::json;
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+const PASSWORD: &str = "Wh0-D1d-Wh4t";
+
+fn get_audit_events(query: &str, token: &str) -> AppResult<Request<Body>> {
+    request(
+        "GET",
+        &format!("/audit-events{}", query),
+        token,
+        Body::empty(),
+    )
+}
+
+#[sqlx::test]
+async fn test_audit_events(db_pool: PgPool) -> AppResult<()> {
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+
+    // Arrange: An admin and a regular user
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let mut users = Vec::new();
+    for username in ["root", "alice"] {
+        let user = User::new(
+            None,
+            format!("{}@example.com", username),
+            &password_hash,
+            username,
+            None,
+        );
+        services::create_user(&db_pool, &user).await?;
+        users.push(user);
+    }
+    let (root, alice) = (&users[0], &users[1]);
+    let admin = services::get_role_by_name(&db_pool, Role::ADMIN)
+        .await?
+        .expect("admin role should be seeded");
+    services::assign_user_role(&db_pool, root.id, &admin).await?;
+
+    // Act: Alice mistypes her password, then logs in
+    let res = app
+        .clone()
+        .oneshot(login_request(
+            "alice",
+            "Not-Her-Passw0rd",
+            Some(("User-Agent", "audit-test")),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let alice_token = login(&app, "alice", PASSWORD).await?;
+    let root_token = login(&app, "root", PASSWORD).await?;
+
+    // Assert: Regular users cannot browse the audit log
+    let res = app
+        .clone()
+        .oneshot(get_audit_events("", &alice_token)?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // Assert: Both attempts are recorded, with where they came from
+    let res = app
+        .clone()
+        .oneshot(get_audit_events("?eventType=login.failed", &root_token)?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let failed: SuccessResponse<GetAllAuditEventsResDto> = serde_json::from_slice(&body)?;
+    assert_eq!(failed.body.audit_events.len(), 1);
+    let event = &failed.body.audit_events[0];
+    assert_eq!(event.actor_id, None);
+    assert_eq!(event.user_agent.as_deref(), Some("audit-test"));
+    assert_eq!(
+        event
+            .details
+            .as_ref()
+            .and_then(|details| details["login"].as_str()),
+        Some("alice")
+    );
+
+    let res = app
+        .clone()
+        .oneshot(get_audit_events(
+            &format!("?actorId={}", alice.id),
+            &root_token,
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let by_alice: SuccessResponse<GetAllAuditEventsResDto> = serde_json::from_slice(&body)?;
+    let event_types: Vec<&str> = by_alice
+        .body
+        .audit_events
+        .iter()
+        .map(|event| event.event_type.as_str())
+        .collect();
+    assert_eq!(event_types, vec![AuditEvent::LOGIN_SUCCEEDED]);
+
+    // Act: The admin deletes Alice
+    let res = app
+        .clone()
+        .oneshot(request(
+            "DELETE",
+            &format!("/users/{}", alice.id),
+            &root_token,
+            Body::empty(),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    // Assert: The deletion outlives the user, newest first
+    let res = app
+        .clone()
+        .oneshot(get_audit_events(
+            &format!("?targetId={}&limit=1", alice.id),
+            &root_token,
+        )?)
+        .await?;
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let latest: SuccessResponse<GetAllAuditEventsResDto> = serde_json::from_slice(&body)?;
+    assert_eq!(latest.body.audit_events.len(), 1);
+    assert_eq!(
+        latest.body.audit_events[0].event_type,
+        AuditEvent::USER_DELETED
+    );
+    assert_eq!(latest.body.audit_events[0].actor_id, Some(root.id));
+
+    // Assert: Every recorded event is chained
+    let res = app
+        .clone()
+        .oneshot(get_audit_events("/verify", &root_token)?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let report: SuccessResponse<AuditChainReportDto> = serde_json::from_slice(&body)?;
+    assert!(report.body.broken_link.is_none());
+    assert_eq!(report.body.unchained, 0);
+    assert!(report.body.checked >= 4);
+
+    Ok(())
+}
+
+/// Sends a request as the holder of `token`, with an optional JSON body.
+async fn send(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    token: &str,
+    body: Option<serde_json::Value>,
+) -> AppResult<(StatusCode, serde_json::Value)> {
+    let body = match body {
+        Some(body) => Body::from(serde_json::to_string(&body)?),
+        None => Body::empty(),
+    };
+    let res = app
+        .clone()
+        .oneshot(request(method, uri, token, body)?)
+        .await?;
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    Ok((status, serde_json::from_slice(&body).unwrap_or_default()))
+}
+
+#[sqlx::test]
+async fn test_audit_admin_events(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: An admin, a user and an organization
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let root = User::new(None, "root@example.com", &password_hash, "root", None);
+    services::create_user(&db_pool, &root).await?;
+    let alice = User::new(None, "alice@example.com", &password_hash, "alice", None);
+    services::create_user(&db_pool, &alice).await?;
+    let admin = services::get_role_by_name(&db_pool, Role::ADMIN)
+        .await?
+        .expect("admin role should be seeded");
+    services::assign_user_role(&db_pool, root.id, &admin).await?;
+    let organization = Organization::new("Acme", "acme");
+    services::create_organization(&db_pool, &organization).await?;
+    let token = login(&app, "root", PASSWORD).await?;
+
+    // Act: Create and revoke an API key, then add Alice to the organization and remove her
+    let (status, created) = send(
+        &app,
+        "POST",
+        "/users/me/api-keys",
+        &token,
+        Some(json!({ "name": "ci", "scopes": ["users:read"] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let key_id = created["body"]["id"].as_str().unwrap().to_string();
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &format!("/users/me/api-keys/{}", key_id),
+        &token,
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let member_uri = format!("/organizations/{}/members/{}", organization.id, alice.id);
+    let (status, _) = send(&app, "PUT", &member_uri, &token, Some(json!({}))).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "DELETE", &member_uri, &token, None).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // Act: Create an organization and a webhook subscription, change and delete the latter
+    let (status, created) = send(
+        &app,
+        "POST",
+        "/organizations",
+        &token,
+        Some(json!({ "name": "Globex", "slug": "globex" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let org_id = created["body"]["id"].as_str().unwrap().to_string();
+    let (status, created) = send(
+        &app,
+        "POST",
+        "/webhooks",
+        &token,
+        Some(json!({ "url": "https://example.com/hook", "event_types": [] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let hook_uri = format!("/webhooks/{}", created["body"]["id"].as_str().unwrap());
+    let (status, _) = send(
+        &app,
+        "PATCH",
+        &hook_uri,
+        &token,
+        Some(json!({ "is_active": false })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "DELETE", &hook_uri, &token, None).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // Act: Revoke every session
+    login(&app, "alice", PASSWORD).await?;
+    let (status, _) = send(&app, "PATCH", "/sessions", &token, None).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // Assert: Sessions of every user are revoked
+    for user_id in [root.id, alice.id] {
+        let session = services::get_session_by_user_id(&db_pool, user_id)
+            .await?
+            .expect("session should exist");
+        assert!(session.is_revoked);
+    }
+
+    // Assert: Each change is recorded along with what it was made to
+    let res = app
+        .clone()
+        .oneshot(get_audit_events(&format!("?actorId={}", root.id), &token)?)
+        .await?;
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let by_root: SuccessResponse<GetAllAuditEventsResDto> = serde_json::from_slice(&body)?;
+    let event_types: Vec<&str> = by_root
+        .body
+        .audit_events
+        .iter()
+        .map(|event| event.event_type.as_str())
+        .collect();
+    assert_eq!(
+        event_types,
+        vec![
+            AuditEvent::SESSION_REVOKED,
+            AuditEvent::WEBHOOK_DELETED,
+            AuditEvent::WEBHOOK_UPDATED,
+            AuditEvent::WEBHOOK_CREATED,
+            AuditEvent::ORG_CREATED,
+            AuditEvent::MEMBER_REMOVED,
+            AuditEvent::MEMBER_UPDATED,
+            AuditEvent::API_KEY_REVOKED,
+            AuditEvent::API_KEY_CREATED,
+            AuditEvent::LOGIN_SUCCEEDED,
+        ]
+    );
+    let created = &by_root.body.audit_events[4];
+    assert_eq!(created.details.as_ref().unwrap()["orgId"], org_id);
+    let updated = &by_root.body.audit_events[2];
+    assert_eq!(updated.details.as_ref().unwrap()["isActive"], false);
+    assert!(updated.details.as_ref().unwrap().get("secret").is_none());
+    let removed = &by_root.body.audit_events[5];
+    assert_eq!(removed.target_id, Some(alice.id));
+    assert_eq!(
+        removed.details.as_ref().unwrap()["orgId"],
+        organization.id.to_string()
+    );
+    let revoked = &by_root.body.audit_events[7];
+    assert_eq!(revoked.details.as_ref().unwrap()["apiKeyId"], key_id);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_audit_chain(db_pool: PgPool) -> AppResult<()> {
+    let key = Some(b"audit-signing-key".as_slice());
+
+    // Arrange: A few chained events
+    for event_type in [
+        AuditEvent::LOGIN_SUCCEEDED,
+        AuditEvent::USER_UPDATED,
+        AuditEvent::LOGOUT,
+    ] {
+        let event = AuditEvent::new(event_type).actor(Uuid::new_v4());
+        services::record_audit_event(&db_pool, key, event).await?;
+    }
+    let checkpoint = services::create_audit_checkpoint(&db_pool)
+        .await?
+        .expect("the chain should have a head");
+
+    // Act & Assert: The intact chain verifies, but not without its key
+    let report = services::verify_audit_chain(&db_pool, key).await?;
+    assert_eq!(report.checked, 3);
+    assert!(report.broken_link.is_none());
+    assert_eq!(report.head.as_deref(), Some(checkpoint.hash.as_str()));
+    let report = services::verify_audit_chain(&db_pool, None).await?;
+    assert_eq!(report.checked, 0);
+    assert!(report.broken_link.is_some());
+
+    // Act: Edit the second event
+    let events = services::get_audit_events(&db_pool, &Default::default(), 10, 0).await?;
+    let second = events
+        .iter()
+        .find(|event| event.event_type == AuditEvent::USER_UPDATED)
+        .expect("the event should be recorded");
+    sqlx::query("UPDATE audit_events SET actor_id = NULL WHERE id = $1")
+        .bind(second.id)
+        .execute(&db_pool)
+        .await?;
+
+    // Assert: The edited event is reported as the first broken link
+    let report = services::verify_audit_chain(&db_pool, key).await?;
+    assert_eq!(report.checked, 1);
+    let broken_link = report.broken_link.expect("the chain should be broken");
+    assert_eq!(broken_link.id, second.id);
+
+    // Act: Remove the edited event along with the rest of the log
+    sqlx::query("DELETE FROM audit_events WHERE seq >= $1")
+        .bind(second.seq)
+        .execute(&db_pool)
+        .await?;
+
+    // Assert: The truncated chain still verifies, but not against its checkpoint
+    let report = services::verify_audit_chain(&db_pool, key).await?;
+    assert!(report.broken_link.is_none());
+    let broken_link = services::verify_audit_checkpoints(&db_pool, &[checkpoint]).await?;
+    assert!(broken_link.is_some());
+
+    Ok(())
+}
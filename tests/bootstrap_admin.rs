@@ -0,0 +1,31 @@
+use auth::{models::Role, services, utils::AppResult};
+use common::config;
+use sqlx::PgPool;
+
+mod common;
+
+#[sqlx::test]
+async fn test_bootstrap_admin(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: First-run admin configuration
+    let config = config(&[
+        ("bootstrap.admin_username", "Operator"),
+        ("bootstrap.admin_email", "ops@example.com"),
+        ("bootstrap.admin_password", "Boot-Strapp3d-Admin"),
+    ])?;
+
+    // Act: Bootstrap twice, as on two consecutive starts
+    services::bootstrap_admin(&db_pool, &config).await?;
+    services::bootstrap_admin(&db_pool, &config).await?;
+
+    // Assert: A single admin user is created
+    let user = services::get_user_by_email(&db_pool, "ops@example.com")
+        .await?
+        .expect("bootstrap admin should be created");
+    assert_eq!(user.username, "operator");
+    assert!(user.is_admin);
+
+    let roles = services::get_user_roles(&db_pool, user.id).await?;
+    assert!(roles.iter().any(Role::is_admin));
+
+    Ok(())
+}
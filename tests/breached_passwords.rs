@@ -0,0 +1,87 @@
+use auth::{
+    dto::UserReqDto,
+    utils::{AppResult, PasswordViolation},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+
+use common::ctx_with;
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+fn sha1_hex(password: &str) -> String {
+    Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect()
+}
+
+#[sqlx::test]
+async fn test_breached_password(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: A sorted corpus in the Pwned Passwords download format
+    let breached = "Tr0ub4dor&3xQ";
+    let mut lines: Vec<String> = ["correct horse", "hunter2hunter2", "Zx9!Zx9!Zx9!"]
+        .iter()
+        .map(|p| format!("{}:7", sha1_hex(p)))
+        .collect();
+    lines.push(format!("{}:4242", sha1_hex(breached)));
+    lines.sort();
+
+    let corpus = std::env::temp_dir().join(format!("pwned-{}.txt", uuid::Uuid::new_v4()));
+    std::fs::write(&corpus, lines.join("\r\n"))?;
+
+    let app = ctx_with(
+        db_pool,
+        &[
+            ("breached_passwords.enabled", "true"),
+            ("breached_passwords.path", &corpus.to_string_lossy()),
+        ],
+    )?;
+
+    let register_req_dto = UserReqDto {
+        username: Some("hieronymus".to_string()),
+        email: Some("hieronymus@example.com".to_string()),
+        password: breached.to_string(),
+        avatar_url: None,
+        github_id: None,
+    };
+
+    let register_req = Request::builder()
+        .uri("/users/register")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&register_req_dto)?))?;
+
+    // Act: Register with a breached password
+    let register_res = app.oneshot(register_req).await?;
+
+    // Assert: The password is refused with its breach count
+    assert_eq!(
+        register_res.status(),
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "Breached password should be rejected"
+    );
+
+    let body: Value =
+        serde_json::from_slice(&to_bytes(register_res.into_body(), usize::MAX).await?)?;
+    assert_eq!(body["details"][0]["rule"], "breached");
+    assert_eq!(body["details"][0]["occurrences"], 4242);
+    assert_eq!(
+        body["details"][0]["message"],
+        "Password has appeared 4242 times in known data breaches"
+    );
+    assert_eq!(
+        PasswordViolation::breached(1).message,
+        "Password has appeared 1 time in known data breaches"
+    );
+
+    std::fs::remove_file(corpus)?;
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use auth::{
+    cli::{run, Cli},
+    models::{Session, User},
+    services,
+    utils::{AppConfig, AppResult},
+};
+use chrono::Duration;
+use clap::Parser;
+use common::config;
+use sqlx::PgPool;
+
+mod common;
+
+#[sqlx::test]
+async fn test_purge_expired_sessions(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: One user with an expired session and one with a live session
+    let expired = services::create_user(
+        &db_pool,
+        &User::new(None, "expired@example.com", "hash", "expired", None),
+    )
+    .await?;
+    let live = services::create_user(
+        &db_pool,
+        &User::new(None, "live@example.com", "hash", "live", None),
+    )
+    .await?;
+    services::create_session(
+        &db_pool,
+        &Session::new(expired.id, "expired-token", Duration::seconds(-1)),
+    )
+    .await?;
+    services::create_session(
+        &db_pool,
+        &Session::new(live.id, "live-token", Duration::hours(1)),
+    )
+    .await?;
+
+    // Act: Purge the expired sessions, twice
+    let purged = services::purge_expired_sessions(&db_pool, Duration::zero(), 1).await?;
+    let purged_again = services::purge_expired_sessions(&db_pool, Duration::zero(), 1).await?;
+
+    // Assert: Only the expired session is gone
+    assert_eq!(purged, 1);
+    assert_eq!(purged_again, 0);
+    assert!(services::get_session_by_user_id(&db_pool, expired.id)
+        .await?
+        .is_none());
+    assert!(services::get_session_by_user_id(&db_pool, live.id)
+        .await?
+        .is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_check() -> AppResult<()> {
+    // Arrange: The test configuration, with a JWT secret long enough to pass
+    let config = config(&[("jwt.secret", &"x".repeat(32))])?;
+
+    // Act: Check it
+    let checks = services::check_config(&config).await;
+
+    // Assert: Every section is reported, and the test secrets pass
+    let names: Vec<_> = checks.iter().map(|check| check.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "server",
+            "jwt",
+            "password_policy",
+            "breached_passwords",
+            "rate_limit",
+            "database"
+        ]
+    );
+    assert!(checks
+        .iter()
+        .filter(|check| check.name != "database")
+        .all(|check| check.ok));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_keys_rotate() -> AppResult<()> {
+    // Arrange: A command that needs neither the database nor a valid key
+    dotenv::dotenv().ok();
+    let cli = Cli::try_parse_from(["auth", "keys", "rotate", "jwt"])?;
+
+    // Act & Assert: It runs, and unknown keys are rejected
+    run(cli, AppConfig::new()?).await?;
+    assert!(Cli::try_parse_from(["auth", "keys", "rotate", "audit"]).is_err());
+
+    Ok(())
+}
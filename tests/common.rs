@@ -1,14 +1,89 @@
-use anyhow::Ok;
+#![allow(dead_code)]
+
 use auth::{
     bootstrap::create_router,
-    utils::{AppConfig, AppResult},
+    dto::{LoginReqDto, LoginResDto},
+    utils::{AppConfig, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::Request,
+    Router,
 };
-use axum::Router;
 use sqlx::PgPool;
+use tower::ServiceExt;
 
 pub fn ctx(db_pool: PgPool) -> AppResult<Router> {
+    ctx_with(db_pool, &[])
+}
+
+/// Creates the router with the test configuration, changed by `overrides`.
+pub fn ctx_with(db_pool: PgPool, overrides: &[(&str, &str)]) -> AppResult<Router> {
+    create_router(db_pool, config(overrides)?)
+}
+
+/// Loads the test configuration with `overrides` such as `("session.idle_timeout_secs", "600")`,
+/// leaving the environment untouched so that tests running alongside are unaffected.
+pub fn config(overrides: &[(&str, &str)]) -> AppResult<AppConfig> {
     dotenv::dotenv().ok();
-    let config = AppConfig::new()?;
+    AppConfig::with_overrides(overrides)
+}
+
+/// Builds a JSON request authenticated with the access token `token`.
+pub fn request(method: &str, uri: &str, token: &str, body: Body) -> AppResult<Request<Body>> {
+    request_with_header(method, uri, token, body, None)
+}
+
+/// Builds a JSON request authenticated with the access token `token`, with an extra `header`
+/// such as `("User-Agent", "test")` when given.
+pub fn request_with_header(
+    method: &str,
+    uri: &str,
+    token: &str,
+    body: Body,
+    header: Option<(&str, &str)>,
+) -> AppResult<Request<Body>> {
+    let mut builder = Request::builder()
+        .uri(uri)
+        .method(method)
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {}", token));
+    if let Some((name, value)) = header {
+        builder = builder.header(name, value);
+    }
+    Ok(builder.body(body)?)
+}
+
+/// Builds the login request of `username`, whose email is `<username>@example.com`, with an
+/// extra `header` when given.
+pub fn login_request(
+    username: &str,
+    password: &str,
+    header: Option<(&str, &str)>,
+) -> AppResult<Request<Body>> {
+    let login_req_dto = LoginReqDto {
+        username: Some(username.to_string()),
+        email: Some(format!("{}@example.com", username)),
+        password: password.to_string(),
+        ..Default::default()
+    };
+    let mut builder = Request::builder()
+        .uri("/auth/login")
+        .method("POST")
+        .header("Content-Type", "application/json");
+    if let Some((name, value)) = header {
+        builder = builder.header(name, value);
+    }
+    Ok(builder.body(Body::from(serde_json::to_string(&login_req_dto)?))?)
+}
 
-    Ok(create_router(db_pool, config))
+/// Logs in as `username`, whose email is `<username>@example.com`, and returns the access token.
+pub async fn login(app: &Router, username: &str, password: &str) -> AppResult<String> {
+    let login_res = app
+        .clone()
+        .oneshot(login_request(username, password, None)?)
+        .await?;
+    let body = to_bytes(login_res.into_body(), usize::MAX).await?;
+    let login_res_dto: SuccessResponse<LoginResDto> = serde_json::from_slice(&body)?;
+    Ok(login_res_dto.body.access_token)
 }
@@ -0,0 +1,145 @@
+use auth::{
+    dto::{GetAllImpersonationsResDto, ImpersonateReqDto, ImpersonateResDto, UserResDto},
+    models::{Permission, Role, User},
+    services,
+    utils::{hash_password, AppConfig, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+
+use common::{ctx, login, request};
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+const PASSWORD: &str = "L0g-In-As-You";
+
+fn impersonate(user_id: Uuid, token: &str) -> AppResult<Request<Body>> {
+    let impersonate_req_dto = ImpersonateReqDto {
+        reason: Some("Ticket #42".to_string()),
+    };
+    request(
+        "POST",
+        &format!("/users/{}/impersonate", user_id),
+        token,
+        Body::from(serde_json::to_string(&impersonate_req_dto)?),
+    )
+}
+
+#[sqlx::test]
+async fn test_impersonation(db_pool: PgPool) -> AppResult<()> {
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+
+    // Arrange: An admin, a support agent allowed to impersonate, and a customer
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let mut users = Vec::new();
+    for username in ["root", "support", "customer"] {
+        let user = User::new(
+            None,
+            format!("{}@example.com", username),
+            &password_hash,
+            username,
+            None,
+        );
+        services::create_user(&db_pool, &user).await?;
+        users.push(user);
+    }
+    let (root, support, customer) = (&users[0], &users[1], &users[2]);
+
+    let admin = services::get_role_by_name(&db_pool, Role::ADMIN)
+        .await?
+        .expect("admin role should be seeded");
+    services::assign_user_role(&db_pool, root.id, &admin).await?;
+    let support_role = services::create_role(&db_pool, &Role::new("support", None)).await?;
+    services::set_role_permissions(
+        &db_pool,
+        support_role.id,
+        &[Permission::USERS_IMPERSONATE.to_string()],
+    )
+    .await?;
+    services::assign_user_role(&db_pool, support.id, &support_role).await?;
+
+    let root_token = login(&app, "root", PASSWORD).await?;
+    let support_token = login(&app, "support", PASSWORD).await?;
+
+    // Act & Assert: Admins cannot be impersonated by less privileged staff
+    let res = app
+        .clone()
+        .oneshot(impersonate(root.id, &support_token)?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // Act: Impersonate the customer
+    let res = app
+        .clone()
+        .oneshot(impersonate(customer.id, &support_token)?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let impersonated: SuccessResponse<ImpersonateResDto> = serde_json::from_slice(&body)?;
+    let token = impersonated.body.access_token;
+
+    // Assert: The token acts as the customer but cannot take privileged actions
+    let res = app
+        .clone()
+        .oneshot(request("GET", "/users/me", &token, Body::empty())?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let me: SuccessResponse<UserResDto> = serde_json::from_slice(&body)?;
+    assert_eq!(me.body.username, "customer");
+    let res = app
+        .clone()
+        .oneshot(request(
+            "PATCH",
+            "/users/me",
+            &token,
+            Body::from(r#"{"password": "An0ther-Passw0rd"}"#),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = app
+        .clone()
+        .oneshot(request("DELETE", "/users/me", &token, Body::empty())?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // Act: Stop the impersonation by logging out
+    let res = app
+        .clone()
+        .oneshot(request("POST", "/auth/logout", &token, Body::empty())?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    // Assert: The token no longer works and the audit trail records start and stop
+    let res = app
+        .clone()
+        .oneshot(request("GET", "/users/me", &token, Body::empty())?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let res = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/impersonations",
+            &root_token,
+            Body::empty(),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let listed: SuccessResponse<GetAllImpersonationsResDto> = serde_json::from_slice(&body)?;
+    assert_eq!(listed.body.impersonations.len(), 1);
+    let impersonation = &listed.body.impersonations[0];
+    assert_eq!(impersonation.actor_id, support.id);
+    assert_eq!(impersonation.user_id, customer.id);
+    assert_eq!(impersonation.reason.as_deref(), Some("Ticket #42"));
+    assert!(impersonation.ended_at.is_some());
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use auth::{
+    dto::{BulkFormat, ImportUsersQueryDto, LoginReqDto},
+    models::User,
+    services,
+    utils::{is_supported_hash, verify_legacy_password, AppResult},
+};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use pbkdf2::{pbkdf2_hmac, Pbkdf2};
+use scrypt::{
+    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
+    Scrypt,
+};
+use sha2::Sha256;
+
+use common::{config, ctx};
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+const PASSWORD: &str = "Migr4ted-From-Elsewhere";
+
+fn legacy_hashes() -> AppResult<Vec<(&'static str, String)>> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    let bcrypt = bcrypt::hash(PASSWORD, 4)?;
+
+    let scrypt = Scrypt
+        .hash_password_customized(
+            PASSWORD.as_bytes(),
+            None,
+            None,
+            scrypt::Params::new(10, 8, 1, 32).map_err(|e| anyhow::anyhow!("{}", e))?,
+            &salt,
+        )
+        .map_err(|e| anyhow::anyhow!("{}", e))?
+        .to_string();
+
+    let pbkdf2 = Pbkdf2
+        .hash_password_customized(
+            PASSWORD.as_bytes(),
+            None,
+            None,
+            pbkdf2::Params {
+                rounds: 1000,
+                output_length: 32,
+            },
+            &salt,
+        )
+        .map_err(|e| anyhow::anyhow!("{}", e))?
+        .to_string();
+
+    let mut derived = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(PASSWORD.as_bytes(), b"seasalt", 1000, &mut derived);
+    let django = format!("pbkdf2_sha256$1000$seasalt${}", STANDARD.encode(derived));
+
+    Ok(vec![
+        ("bcrypt", bcrypt),
+        ("scrypt", scrypt),
+        ("pbkdf2", pbkdf2),
+        ("django", django),
+    ])
+}
+
+#[sqlx::test]
+async fn test_login_with_legacy_hashes(db_pool: PgPool) -> AppResult<()> {
+    let app = ctx(db_pool.clone())?;
+
+    for (name, hash) in legacy_hashes()? {
+        // Arrange: A user imported with a legacy hash
+        let email = format!("{}@example.com", name);
+        let user = User::new(None, &email, hash, name, None);
+        services::create_user(&db_pool, &user).await?;
+
+        let login_req_dto = LoginReqDto {
+            username: Some(name.to_string()),
+            email: Some(email),
+            password: PASSWORD.to_string(),
+            ..Default::default()
+        };
+
+        let login_req = Request::builder()
+            .uri("/auth/login")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&login_req_dto)?))?;
+
+        // Act: Log in with the original password
+        let login_res = app.clone().oneshot(login_req).await?;
+
+        // Assert: Login succeeds and the hash is upgraded to Argon2
+        assert_eq!(
+            login_res.status(),
+            StatusCode::CREATED,
+            "Login with a {} hash should succeed",
+            name
+        );
+
+        let stored = services::get_user_by_id(&db_pool, user.id)
+            .await?
+            .expect("user should exist");
+        assert!(
+            stored.password_hash.starts_with("$argon2id$"),
+            "{} hash should be upgraded to Argon2, got {}",
+            name,
+            stored.password_hash
+        );
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_reject_malformed_legacy_hashes(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: Hashes with an empty or truncated digest, or out of range costs
+    let digest = STANDARD.encode([0u8; 32]);
+    let malformed = [
+        "pbkdf2_sha256$1000$seasalt$".to_string(),
+        format!("pbkdf2_sha256$1000$seasalt${}", STANDARD.encode([0u8; 4])),
+        format!("pbkdf2_sha1$1000$seasalt${}", digest),
+        format!("pbkdf2_sha256$0$seasalt${}", digest),
+        format!("pbkdf2_sha256$4294967295$seasalt${}", digest),
+        "$pbkdf2-sha256$i=4294967295,l=32$c2Vhc2FsdA$AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"
+            .to_string(),
+        "$scrypt$ln=30,r=8,p=1$c2Vhc2FsdA$AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA".to_string(),
+        "$2b$31$abcdefghijklmnopqrstuuABCDEFGHIJKLMNOPQRSTUVWXYZ01234".to_string(),
+        "bcrypt$garbage".to_string(),
+    ];
+
+    // Act & Assert: They are neither accepted nor able to verify a password
+    for hash in &malformed {
+        assert!(!is_supported_hash(hash), "{} should be rejected", hash);
+        assert!(
+            matches!(verify_legacy_password("anything", hash), Some(Err(_))),
+            "{} should not verify",
+            hash
+        );
+    }
+
+    // Arrange: A user stored with an empty digest despite the checks above
+    let app = ctx(db_pool.clone())?;
+    let user = User::new(None, "empty@example.com", &malformed[0], "empty", None);
+    services::create_user(&db_pool, &user).await?;
+    let login_req_dto = LoginReqDto {
+        username: Some("empty".to_string()),
+        email: Some("empty@example.com".to_string()),
+        password: "any-password-at-all".to_string(),
+        ..Default::default()
+    };
+    let login_req = Request::builder()
+        .uri("/auth/login")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&login_req_dto)?))?;
+
+    // Act & Assert: Any password does not log in
+    let login_res = app.oneshot(login_req).await?;
+    assert_ne!(login_res.status(), StatusCode::CREATED);
+
+    // Act: Import a row with an empty digest
+    let csv = format!(
+        "username,email,password_hash\nbypass,bypass@example.com,{}\n",
+        malformed[0]
+    );
+    let options = ImportUsersQueryDto {
+        format: BulkFormat::Csv,
+        dry_run: false,
+        invite: false,
+    };
+    let report =
+        services::import_users(&db_pool, &config(&[])?, None, csv.as_bytes(), &options).await?;
+
+    // Assert: The row is refused
+    assert_eq!((report.imported, report.failed), (0, 1));
+    assert_eq!(report.errors[0].message, "Unsupported password hash format");
+    assert!(services::get_user_by_username(&db_pool, "bypass")
+        .await?
+        .is_none());
+
+    Ok(())
+}
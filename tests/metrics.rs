@@ -0,0 +1,99 @@
+use auth::{
+    dto::LoginResDto,
+    models::{Role, User},
+    services,
+    utils::{hash_password, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    Router,
+};
+use common::{config, ctx, ctx_with};
+use serde_json::json;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+const PASSWORD: &str = "C0unt-Every-L0gin";
+
+async fn login(app: &Router, password: &str) -> AppResult<Option<String>> {
+    let req = Request::builder()
+        .uri("/auth/login")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&json!({
+            "username": "metrics",
+            "email": "metrics@example.com",
+            "password": password,
+        }))?))?;
+    let res = app.clone().oneshot(req).await?;
+    if res.status() != StatusCode::CREATED {
+        return Ok(None);
+    }
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let login_res_dto: SuccessResponse<LoginResDto> = serde_json::from_slice(&body)?;
+    Ok(Some(login_res_dto.body.access_token))
+}
+
+fn metrics_request(token: Option<&str>) -> AppResult<Request<Body>> {
+    let mut builder = Request::builder().uri("/metrics");
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
+    }
+    Ok(builder.body(Body::empty())?)
+}
+
+#[sqlx::test]
+async fn test_metrics(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: Metrics served alongside the API, and an admin to read them
+    let overrides = [("metrics.port", "0")];
+    let config = config(&overrides)?;
+    let app = ctx_with(db_pool.clone(), &overrides)?;
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let user = User::new(None, "metrics@example.com", password_hash, "metrics", None);
+    services::create_user(&db_pool, &user).await?;
+    let admin = services::get_role_by_name(&db_pool, Role::ADMIN)
+        .await?
+        .expect("admin role should be seeded");
+    services::assign_user_role(&db_pool, user.id, &admin).await?;
+
+    // Act: Fail to log in, then log in
+    assert!(login(&app, "wrong").await?.is_none());
+    let token = login(&app, PASSWORD).await?.expect("login should succeed");
+
+    // Act & Assert: Metrics are not served without a token
+    let res = app.clone().oneshot(metrics_request(None)?).await?;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    // Act
+    let res = app.clone().oneshot(metrics_request(Some(&token))?).await?;
+
+    // Assert: Requests, logins, tokens and sessions are exposed in the Prometheus format
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers()[header::CONTENT_TYPE]
+        .to_str()?
+        .starts_with("text/plain; version=0.0.4"));
+    let body = String::from_utf8(to_bytes(res.into_body(), usize::MAX).await?.to_vec())?;
+    assert!(
+        body.contains(r#"http_requests_total{method="POST",route="/auth/login",status="201"} 1"#)
+    );
+    assert!(body.contains(r#"http_request_duration_seconds_bucket{method="POST",route="/auth/login",status="401",le="+Inf"} 1"#));
+    assert!(body.contains(r#"auth_logins_total{outcome="success"} 1"#));
+    assert!(body.contains(r#"auth_logins_total{outcome="failure"} 1"#));
+    assert!(body.contains(r#"auth_tokens_issued_total{kind="refresh"} 1"#));
+    assert!(body.contains("auth_active_sessions 1"));
+    assert!(body.contains(r#"db_pool_connections{state="in_use"}"#));
+
+    // Act: Move the metrics to their own admin port, as by default
+    let app = ctx(db_pool.clone())?;
+    let res = app.oneshot(metrics_request(Some(&token))?).await?;
+
+    // Assert: They are no longer served alongside the API, and only on loopback by default
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    let config = common::config(&[("server.host", "0.0.0.0")])?;
+    assert_eq!(config.get_metrics().get_host(), "127.0.0.1");
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use auth::{
+    models::{Organization, User},
+    services,
+    utils::AppResult,
+};
+use sqlx::PgPool;
+
+/// Version of the migration adding organizations.
+const ORGANIZATIONS_VERSION: i64 = 20250110120000;
+
+#[sqlx::test(migrations = false)]
+async fn test_migrations(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: An empty database and the embedded migrations
+    let status = services::get_migration_status(&db_pool).await?;
+    assert!(status.iter().all(|migration| !migration.applied));
+    let latest = status.last().map(|migration| migration.version).unwrap();
+    let previous = status[status.len() - 2].version;
+
+    // Act: Apply them from two instances booting together
+    let (first, second) = tokio::join!(
+        services::run_migrations(&db_pool),
+        services::run_migrations(&db_pool)
+    );
+
+    // Assert: Both succeed and every migration is applied once
+    first?;
+    second?;
+    let status = services::get_migration_status(&db_pool).await?;
+    assert!(status
+        .iter()
+        .all(|migration| migration.applied && !migration.checksum_mismatch));
+    let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&db_pool)
+        .await?;
+    assert_eq!(users, 0);
+
+    // Act & Assert: Revert the latest migration, then apply it again
+    assert_eq!(
+        services::revert_migrations(&db_pool, None).await?,
+        vec![latest]
+    );
+    let status = services::get_migration_status(&db_pool).await?;
+    assert!(!status.last().unwrap().applied);
+    assert!(status[status.len() - 2].applied);
+
+    services::run_migrations(&db_pool).await?;
+    assert!(services::get_migration_status(&db_pool)
+        .await?
+        .iter()
+        .all(|migration| migration.applied));
+
+    // Act & Assert: Revert down to a target version
+    assert_eq!(
+        services::revert_migrations(&db_pool, Some(previous - 1)).await?,
+        vec![latest, previous]
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_revert_organizations(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: Two organizations with a user of the same name each
+    for slug in ["acme", "globex"] {
+        let organization = Organization::new(slug, slug);
+        services::create_organization(&db_pool, &organization).await?;
+        let user = User::new(None, "jane@example.com", "hash", "jane", None)
+            .with_org(Some(organization.id));
+        services::create_user(&db_pool, &user).await?;
+    }
+
+    // Act: Revert to before organizations
+    let result = services::revert_migrations(&db_pool, Some(ORGANIZATIONS_VERSION - 1)).await;
+
+    // Assert: The rollback stops rather than deleting the users
+    assert!(result.is_err());
+    let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&db_pool)
+        .await?;
+    assert_eq!(users, 2);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_global_users_unique(db_pool: PgPool) -> AppResult<()> {
+    // Arrange
+    let user = User::new(None, "jane@example.com", "hash", "jane", None);
+    services::create_user(&db_pool, &user).await?;
+
+    // Act: Insert a second user outside any organization with the same username
+    let twin = User::new(None, "twin@example.com", "hash", "jane", None);
+    let result = services::create_user(&db_pool, &twin).await;
+
+    // Assert: The database rejects it, whatever the application checked before
+    assert!(result.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,278 @@
+use auth::{
+    dto::{LoginReqDto, LoginResDto, MembershipReqDto, OrganizationReqDto},
+    models::{Membership, Organization, Role, User},
+    services,
+    utils::{hash_password, AppConfig, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    response::Response,
+    Router,
+};
+
+use common::{ctx, request};
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+const PASSWORD: &str = "Tenants-And-0rgs";
+
+async fn login(app: &Router, username: &str, org: Option<&str>) -> AppResult<Response> {
+    let login_req_dto = LoginReqDto {
+        username: Some(username.to_string()),
+        email: Some(format!("{}@example.com", username)),
+        password: PASSWORD.to_string(),
+        org: org.map(str::to_string),
+        ..Default::default()
+    };
+    let login_req = Request::builder()
+        .uri("/auth/login")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&login_req_dto)?))?;
+
+    Ok(app.clone().oneshot(login_req).await?)
+}
+
+async fn access_token(res: Response) -> AppResult<String> {
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let login_res_dto: SuccessResponse<LoginResDto> = serde_json::from_slice(&body)?;
+    Ok(login_res_dto.body.access_token)
+}
+
+#[sqlx::test]
+async fn test_org_scoped_administration(db_pool: PgPool) -> AppResult<()> {
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+
+    // Arrange: A global admin creates two organizations
+    let root = User::new(None, "root@example.com", &password_hash, "root", None);
+    services::create_user(&db_pool, &root).await?;
+    let admin = services::get_role_by_name(&db_pool, Role::ADMIN)
+        .await?
+        .expect("admin role should be seeded");
+    services::assign_user_role(&db_pool, root.id, &admin).await?;
+    let root_token = access_token(login(&app, "root", None).await?).await?;
+
+    let mut org_ids = Vec::new();
+    for slug in ["acme", "globex"] {
+        let org_req_dto = OrganizationReqDto {
+            name: slug.to_uppercase(),
+            slug: slug.to_string(),
+        };
+        let res = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/organizations",
+                &root_token,
+                Body::from(serde_json::to_string(&org_req_dto)?),
+            )?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        let org: SuccessResponse<Organization> = serde_json::from_slice(&body)?;
+        org_ids.push(org.body.id);
+    }
+    let (acme, globex) = (org_ids[0], org_ids[1]);
+
+    // Arrange: Each organization owns users; alice administers acme
+    let org_admin = services::get_role_by_name(&db_pool, Role::ORG_ADMIN)
+        .await?
+        .expect("org_admin role should be seeded");
+    let mut users = Vec::new();
+    for (username, org_id) in [("alice", acme), ("carol", acme), ("bob", globex)] {
+        let user = User::new(
+            None,
+            format!("{}@example.com", username),
+            &password_hash,
+            username,
+            None,
+        )
+        .with_org(Some(org_id));
+        services::create_user(&db_pool, &user).await?;
+        let role_id = (username == "alice").then_some(org_admin.id);
+        services::upsert_membership(&db_pool, &Membership::new(org_id, user.id, role_id)).await?;
+        users.push(user);
+    }
+    let (carol, bob) = (&users[1], &users[2]);
+
+    // Act: alice signs in to her own organization by default
+    let res = login(&app, "alice", None).await?;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let alice_token = access_token(res).await?;
+
+    // Assert: Only acme users are listed
+    let res = app
+        .clone()
+        .oneshot(request("GET", "/users", &alice_token, Body::empty())?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let listed: serde_json::Value = serde_json::from_slice(&body)?;
+    let mut usernames: Vec<&str> = listed["body"]["users"]
+        .as_array()
+        .expect("users should be listed")
+        .iter()
+        .filter_map(|user| user["username"].as_str())
+        .collect();
+    usernames.sort();
+    assert_eq!(usernames, ["alice", "carol"]);
+
+    // Assert: Users of other tenants are hidden
+    let res = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            &format!("/users/{}", carol.id),
+            &alice_token,
+            Body::empty(),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            &format!("/users/{}", bob.id),
+            &alice_token,
+            Body::empty(),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    let res = app
+        .clone()
+        .oneshot(request(
+            "DELETE",
+            &format!("/users/{}", bob.id),
+            &alice_token,
+            Body::empty(),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    // Assert: Members holding global roles cannot be changed from inside the organization
+    services::assign_user_role(&db_pool, carol.id, &admin).await?;
+    for (method, body) in [("PATCH", r#"{"username": "mallory"}"#), ("DELETE", "")] {
+        let res = app
+            .clone()
+            .oneshot(request(
+                method,
+                &format!("/users/{}", carol.id),
+                &alice_token,
+                Body::from(body),
+            )?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+    let res = app
+        .clone()
+        .oneshot(request(
+            "PATCH",
+            &format!("/sessions/{}", carol.id),
+            &alice_token,
+            Body::empty(),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    services::revoke_user_role(&db_pool, carol.id, &admin).await?;
+
+    // Assert: Organization management stays global
+    let res = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/organizations",
+            &alice_token,
+            Body::empty(),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // Act & Assert: alice manages acme members but cannot enroll other tenants' users
+    let member_req = |user_id: Uuid| -> AppResult<Request<Body>> {
+        request(
+            "PUT",
+            &format!("/organizations/{}/members/{}", acme, user_id),
+            &alice_token,
+            Body::from(serde_json::to_string(&MembershipReqDto::default())?),
+        )
+    };
+    let res = app.clone().oneshot(member_req(carol.id)?).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = app.clone().oneshot(member_req(bob.id)?).await?;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    let res = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            &format!("/organizations/{}/members", globex),
+            &alice_token,
+            Body::empty(),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    // Act & Assert: bob cannot sign in to an organization he is not a member of
+    let res = login(&app, "bob", Some("acme")).await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = login(&app, "bob", Some("initech")).await?;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    // Act & Assert: Once removed, carol can no longer sign in to acme
+    let res = app
+        .clone()
+        .oneshot(request(
+            "DELETE",
+            &format!("/organizations/{}/members/{}", acme, carol.id),
+            &alice_token,
+            Body::empty(),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let res = login(&app, "carol", None).await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // Act & Assert: Organizations cannot be deleted from under their users
+    let deleted = sqlx::query("DELETE FROM organizations WHERE id = $1")
+        .bind(globex)
+        .execute(&db_pool)
+        .await;
+    assert!(deleted.is_err());
+
+    // Act: The global admin deletes globex
+    let res = app
+        .clone()
+        .oneshot(request(
+            "DELETE",
+            &format!("/organizations/{}", globex),
+            &root_token,
+            Body::empty(),
+        )?)
+        .await?;
+
+    // Assert: Its users are deleted, each recorded in the outbox, and the deletion is audited
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    assert!(services::get_user_by_id(&db_pool, bob.id).await?.is_none());
+    let outbox: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM outbox_events WHERE event_type = 'user.deleted' AND payload->>'userId' = $1",
+    )
+    .bind(bob.id.to_string())
+    .fetch_one(&db_pool)
+    .await?;
+    assert_eq!(outbox, 1);
+    let details: serde_json::Value = sqlx::query_scalar(
+        "SELECT details FROM audit_events WHERE event_type = 'org.deleted' AND actor_id = $1",
+    )
+    .bind(root.id)
+    .fetch_one(&db_pool)
+    .await?;
+    assert_eq!(details["slug"], "globex");
+    assert_eq!(details["deletedUserIds"], serde_json::json!([bob.id]));
+
+    Ok(())
+}
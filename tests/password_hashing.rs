@@ -0,0 +1,140 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
+    Argon2, PasswordHash,
+};
+use auth::{
+    dto::LoginReqDto,
+    models::User,
+    services,
+    utils::{hash_password, AppResult},
+};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+
+use common::{config, ctx_with};
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+#[sqlx::test]
+async fn test_rehash_on_login(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: A user whose hash uses the library defaults and no pepper
+    let password = "Pl4in-Old-Hash";
+    let salt = SaltString::generate(&mut OsRng);
+    let old_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("{}", e))?
+        .to_string();
+
+    let user = User::new(None, "legacy@example.com", old_hash, "legacy", None);
+    services::create_user(&db_pool, &user).await?;
+
+    let app = ctx_with(
+        db_pool.clone(),
+        &[
+            ("password_hashing.time_cost", "3"),
+            ("password_hashing.pepper", "a-server-side-pepper"),
+        ],
+    )?;
+
+    let login_req_dto = LoginReqDto {
+        username: Some("legacy".to_string()),
+        email: Some("legacy@example.com".to_string()),
+        password: password.to_string(),
+        ..Default::default()
+    };
+
+    let login = || -> AppResult<Request<Body>> {
+        Ok(Request::builder()
+            .uri("/auth/login")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&login_req_dto)?))?)
+    };
+
+    // Act: Log in with the outdated hash
+    let login_res = app.clone().oneshot(login()?).await?;
+
+    // Assert: Login succeeds and the stored hash is upgraded
+    assert_eq!(login_res.status(), StatusCode::CREATED);
+
+    let stored = services::get_user_by_id(&db_pool, user.id)
+        .await?
+        .expect("user should exist");
+    let hash = PasswordHash::new(&stored.password_hash).map_err(|e| anyhow::anyhow!("{}", e))?;
+    assert_eq!(
+        hash.params.get_decimal("t"),
+        Some(3),
+        "Time cost should be raised"
+    );
+    assert!(
+        hash.params.get_str("keyid").is_some(),
+        "Upgraded hash should be peppered"
+    );
+
+    // Act: Log in again against the upgraded hash
+    let login_res = app.oneshot(login()?).await?;
+
+    // Assert: The peppered hash verifies
+    assert_eq!(login_res.status(), StatusCode::CREATED);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_pepper_rotation(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: A user whose hash uses a pepper since rotated out
+    let password = "R0tat3d-P3pper";
+    let old_config = config(&[("password_hashing.pepper", "old-pepper")])?;
+    let old_hash = hash_password(password, old_config.get_password_hashing())?;
+    let old_keyid = PasswordHash::new(&old_hash)
+        .map_err(|e| anyhow::anyhow!("{}", e))?
+        .params
+        .get_str("keyid")
+        .map(str::to_string);
+
+    let user = User::new(None, "rotated@example.com", old_hash, "rotated", None);
+    services::create_user(&db_pool, &user).await?;
+
+    let app = ctx_with(
+        db_pool.clone(),
+        &[
+            ("password_hashing.pepper", "new-pepper"),
+            (
+                "password_hashing.previous_peppers",
+                "older-pepper, old-pepper",
+            ),
+        ],
+    )?;
+
+    let login_req_dto = LoginReqDto {
+        username: Some("rotated".to_string()),
+        email: Some("rotated@example.com".to_string()),
+        password: password.to_string(),
+        ..Default::default()
+    };
+    let login_req = Request::builder()
+        .uri("/auth/login")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&login_req_dto)?))?;
+
+    // Act: Log in with the hash of the previous pepper
+    let login_res = app.oneshot(login_req).await?;
+
+    // Assert: Login succeeds and the stored hash moves to the current pepper
+    assert_eq!(login_res.status(), StatusCode::CREATED);
+
+    let stored = services::get_user_by_id(&db_pool, user.id)
+        .await?
+        .expect("user should exist");
+    let hash = PasswordHash::new(&stored.password_hash).map_err(|e| anyhow::anyhow!("{}", e))?;
+    let keyid = hash.params.get_str("keyid").map(str::to_string);
+    assert!(keyid.is_some(), "Re-hashed password should be peppered");
+    assert_ne!(keyid, old_keyid, "Hash should use the current pepper");
+
+    Ok(())
+}
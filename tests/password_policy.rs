@@ -0,0 +1,136 @@
+use std::borrow::BorrowMut;
+
+use auth::{
+    dto::{LoginReqDto, LoginResDto, UserReqDto},
+    utils::{AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+
+use common::ctx;
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+fn violated_rules(body: &Value) -> Vec<&str> {
+    body["details"]
+        .as_array()
+        .map(|violations| {
+            violations
+                .iter()
+                .filter_map(|v| v["rule"].as_str())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+async fn register(app: &mut Router, dto: &UserReqDto) -> AppResult<(StatusCode, Value)> {
+    let req = Request::builder()
+        .uri("/users/register")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(dto)?))?;
+
+    let res = app.borrow_mut().oneshot(req).await?;
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    Ok((status, serde_json::from_slice(&body)?))
+}
+
+#[sqlx::test]
+async fn test_password_policy(db_pool: PgPool) -> AppResult<()> {
+    let mut app = ctx(db_pool)?;
+
+    // Arrange: A short, trivially guessable password
+    let mut register_req_dto = UserReqDto {
+        username: Some("Cassandra".to_string()),
+        email: Some("cassandra@example.com".to_string()),
+        password: "abc".to_string(),
+        avatar_url: None,
+        github_id: None,
+    };
+
+    // Act: Send the registration request
+    let (status, body) = register(&mut app, &register_req_dto).await?;
+
+    // Assert: Every violated rule is reported
+    assert_eq!(
+        status,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "Weak password should be rejected with 422 Unprocessable Entity"
+    );
+    assert_eq!(violated_rules(&body), vec!["min_length", "strength"]);
+
+    // Arrange: A password containing the username
+    register_req_dto.password = "MyCassandra#2024".to_string();
+
+    // Act: Send the registration request
+    let (status, body) = register(&mut app, &register_req_dto).await?;
+
+    // Assert: The password is refused for containing user info
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(violated_rules(&body), vec!["user_info"]);
+
+    // Arrange: A strong password
+    register_req_dto.password = "Vq7#mZp2xLw!".to_string();
+
+    // Act: Send the registration request
+    let (status, _) = register(&mut app, &register_req_dto).await?;
+
+    // Assert: Registration succeeds
+    assert_eq!(
+        status,
+        StatusCode::CREATED,
+        "Strong password should be accepted"
+    );
+
+    // Arrange: Log in to change the password
+    let login_req_dto = LoginReqDto {
+        username: register_req_dto.username.clone(),
+        email: register_req_dto.email.clone(),
+        password: register_req_dto.password.clone(),
+        ..Default::default()
+    };
+
+    let login_req = Request::builder()
+        .uri("/auth/login")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&login_req_dto)?))?;
+
+    let login_res = app.borrow_mut().oneshot(login_req).await?;
+    let body = to_bytes(login_res.into_body(), usize::MAX).await?;
+    let login_res_dto: SuccessResponse<LoginResDto> = serde_json::from_slice(&body)?;
+
+    // Act: Try to change the password to the current one
+    let reuse_req = Request::builder()
+        .uri("/users/me")
+        .method("PATCH")
+        .header("Content-Type", "application/json")
+        .header(
+            "Authorization",
+            format!("Bearer {}", login_res_dto.body.access_token),
+        )
+        .body(Body::from(
+            serde_json::json!({ "password": register_req_dto.password }).to_string(),
+        ))?;
+
+    let reuse_res = app.oneshot(reuse_req).await?;
+
+    // Assert: Reuse of a recent password is refused
+    assert_eq!(
+        reuse_res.status(),
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "Reusing a recent password should be rejected"
+    );
+
+    let body: Value = serde_json::from_slice(&to_bytes(reuse_res.into_body(), usize::MAX).await?)?;
+    assert_eq!(violated_rules(&body), vec!["history"]);
+
+    Ok(())
+}
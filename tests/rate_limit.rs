@@ -0,0 +1,267 @@
+use std::time::Duration;
+
+use auth::{
+    dto::{GetRateLimitsResDto, LoginReqDto, LoginResDto},
+    models::{Role, User},
+    services,
+    token::{Grant, TokenManager},
+    utils::{hash_password, AppResult, RateLimitPolicy, RateLimiter, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use common::{config, ctx_with};
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+const PASSWORD: &str = "Wh0-D1d-Wh4t";
+
+/// Tight budgets, counted in memory as Redis is unreachable. Tests of this file run
+/// concurrently, so they all share these settings.
+const OVERRIDES: &[(&str, &str)] = &[
+    ("server.trust_forwarded_for", "true"),
+    ("rate_limit.requests_per_window", "100"),
+    ("rate_limit.window_size", "3600"),
+    ("rate_limit.redis_uri", "redis://127.0.0.1:1"),
+    ("rate_limit.key_strategy", "token"),
+    ("rate_limit.read.requests_per_window", "3"),
+    ("rate_limit.read.window_size", "3600"),
+    ("rate_limit.auth.requests_per_window", "4"),
+    ("rate_limit.auth.window_size", "3600"),
+    ("rate_limit.identifier.requests_per_window", "2"),
+    ("rate_limit.identifier.window_size", "3600"),
+];
+
+fn login_request(username: &str, password: &str, ip: &str) -> AppResult<Request<Body>> {
+    let login_req_dto = LoginReqDto {
+        username: Some(username.to_string()),
+        email: Some(format!("{}@example.com", username)),
+        password: password.to_string(),
+        ..Default::default()
+    };
+    Ok(Request::builder()
+        .uri("/auth/login")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .header("X-Forwarded-For", ip)
+        .body(Body::from(serde_json::to_string(&login_req_dto)?))?)
+}
+
+async fn login_status(app: &Router, username: &str, ip: &str) -> AppResult<StatusCode> {
+    let res = app
+        .clone()
+        .oneshot(login_request(username, "wrong-password", ip)?)
+        .await?;
+    Ok(res.status())
+}
+
+fn health_check(token: Option<&str>) -> AppResult<Request<Body>> {
+    let mut builder = Request::builder().uri("/");
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
+    }
+    Ok(builder.body(Body::empty())?)
+}
+
+#[sqlx::test]
+async fn test_rate_limit(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: A budget of three reads per client
+    let app = ctx_with(db_pool, OVERRIDES)?;
+
+    // Act & Assert: Requests within budget go through and report what is left
+    for remaining in (0..3).rev() {
+        let res = app.clone().oneshot(health_check(None)?).await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["ratelimit-limit"], "3");
+        assert_eq!(res.headers()["ratelimit-remaining"], remaining.to_string());
+        assert_eq!(res.headers()["ratelimit-policy"], "3;w=3600");
+    }
+
+    // Act & Assert: The next one is rejected with a hint of when to retry
+    let res = app.clone().oneshot(health_check(None)?).await?;
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(res.headers()["ratelimit-remaining"], "0");
+    let retry_after: u64 = res.headers()["retry-after"].to_str()?.parse()?;
+    assert!(retry_after > 0 && retry_after <= 3600);
+
+    // Act & Assert: Made-up tokens do not earn a budget of their own
+    let res = app
+        .clone()
+        .oneshot(health_check(Some("some-token"))?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Act & Assert: Clients presenting a valid token have a budget of their own
+    let config = config(OVERRIDES)?;
+    let token_manager = TokenManager::new(config.get_jwt().get_secret().as_bytes(), None);
+    let (token, _) = token_manager.create_access_token(
+        Uuid::new_v4(),
+        "limited@example.com",
+        Grant::default(),
+        chrono::Duration::minutes(5),
+    )?;
+    let res = app.clone().oneshot(health_check(Some(&token))?).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["ratelimit-remaining"], "2");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_auth_rate_limit(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: Two users and an admin, and an app allowing four credential attempts per IP
+    // address and two per account
+    let config = config(OVERRIDES)?;
+    let app = ctx_with(db_pool.clone(), OVERRIDES)?;
+
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    for username in ["root", "alice", "bob"] {
+        let user = User::new(
+            None,
+            format!("{}@example.com", username),
+            &password_hash,
+            username,
+            None,
+        );
+        services::create_user(&db_pool, &user).await?;
+        if username == "root" {
+            let admin = services::get_role_by_name(&db_pool, Role::ADMIN)
+                .await?
+                .expect("admin role should be seeded");
+            services::assign_user_role(&db_pool, user.id, &admin).await?;
+        }
+    }
+
+    // Act & Assert: Guessing the password of one account is stopped first, however its
+    // username is spelled
+    assert_eq!(
+        login_status(&app, "alice", "10.0.0.1").await?,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        login_status(&app, "Alice", "10.0.0.1").await?,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        login_status(&app, "alice", "10.0.0.1").await?,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+
+    // Act & Assert: Moving to another account only lasts until the IP address runs out
+    assert_eq!(
+        login_status(&app, "bob", "10.0.0.1").await?,
+        StatusCode::UNAUTHORIZED
+    );
+    let res = app
+        .clone()
+        .oneshot(login_request("bob", "wrong-password", "10.0.0.1")?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(res.headers()["ratelimit-policy"], "4;w=3600");
+
+    // Act & Assert: Other IP addresses are unaffected
+    let res = app
+        .clone()
+        .oneshot(login_request("root", PASSWORD, "10.0.0.2")?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let login_res_dto: SuccessResponse<LoginResDto> = serde_json::from_slice(&body)?;
+    let token = login_res_dto.body.access_token;
+
+    // Act: The admin looks at the limiter
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/rate-limits")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("X-Forwarded-For", "10.0.0.2")
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    // Assert: Every policy is listed with the usage of its clients
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let res_dto: SuccessResponse<GetRateLimitsResDto> = serde_json::from_slice(&body)?;
+    assert_eq!(res_dto.body.backend, "memory");
+    let mut names: Vec<&str> = res_dto
+        .body
+        .policies
+        .iter()
+        .map(|policy| policy.name.as_str())
+        .collect();
+    names.sort_unstable();
+    assert_eq!(names, vec!["auth", "default", "identifier", "read"]);
+
+    let policy = |name| {
+        res_dto
+            .body
+            .policies
+            .iter()
+            .find(|policy| policy.name == name)
+            .expect("policy should be listed")
+    };
+    let auth = policy("auth");
+    assert_eq!(auth.clients[0].key, "ip:10.0.0.1");
+    assert_eq!((auth.clients[0].used, auth.clients[0].remaining), (4, 0));
+    let identifier = policy("identifier");
+    assert_eq!(identifier.clients.len(), 3);
+    assert_eq!(identifier.clients[0].used, 2);
+    assert!(identifier.clients[0].key.starts_with("identifier:"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sliding_window() {
+    // Arrange
+    let limiter = RateLimiter::in_memory();
+    let strict = RateLimitPolicy::new("strict", 2, Duration::from_secs(3600));
+    let generous = RateLimitPolicy::new("generous", 100, Duration::from_secs(3600));
+
+    // Act
+    let mut decisions = Vec::new();
+    for _ in 0..3 {
+        decisions.push(limiter.check(&strict, "ip:10.0.0.1").await.allowed);
+    }
+    let other_client = limiter.check(&strict, "ip:10.0.0.2").await;
+    let other_policy = limiter.check(&generous, "ip:10.0.0.1").await;
+
+    // Assert: Budgets are kept per policy and per client
+    assert_eq!(decisions, vec![true, true, false]);
+    assert!(other_client.allowed);
+    assert!(other_policy.allowed);
+    assert_eq!(other_policy.remaining, 99);
+}
+
+#[tokio::test]
+async fn test_memory_bound() {
+    // Arrange: As many clients counted in memory as the limiter keeps
+    let limiter = RateLimiter::in_memory();
+    let policy = RateLimitPolicy::new("default", 10, Duration::from_secs(3600));
+    for client in 0..RateLimiter::MAX_MEMORY_COUNTERS {
+        assert!(
+            limiter
+                .check(&policy, &format!("ip:{}", client))
+                .await
+                .allowed
+        );
+    }
+
+    // Act
+    let known = limiter.check(&policy, "ip:0").await;
+    let unknown = limiter.check(&policy, "ip:unknown").await;
+
+    // Assert: Counted clients keep their budget, new ones are turned away
+    assert!(known.allowed);
+    assert_eq!(known.remaining, 8);
+    assert!(!unknown.allowed);
+    assert_eq!(unknown.remaining, 0);
+}
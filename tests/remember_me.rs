@@ -0,0 +1,119 @@
+use auth::{
+    dto::{LoginReqDto, LoginResDto},
+    models::User,
+    services,
+    utils::{hash_password, AppConfig, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, HeaderMap, Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, Utc};
+use common::ctx;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+const PASSWORD: &str = "R3member-Me-N0t";
+
+async fn login(app: &Router, remember_me: bool) -> AppResult<(HeaderMap, LoginResDto)> {
+    let login_req_dto = LoginReqDto {
+        username: Some("remember".to_string()),
+        email: Some("remember@example.com".to_string()),
+        password: PASSWORD.to_string(),
+        remember_me,
+        ..Default::default()
+    };
+    let login_req = Request::builder()
+        .uri("/auth/login")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&login_req_dto)?))?;
+
+    let res = app.clone().oneshot(login_req).await?;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let headers = res.headers().clone();
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let login_res_dto = serde_json::from_slice::<SuccessResponse<LoginResDto>>(&body)?.body;
+    Ok((headers, login_res_dto))
+}
+
+fn set_cookie(headers: &HeaderMap) -> String {
+    headers
+        .get(header::SET_COOKIE)
+        .expect("a refresh token cookie should be set")
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[sqlx::test]
+async fn test_remember_me(db_pool: PgPool) -> AppResult<()> {
+    // Arrange
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let user = User::new(
+        None,
+        "remember@example.com",
+        password_hash,
+        "remember",
+        None,
+    );
+    services::create_user(&db_pool, &user).await?;
+
+    // Act: Log in without "remember me"
+    let (headers, browser) = login(&app, false).await?;
+
+    // Assert: The cookie lasts for the browser session only
+    let cookie = set_cookie(&headers);
+    assert!(!cookie.contains("Max-Age"));
+    assert!(!cookie.contains("Expires"));
+    let session = services::get_session_by_user_id(&db_pool, user.id)
+        .await?
+        .unwrap();
+    assert!(!session.is_persistent);
+
+    // Act: Log in again with "remember me"
+    let (headers, persistent) = login(&app, true).await?;
+
+    // Assert: The browser session is replaced by a persistent one with its own lifetime
+    assert_ne!(persistent.session_id, browser.session_id);
+    assert_eq!(
+        persistent.refresh_token_expires_at,
+        *config.get_session().get_remember_me_lifetime_secs()
+    );
+    let cookie = set_cookie(&headers);
+    assert!(cookie.contains("Max-Age"));
+    let session = services::get_session_by_user_id(&db_pool, user.id)
+        .await?
+        .unwrap();
+    assert!(session.is_persistent);
+    let lifetime = Duration::seconds(*config.get_session().get_remember_me_lifetime_secs());
+    assert!(
+        (session.expires_at - (Utc::now() + lifetime))
+            .num_seconds()
+            .abs()
+            < 60
+    );
+
+    // Act: Refresh through the cookie
+    let refresh_req = Request::builder()
+        .uri("/sessions/refresh-cookie")
+        .method("POST")
+        .header(header::COOKIE, cookie.split(';').next().unwrap())
+        .body(Body::empty())?;
+    let res = app.clone().oneshot(refresh_req).await?;
+
+    // Assert: The session keeps its class, and so does the cookie
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert!(set_cookie(res.headers()).contains("Max-Age"));
+    let session = services::get_session_by_user_id(&db_pool, user.id)
+        .await?
+        .unwrap();
+    assert!(session.is_persistent);
+
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use auth::{
+    dto::{RoleReqDto, RoleResDto},
+    models::{Role, User},
+    services,
+    utils::{hash_password, AppConfig, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+
+use common::{ctx, login};
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+const PASSWORD: &str = "Rol3s-And-Perms";
+
+#[sqlx::test]
+async fn test_role_permissions(db_pool: PgPool) -> AppResult<()> {
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+
+    // Arrange: An admin through the built-in role and a regular user
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    for username in ["root", "member"] {
+        let user = User::new(
+            None,
+            format!("{}@example.com", username),
+            &password_hash,
+            username,
+            None,
+        );
+        services::create_user(&db_pool, &user).await?;
+        if username == "root" {
+            let admin = services::get_role_by_name(&db_pool, Role::ADMIN)
+                .await?
+                .expect("admin role should be seeded");
+            services::assign_user_role(&db_pool, user.id, &admin).await?;
+        }
+    }
+    let admin_token = login(&app, "root", PASSWORD).await?;
+    let member_token = login(&app, "member", PASSWORD).await?;
+
+    let list_users = |token: &str| {
+        Request::builder()
+            .uri("/users")
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+    };
+
+    // Act & Assert: Listing users requires users:read
+    let res = app.clone().oneshot(list_users(&member_token)?).await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = app.clone().oneshot(list_users(&admin_token)?).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // Act: Create a role with an unknown permission
+    let create_role = |permissions: &[&str]| -> AppResult<Request<Body>> {
+        let role_req_dto = RoleReqDto {
+            name: "support".to_string(),
+            description: None,
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+        };
+        Ok(Request::builder()
+            .uri("/roles")
+            .method("POST")
+            .header("Authorization", format!("Bearer {}", admin_token))
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&role_req_dto)?))?)
+    };
+    let res = app.clone().oneshot(create_role(&["users:fly"])?).await?;
+
+    // Assert: Unknown permissions are rejected
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Act: Create a valid role and grant it to the regular user
+    let res = app.clone().oneshot(create_role(&["users:read"])?).await?;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let role: SuccessResponse<RoleResDto> = serde_json::from_slice(&body)?;
+    assert_eq!(role.body.permissions, vec!["users:read".to_string()]);
+
+    let member = services::get_user_by_username(&db_pool, "member")
+        .await?
+        .expect("member should exist");
+    let support = services::get_role_by_id(&db_pool, role.body.id)
+        .await?
+        .expect("role should exist");
+    services::assign_user_role(&db_pool, member.id, &support).await?;
+    services::delete_session_by_user_id(&db_pool, member.id).await?;
+    let member_token = login(&app, "member", PASSWORD).await?;
+
+    // Assert: The new permission applies from the next token
+    let res = app.oneshot(list_users(&member_token)?).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_grant_and_revoke_admin(db_pool: PgPool) -> AppResult<()> {
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+
+    // Arrange: A single admin and a regular user
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let root = User::new(None, "root@example.com", &password_hash, "root", None);
+    let member = User::new(None, "member@example.com", &password_hash, "member", None);
+    services::create_user(&db_pool, &root).await?;
+    services::create_user(&db_pool, &member).await?;
+    let admin = services::get_role_by_name(&db_pool, Role::ADMIN)
+        .await?
+        .expect("admin role should be seeded");
+    services::assign_user_role(&db_pool, root.id, &admin).await?;
+    let root_token = login(&app, "root", PASSWORD).await?;
+
+    let user_role = |method: &str, user_id: uuid::Uuid| {
+        Request::builder()
+            .uri(format!("/users/{}/roles/{}", user_id, admin.id))
+            .method(method)
+            .header("Authorization", format!("Bearer {}", root_token))
+            .body(Body::empty())
+    };
+
+    // Act: The last admin tries to demote themselves
+    let res = app.clone().oneshot(user_role("DELETE", root.id)?).await?;
+
+    // Assert: The last admin is protected
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    // Act: Promote the regular user, then step down
+    let res = app.clone().oneshot(user_role("PUT", member.id)?).await?;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let res = app.clone().oneshot(user_role("DELETE", root.id)?).await?;
+
+    // Assert: Demotion succeeds and the admin flag follows the role
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let root = services::get_user_by_id(&db_pool, root.id)
+        .await?
+        .expect("root should exist");
+    let member = services::get_user_by_id(&db_pool, member.id)
+        .await?
+        .expect("member should exist");
+    assert!(!root.is_admin);
+    assert!(member.is_admin);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_assign_only_held_permissions(db_pool: PgPool) -> AppResult<()> {
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+
+    // Arrange: An admin, and an assigner who may only assign roles and read users
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let root = User::new(None, "root@example.com", &password_hash, "root", None);
+    let assigner = User::new(
+        None,
+        "assigner@example.com",
+        &password_hash,
+        "assigner",
+        None,
+    );
+    services::create_user(&db_pool, &root).await?;
+    services::create_user(&db_pool, &assigner).await?;
+    let admin = services::get_role_by_name(&db_pool, Role::ADMIN)
+        .await?
+        .expect("admin role should be seeded");
+    services::assign_user_role(&db_pool, root.id, &admin).await?;
+    let assigning = services::create_role(&db_pool, &Role::new("assigning", None)).await?;
+    let permissions = ["roles:assign".to_string(), "users:read".to_string()];
+    services::set_role_permissions(&db_pool, assigning.id, &permissions).await?;
+    services::assign_user_role(&db_pool, assigner.id, &assigning).await?;
+    let reader = services::create_role(&db_pool, &Role::new("reader", None)).await?;
+    services::set_role_permissions(&db_pool, reader.id, &permissions[1..]).await?;
+    let token = login(&app, "assigner", PASSWORD).await?;
+
+    let user_role = |method: &str, user_id: uuid::Uuid, role_id: uuid::Uuid| {
+        Request::builder()
+            .uri(format!("/users/{}/roles/{}", user_id, role_id))
+            .method(method)
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+    };
+
+    // Act & Assert: The assigner can neither grant themselves admin nor demote the admin
+    let res = app
+        .clone()
+        .oneshot(user_role("PUT", assigner.id, admin.id)?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = app
+        .clone()
+        .oneshot(user_role("DELETE", root.id, admin.id)?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let assigner_roles = services::get_user_roles(&db_pool, assigner.id).await?;
+    assert!(!assigner_roles.iter().any(|role| role.id == admin.id));
+
+    // Act & Assert: Roles within their own permissions can be granted
+    let res = app
+        .clone()
+        .oneshot(user_role("PUT", root.id, reader.id)?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use auth::{
+    dto::{LoginReqDto, LoginResDto},
+    models::User,
+    services,
+    utils::{hash_password, AppConfig, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+
+use common::ctx;
+use serde_json::json;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+const PASSWORD: &str = "Narrow-Sc0pes";
+
+async fn login(app: &Router, scope: Option<&str>) -> AppResult<(StatusCode, Option<LoginResDto>)> {
+    let login_req_dto = LoginReqDto {
+        username: Some("integration".to_string()),
+        email: Some("integration@example.com".to_string()),
+        password: PASSWORD.to_string(),
+        scope: scope.map(str::to_string),
+        ..Default::default()
+    };
+    let login_req = Request::builder()
+        .uri("/auth/login")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&login_req_dto)?))?;
+
+    let res = app.clone().oneshot(login_req).await?;
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let login_res_dto = serde_json::from_slice::<SuccessResponse<LoginResDto>>(&body)
+        .ok()
+        .map(|res| res.body);
+    Ok((status, login_res_dto))
+}
+
+fn me(method: &str, token: &str) -> AppResult<Request<Body>> {
+    Ok(Request::builder()
+        .uri("/users/me")
+        .method(method)
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::from("{}"))?)
+}
+
+#[sqlx::test]
+async fn test_downscoped_tokens(db_pool: PgPool) -> AppResult<()> {
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+
+    // Arrange
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let user = User::new(
+        None,
+        "integration@example.com",
+        password_hash,
+        "integration",
+        None,
+    );
+    services::create_user(&db_pool, &user).await?;
+
+    // Act & Assert: Unknown scopes are rejected
+    let (status, _) = login(&app, Some("profile:read everything")).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Act: Log in for a read-only integration
+    let (status, res) = login(&app, Some("profile:read")).await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let token = res.expect("login should succeed").access_token;
+
+    // Assert: The profile can be read but neither changed nor deleted
+    let res = app.clone().oneshot(me("GET", &token)?).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = app.clone().oneshot(me("PATCH", &token)?).await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = app.clone().oneshot(me("DELETE", &token)?).await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // Act: Log in without a scope, then narrow the token on refresh
+    let (_, res) = login(&app, None).await?;
+    let refresh_token = res.expect("login should succeed").refresh_token;
+    let refresh = |scope: &str| -> AppResult<Request<Body>> {
+        Ok(Request::builder()
+            .uri("/sessions/refresh")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(
+                &json!({"refresh_token": refresh_token, "scope": scope}),
+            )?))?)
+    };
+    let res = app.clone().oneshot(refresh("profile:read")?).await?;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let refreshed: serde_json::Value = serde_json::from_slice(&body)?;
+    let token = refreshed["body"]["accessToken"]
+        .as_str()
+        .expect("access token should be returned");
+
+    // Assert: The refreshed token is limited to the requested scope
+    let res = app.clone().oneshot(me("GET", token)?).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = app.clone().oneshot(me("DELETE", token)?).await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
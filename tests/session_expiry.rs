@@ -0,0 +1,229 @@
+use auth::{
+    dto::{LoginReqDto, LoginResDto},
+    models::{Session, User},
+    services,
+    utils::{hash_password, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use common::{config, ctx_with};
+use serde_json::json;
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+const PASSWORD: &str = "Sl1d1ng-D00rs";
+
+/// Every test of this file runs with a 10 minute idle timeout and sliding sessions living
+/// at most 2 days.
+const OVERRIDES: &[(&str, &str)] = &[
+    ("session.idle_timeout_secs", "600"),
+    ("session.sliding_expiration", "true"),
+    ("session.max_lifetime_secs", "172800"),
+    ("jwt.refresh_token_expiration_secs", "86400"),
+];
+
+async fn create_user(db_pool: &PgPool) -> AppResult<User> {
+    let config = config(OVERRIDES)?;
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let user = User::new(None, "sliding@example.com", password_hash, "sliding", None);
+    services::create_user(db_pool, &user).await?;
+    Ok(user)
+}
+
+async fn login(app: &Router, remember_me: bool, scope: Option<&str>) -> AppResult<LoginResDto> {
+    let login_req_dto = LoginReqDto {
+        username: Some("sliding".to_string()),
+        email: Some("sliding@example.com".to_string()),
+        password: PASSWORD.to_string(),
+        scope: scope.map(str::to_string),
+        remember_me,
+        ..Default::default()
+    };
+    let login_req = Request::builder()
+        .uri("/auth/login")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&login_req_dto)?))?;
+
+    let res = app.clone().oneshot(login_req).await?;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    Ok(serde_json::from_slice::<SuccessResponse<LoginResDto>>(&body)?.body)
+}
+
+async fn refresh(app: &Router, refresh_token: &str) -> AppResult<StatusCode> {
+    let req = Request::builder()
+        .uri("/sessions/refresh")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(
+            &json!({ "refresh_token": refresh_token }),
+        )?))?;
+    Ok(app.clone().oneshot(req).await?.status())
+}
+
+/// Moves the timestamps of a session, as if time had passed.
+async fn age_session(
+    db_pool: &PgPool,
+    id: Uuid,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    last_used_at: DateTime<Utc>,
+) -> AppResult<()> {
+    sqlx::query(
+        "UPDATE sessions SET created_at = $2, expires_at = $3, last_used_at = $4 WHERE id = $1",
+    )
+    .bind(id)
+    .bind(created_at)
+    .bind(expires_at)
+    .bind(last_used_at)
+    .execute(db_pool)
+    .await?;
+    Ok(())
+}
+
+async fn get_session(db_pool: &PgPool, user: &User) -> AppResult<Option<Session>> {
+    services::get_session_by_user_id(db_pool, user.id).await
+}
+
+#[sqlx::test]
+async fn test_idle_timeout(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: A session last used 20 minutes ago, with a 10 minute idle timeout
+    let app = ctx_with(db_pool.clone(), OVERRIDES)?;
+    let user = create_user(&db_pool).await?;
+    let login_res = login(&app, false, None).await?;
+    let now = Utc::now();
+    age_session(
+        &db_pool,
+        login_res.session_id,
+        now - Duration::hours(1),
+        now + Duration::hours(23),
+        now - Duration::minutes(20),
+    )
+    .await?;
+
+    // Act
+    let status = refresh(&app, &login_res.refresh_token).await?;
+
+    // Assert: The session expired although its expiry is a day away
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(get_session(&db_pool, &user).await?.is_none());
+
+    // Act & Assert: Logging in again opens a new session
+    let again = login(&app, false, None).await?;
+    assert_ne!(again.session_id, login_res.session_id);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_sliding_expiration(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: A session opened an hour ago, close to its expiry
+    let app = ctx_with(db_pool.clone(), OVERRIDES)?;
+    let user = create_user(&db_pool).await?;
+    let login_res = login(&app, false, None).await?;
+    let now = Utc::now();
+    age_session(
+        &db_pool,
+        login_res.session_id,
+        now - Duration::hours(1),
+        now + Duration::minutes(5),
+        now - Duration::minutes(5),
+    )
+    .await?;
+
+    // Act
+    let status = refresh(&app, &login_res.refresh_token).await?;
+
+    // Assert: The session is extended by a full refresh period and marked as used
+    assert_eq!(status, StatusCode::CREATED);
+    let session = get_session(&db_pool, &user).await?.unwrap();
+    let extension = session.expires_at - (now + Duration::days(1));
+    assert!(extension.num_seconds().abs() < 60);
+    assert!(session.last_used_at >= now);
+
+    // Arrange: The same session opened 36 hours ago
+    age_session(
+        &db_pool,
+        login_res.session_id,
+        now - Duration::hours(36),
+        now + Duration::minutes(5),
+        now - Duration::minutes(5),
+    )
+    .await?;
+
+    // Act
+    let status = refresh(&app, &login_res.refresh_token).await?;
+
+    // Assert: The extension stops at the maximum lifetime, 2 days after login
+    assert_eq!(status, StatusCode::CREATED);
+    let session = get_session(&db_pool, &user).await?.unwrap();
+    let limit = session.expires_at - (session.created_at + Duration::days(2));
+    assert_eq!(limit.num_seconds(), 0);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_replaced_session(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: A session replaced by logging in again with another scope
+    let app = ctx_with(db_pool.clone(), OVERRIDES)?;
+    create_user(&db_pool).await?;
+    let replaced = login(&app, false, Some("profile:read")).await?;
+    let current = login(&app, false, None).await?;
+    assert_ne!(replaced.session_id, current.session_id);
+
+    // Act & Assert: The refresh token of the replaced session no longer works
+    let status = refresh(&app, &replaced.refresh_token).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Act & Assert: The one of the current session does
+    let status = refresh(&app, &current.refresh_token).await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_max_lifetime(db_pool: PgPool) -> AppResult<()> {
+    // Arrange
+    let app = ctx_with(db_pool.clone(), OVERRIDES)?;
+    let user = create_user(&db_pool).await?;
+
+    // Act: Log in with "remember me", whose lifetime is longer than the maximum
+    let login_res = login(&app, true, None).await?;
+
+    // Assert: The session ends at the maximum lifetime, 2 days after login
+    let session = get_session(&db_pool, &user).await?.unwrap();
+    let limit = session.expires_at - (session.created_at + Duration::days(2));
+    assert_eq!(limit.num_seconds(), 0);
+
+    // Arrange: The session opened a day ago, set to expire in 5 days
+    let now = Utc::now();
+    age_session(
+        &db_pool,
+        login_res.session_id,
+        now - Duration::days(1),
+        now + Duration::days(5),
+        now - Duration::minutes(5),
+    )
+    .await?;
+
+    // Act
+    let status = refresh(&app, &login_res.refresh_token).await?;
+
+    // Assert: Its use cuts it short to the maximum lifetime
+    assert_eq!(status, StatusCode::CREATED);
+    let session = get_session(&db_pool, &user).await?.unwrap();
+    let limit = session.expires_at - (session.created_at + Duration::days(2));
+    assert_eq!(limit.num_seconds(), 0);
+
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::{sync::Arc, time::Duration as StdDuration};
+
+use auth::{
+    models::{Impersonation, Session, User},
+    services,
+    stores::{PgStore, SessionStore, UserStore},
+    utils::AppResult,
+};
+use chrono::{Duration, Utc};
+use common::config;
+use sqlx::PgPool;
+use tokio_util::sync::CancellationToken;
+
+mod common;
+
+/// Creates a user holding a session that expires after `duration`.
+async fn user_with_session(
+    store: &PgStore,
+    username: &str,
+    duration: Duration,
+) -> AppResult<Session> {
+    let user = User::new(
+        None,
+        format!("{}@example.com", username),
+        "hash",
+        username,
+        None,
+    );
+    store.create_user(&user).await?;
+    store
+        .create_session(&Session::new(user.id, username, duration))
+        .await
+}
+
+#[sqlx::test]
+async fn test_reap_sessions(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: Sessions expired long ago, revoked, expired within the retention window, and live
+    let store = PgStore::new(db_pool.clone());
+    let old = user_with_session(&store, "old", Duration::days(-2)).await?;
+    let older = user_with_session(&store, "older", Duration::days(-3)).await?;
+    let recent = user_with_session(&store, "recent", Duration::minutes(-5)).await?;
+    let revoked = user_with_session(&store, "revoked", Duration::hours(1)).await?;
+    let live = user_with_session(&store, "live", Duration::hours(1)).await?;
+    let mut idle = user_with_session(&store, "idle", Duration::hours(1)).await?;
+    store.revoke_session(revoked.user_id).await?;
+    idle.last_used_at = Utc::now() - Duration::hours(2);
+    store.touch_session(&idle).await?;
+
+    // Act: Reap what went stale more than a day ago, then what went stale until now, one
+    // session per batch
+    let shutdown = CancellationToken::new();
+    let day_ago = services::reap_sessions(
+        &store,
+        Utc::now() - Duration::days(1),
+        Duration::zero(),
+        1,
+        &shutdown,
+    )
+    .await?;
+    let now = services::reap_sessions(
+        &store,
+        Utc::now() + Duration::seconds(1),
+        Duration::zero(),
+        1,
+        &shutdown,
+    )
+    .await?;
+
+    // Assert: Every stale session is gone, over several batches, and the live one remains
+    assert_eq!(day_ago, 2);
+    assert_eq!(now, 2);
+    for session in [old, older, recent, revoked] {
+        assert!(store
+            .get_session_by_user_id(session.user_id)
+            .await?
+            .is_none());
+    }
+    assert!(store.get_session_by_user_id(live.user_id).await?.is_some());
+    assert!(store.get_session_by_user_id(idle.user_id).await?.is_some());
+
+    // Act: Reap again with an idle timeout
+    let idle_reaped =
+        services::reap_sessions(&store, Utc::now(), Duration::hours(1), 1, &shutdown).await?;
+
+    // Assert: Only the session unused for longer than the timeout is gone
+    assert_eq!(idle_reaped, 1);
+    assert!(store.get_session_by_user_id(idle.user_id).await?.is_none());
+    assert!(store.get_session_by_user_id(live.user_id).await?.is_some());
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_session_reaper(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: A stale session, an expired invite, an expired and a live API key, an ended
+    // impersonation, and a reaper without a retention window
+    let config = config(&[("session_reaper.retention_secs", "0")])?;
+    let store = Arc::new(PgStore::new(db_pool.clone()));
+    let session = user_with_session(&store, "stale", Duration::seconds(-1)).await?;
+    let user_id = session.user_id;
+    let token = services::create_user_invite(&db_pool, user_id, Duration::seconds(-1)).await?;
+    let expires_at = |duration| Some(Utc::now() + duration);
+    services::create_api_key(
+        &db_pool,
+        user_id,
+        None,
+        "expired",
+        Vec::new(),
+        expires_at(Duration::seconds(-1)),
+    )
+    .await?;
+    let (live_key, _) = services::create_api_key(
+        &db_pool,
+        user_id,
+        None,
+        "live",
+        Vec::new(),
+        expires_at(Duration::hours(1)),
+    )
+    .await?;
+    let impersonation = services::create_impersonation(
+        &db_pool,
+        &Impersonation::new(user_id, user_id, None, Duration::hours(1)),
+    )
+    .await?;
+    services::end_impersonation(&db_pool, impersonation.id).await?;
+
+    // Act: Run the reaper until its first pass is done, then shut it down
+    let shutdown = CancellationToken::new();
+    let reaper = tokio::spawn(services::run_session_reaper(
+        store.clone(),
+        Some(db_pool.clone()),
+        config.get_session_reaper().clone(),
+        Duration::zero(),
+        shutdown.clone(),
+    ));
+    for _ in 0..50 {
+        if services::get_impersonation_by_id(&db_pool, impersonation.id)
+            .await?
+            .is_none()
+        {
+            break;
+        }
+        tokio::time::sleep(StdDuration::from_millis(100)).await;
+    }
+    shutdown.cancel();
+
+    // Assert: The reaper stops, having deleted all but the live API key
+    tokio::time::timeout(StdDuration::from_secs(5), reaper).await??;
+    assert!(store
+        .get_session_by_user_id(session.user_id)
+        .await?
+        .is_none());
+    assert!(services::get_user_invite_by_token(&db_pool, &token)
+        .await?
+        .is_none());
+    let key_ids: Vec<_> = services::get_api_keys_by_user_id(&db_pool, user_id)
+        .await?
+        .into_iter()
+        .map(|api_key| api_key.id)
+        .collect();
+    assert_eq!(key_ids, vec![live_key.id]);
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use auth::{
+    bootstrap::{create_memory_router, create_router_with_stores},
+    dto::{AccessTokenResDto, LoginReqDto, LoginResDto, UserReqDto, UserResDto},
+    models::{AuditEvent, Permission, Role, Session, User},
+    stores::{MemoryStore, SessionStore, Stores, UserStore},
+    utils::{hash_password, AppConfig, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::Duration;
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "Wh0-D1d-Wh4t";
+
+fn json_request(method: &str, uri: &str, body: serde_json::Value) -> AppResult<Request<Body>> {
+    Ok(Request::builder()
+        .uri(uri)
+        .method(method)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&body)?))?)
+}
+
+async fn send<T: serde::Serialize + serde::de::DeserializeOwned>(
+    app: &Router,
+    req: Request<Body>,
+    status: StatusCode,
+) -> AppResult<T> {
+    let res = app.clone().oneshot(req).await?;
+    assert_eq!(res.status(), status);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let res: SuccessResponse<T> = serde_json::from_slice(&body)?;
+    Ok(res.body)
+}
+
+#[tokio::test]
+async fn test_memory_router() -> AppResult<()> {
+    // Arrange: A router keeping everything in memory, with no database
+    dotenv::dotenv().ok();
+    let app = create_memory_router(AppConfig::new()?)?;
+    let register_req_dto = UserReqDto {
+        username: Some("alice".to_string()),
+        email: Some("alice@example.com".to_string()),
+        password: PASSWORD.to_string(),
+        avatar_url: None,
+        github_id: None,
+    };
+    let login_req_dto = LoginReqDto {
+        username: Some("alice".to_string()),
+        email: Some("alice@example.com".to_string()),
+        password: PASSWORD.to_string(),
+        ..Default::default()
+    };
+
+    // Act & Assert: Register, then register again
+    let user: UserResDto = send(
+        &app,
+        json_request("POST", "/users/register", json!(register_req_dto))?,
+        StatusCode::CREATED,
+    )
+    .await?;
+    assert_eq!(user.username, "alice");
+    let res = app
+        .clone()
+        .oneshot(json_request(
+            "POST",
+            "/users/register",
+            json!(register_req_dto),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    // Act & Assert: Log in, and log in again into the same session
+    let login: LoginResDto = send(
+        &app,
+        json_request("POST", "/auth/login", json!(login_req_dto))?,
+        StatusCode::CREATED,
+    )
+    .await?;
+    let again: LoginResDto = send(
+        &app,
+        json_request("POST", "/auth/login", json!(login_req_dto))?,
+        StatusCode::CREATED,
+    )
+    .await?;
+    assert_eq!(again.session_id, login.session_id);
+
+    // Act & Assert: Read and update the profile
+    let me: UserResDto = send(
+        &app,
+        Request::builder()
+            .uri("/users/me")
+            .header("Authorization", format!("Bearer {}", login.access_token))
+            .body(Body::empty())?,
+        StatusCode::OK,
+    )
+    .await?;
+    assert_eq!(me.email, "alice@example.com");
+    let mut req = json_request("PATCH", "/users/me", json!({ "password": PASSWORD }))?;
+    req.headers_mut().insert(
+        "Authorization",
+        format!("Bearer {}", login.access_token).parse()?,
+    );
+    let res = app.clone().oneshot(req).await?;
+    assert_eq!(
+        res.status(),
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "Reusing a password should be rejected from the in-memory history"
+    );
+
+    // Act & Assert: Refresh, log out, and fail to refresh again
+    let refresh = || {
+        json_request(
+            "POST",
+            "/sessions/refresh",
+            json!({ "refresh_token": login.refresh_token }),
+        )
+    };
+    let access: AccessTokenResDto = send(&app, refresh()?, StatusCode::CREATED).await?;
+    assert!(!access.access_token.is_empty());
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/auth/logout")
+                .method("POST")
+                .header("Authorization", format!("Bearer {}", login.access_token))
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let res = app.clone().oneshot(refresh()?).await?;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memory_store() -> AppResult<()> {
+    // Arrange: A router over a store the test keeps a handle on
+    dotenv::dotenv().ok();
+    let config = AppConfig::new()?;
+    let store = Arc::new(MemoryStore::new());
+    let db_pool =
+        PgPoolOptions::new().connect_lazy_with(config.get_database().to_pg_connect_options());
+    let app = create_router_with_stores(db_pool, config.clone(), Stores::new(store.clone()))?;
+
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let user = User::new(None, "bob@example.com", &password_hash, "bob", None);
+    store.create_user(&user).await?;
+    store
+        .create_session(&Session::new(user.id, "token", Duration::hours(1)))
+        .await?;
+
+    // Act: Log in with a wrong password, then delete the user
+    let res = app
+        .clone()
+        .oneshot(json_request(
+            "POST",
+            "/auth/login",
+            json!({ "username": "bob", "email": "bob@example.com", "password": "wrong" }),
+        )?)
+        .await?;
+    store.delete_user(user.id).await?;
+
+    // Assert: The failure is audited and the session goes with the user
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let events = store.audit_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event_type, AuditEvent::LOGIN_FAILED);
+    assert!(store.get_session_by_user_id(user.id).await?.is_none());
+
+    // Assert: Usernames stay unique
+    let duplicate = User::new(None, "other@example.com", "hash", "carol", None);
+    store.create_user(&duplicate).await?;
+    let mut clash = User::new(None, "carol@example.com", "hash", "carol", None);
+    assert!(store.create_user(&clash).await.is_err());
+    clash.username = "dave".to_string();
+    assert!(store.create_user(&clash).await.is_ok());
+
+    // Act: Carol becomes the only admin, and Dave an organization admin
+    store.assign_user_role(duplicate.id, Role::ADMIN)?;
+    let org_id = Uuid::new_v4();
+    store.assign_member_role(org_id, clash.id, Role::ORG_ADMIN)?;
+
+    // Assert: Roles grant what the migrations seed them with
+    assert!(store.assign_user_role(clash.id, "unknown").is_err());
+    let permissions = store.resolve_permissions(duplicate.id, None).await?;
+    assert_eq!(permissions.len(), Permission::ALL.len());
+    assert!(store
+        .resolve_permissions(duplicate.id, Some(org_id))
+        .await?
+        .is_empty());
+    assert!(store.resolve_permissions(clash.id, None).await?.is_empty());
+    let permissions = store.resolve_permissions(clash.id, Some(org_id)).await?;
+    assert!(permissions.contains(&Permission::USERS_DELETE.to_string()));
+    assert!(!permissions.contains(&Permission::ROLES_ASSIGN.to_string()));
+    assert!(store.is_last_admin(duplicate.id).await?);
+    assert!(!store.is_last_admin(clash.id).await?);
+    store.assign_user_role(clash.id, Role::ADMIN)?;
+    assert!(!store.is_last_admin(duplicate.id).await?);
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::sync::{Arc, Mutex};
+
+use auth::{
+    bootstrap::init_tracing,
+    models::User,
+    services,
+    utils::{hash_password, AppResult},
+};
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{Request, StatusCode},
+    routing::post,
+    Router,
+};
+use common::{config, ctx};
+use serde_json::json;
+use sqlx::PgPool;
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+mod common;
+
+const PASSWORD: &str = "Tr4ce-Every-H0p";
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_ID: &str = "00f067aa0ba902b7";
+
+/// Starts a stand-in OTLP/HTTP collector keeping the payloads it receives.
+async fn start_collector() -> AppResult<(String, Arc<Mutex<Vec<Bytes>>>)> {
+    let payloads = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new()
+        .route(
+            "/v1/traces",
+            post(
+                |State(payloads): State<Arc<Mutex<Vec<Bytes>>>>, body: Bytes| async move {
+                    payloads.lock().unwrap().push(body);
+                    StatusCode::OK
+                },
+            ),
+        )
+        .with_state(payloads.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    Ok((format!("http://{}", address), payloads))
+}
+
+/// Decodes a hex string into the bytes it encodes.
+fn decode_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[sqlx::test]
+async fn test_trace_export(db_pool: PgPool) -> AppResult<()> {
+    // Arrange: Traces exported to a collector, and a user to log in as
+    let (endpoint, payloads) = start_collector().await?;
+    let config = config(&[("telemetry.otlp_endpoint", &endpoint)])?;
+    let provider = init_tracing(config.get_telemetry())?.expect("an exporter should be set up");
+    let app = ctx(db_pool.clone())?;
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let user = User::new(None, "traced@example.com", password_hash, "traced", None);
+    services::create_user(&db_pool, &user).await?;
+
+    // Act: Log in as part of a caller's trace, request an unknown path, then flush the spans
+    let req = Request::builder()
+        .uri("/auth/login")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .header("traceparent", format!("00-{}-{}-01", TRACE_ID, PARENT_ID))
+        .body(Body::from(serde_json::to_string(&json!({
+            "username": "traced",
+            "email": "traced@example.com",
+            "password": PASSWORD,
+        }))?))?;
+    let res = app.clone().oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    // The request span ends along with the response body
+    drop(res);
+    let req = Request::builder()
+        .uri("/no-such-route/42")
+        .body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    drop(res);
+    tokio::task::spawn_blocking(move || provider.force_flush()).await??;
+
+    // Assert: The request and its queries are exported in the caller's trace
+    let payloads = payloads.lock().unwrap().concat();
+    assert!(contains(&payloads, &decode_hex(TRACE_ID)));
+    assert!(contains(&payloads, &decode_hex(PARENT_ID)));
+    assert!(contains(&payloads, b"POST /auth/login"));
+    assert!(contains(&payloads, b"create_session"));
+    assert!(contains(&payloads, b"postgresql"));
+
+    // Assert: Requests matching no route are named alike, whatever their path
+    assert!(contains(&payloads, b"unmatched"));
+    assert!(!contains(&payloads, b"GET /no-such-route"));
+
+    Ok(())
+}
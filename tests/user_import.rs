@@ -0,0 +1,141 @@
+use auth::{
+    dto::{BulkFormat, ImportUsersQueryDto},
+    services,
+    utils::{AppConfig, AppResult},
+};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use futures_util::TryStreamExt;
+
+use common::ctx;
+use serde_json::json;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+#[sqlx::test]
+async fn test_import_and_export_users(db_pool: PgPool) -> AppResult<()> {
+    dotenv::dotenv().ok();
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+
+    // Arrange: A CSV with a pre-hashed user, an invited user and two bad rows
+    let bcrypt_hash = bcrypt::hash("Imp0rted-Elsewhere", 4)?;
+    let csv = format!(
+        "username,email,password_hash\n\
+         ada,ada@example.com,{}\n\
+         grace,grace@example.com,\n\
+         x,not-an-email,\n\
+         ada,other@example.com,{}\n",
+        bcrypt_hash, bcrypt_hash
+    );
+    let mut options = ImportUsersQueryDto {
+        format: BulkFormat::Csv,
+        dry_run: true,
+        invite: true,
+    };
+
+    // Act: Dry run
+    let report = services::import_users(&db_pool, &config, None, csv.as_bytes(), &options).await?;
+
+    // Assert: Rows are validated but nothing is written
+    assert_eq!((report.total, report.imported, report.failed), (4, 2, 2));
+    assert_eq!(report.errors[0].line, 4);
+    assert_eq!(report.errors[1].message, "Duplicate username in import");
+    assert!(services::get_user_by_username(&db_pool, "ada")
+        .await?
+        .is_none());
+
+    // Act: Import for real
+    options.dry_run = false;
+    let report = services::import_users(&db_pool, &config, None, csv.as_bytes(), &options).await?;
+
+    // Assert: Users are created and the invited user gets a token
+    assert_eq!(report.imported, 2);
+    assert_eq!(report.invites.len(), 1);
+    assert_eq!(report.invites[0].email, "grace@example.com");
+
+    // Act: The invited user accepts with a password of their own
+    let accept_req = Request::builder()
+        .uri("/users/invites/accept")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&json!({
+            "token": report.invites[0].token,
+            "password": "Compil3rs-Are-Fun",
+        }))?))?;
+    let accept_res = app.clone().oneshot(accept_req).await?;
+
+    // Assert: The invite is consumed
+    assert_eq!(accept_res.status(), StatusCode::OK);
+    let reused_req = Request::builder()
+        .uri("/users/invites/accept")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&json!({
+            "token": report.invites[0].token,
+            "password": "Compil3rs-Are-Fun",
+        }))?))?;
+    assert_eq!(
+        app.oneshot(reused_req).await?.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    // Act: Export as JSON Lines
+    let chunks: Vec<_> = services::export_users(db_pool, None, BulkFormat::Jsonl)
+        .try_collect()
+        .await?;
+    let export = String::from_utf8(chunks.concat())?;
+
+    // Assert: Every user is exported without a password hash
+    assert_eq!(export.lines().count(), 2);
+    assert!(export.contains("\"username\":\"ada\""));
+    assert!(!export.contains("password_hash"));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_import_row_is_atomic(db_pool: PgPool) -> AppResult<()> {
+    dotenv::dotenv().ok();
+    let config = AppConfig::new()?;
+
+    // Arrange: Issuing invites fails partway through a row
+    sqlx::raw_sql(
+        r#"
+        CREATE FUNCTION fail_invite() RETURNS trigger AS $$
+        BEGIN
+            RAISE EXCEPTION 'invites are unavailable';
+        END;
+        $$ LANGUAGE plpgsql;
+        CREATE TRIGGER fail_invite BEFORE INSERT ON user_invites
+            FOR EACH ROW EXECUTE FUNCTION fail_invite();
+        "#,
+    )
+    .execute(&db_pool)
+    .await?;
+    let csv = "username,email,password_hash\ngrace,grace@example.com,\n";
+    let options = ImportUsersQueryDto {
+        format: BulkFormat::Csv,
+        dry_run: false,
+        invite: true,
+    };
+
+    // Act: Import the row
+    let result = services::import_users(&db_pool, &config, None, csv.as_bytes(), &options).await;
+
+    // Assert: The import aborts without leaving a user who was never invited
+    assert!(result.is_err());
+    assert!(services::get_user_by_username(&db_pool, "grace")
+        .await?
+        .is_none());
+    let outbox_rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM outbox_events")
+        .fetch_one(&db_pool)
+        .await?;
+    assert_eq!(outbox_rows, 0);
+
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use std::sync::{Arc, Mutex};
+
+use auth::{
+    dto::{
+        CreateWebhookSubscriptionResDto, GetAllWebhookDeliveriesResDto, WebhookSubscriptionReqDto,
+    },
+    models::{OutboxEvent, Role, User},
+    services,
+    utils::{hash_password, AppConfig, AppResult, SuccessResponse},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+
+use common::{ctx, login, request};
+use sqlx::PgPool;
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+mod common;
+
+const PASSWORD: &str = "H00k-Me-Up";
+
+type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+/// Serves an endpoint recording the deliveries it accepts, and another one failing them all.
+async fn receiver() -> AppResult<(String, Received)> {
+    let received = Received::default();
+    let app = Router::new()
+        .route(
+            "/hook",
+            post({
+                let received = received.clone();
+                move |headers: HeaderMap, body: String| async move {
+                    received.lock().unwrap().push((headers, body));
+                    StatusCode::NO_CONTENT
+                }
+            }),
+        )
+        .route(
+            "/broken",
+            post(|| async { StatusCode::INTERNAL_SERVER_ERROR }),
+        );
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    Ok((format!("http://{}", address), received))
+}
+
+async fn subscribe(
+    app: &Router,
+    token: &str,
+    url: String,
+    event_types: &[&str],
+) -> AppResult<CreateWebhookSubscriptionResDto> {
+    let dto = WebhookSubscriptionReqDto {
+        url,
+        event_types: event_types.iter().map(|t| t.to_string()).collect(),
+    };
+    let res = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/webhooks",
+            token,
+            Body::from(serde_json::to_string(&dto)?),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let created: SuccessResponse<CreateWebhookSubscriptionResDto> = serde_json::from_slice(&body)?;
+    Ok(created.body)
+}
+
+#[sqlx::test]
+async fn test_webhooks(db_pool: PgPool) -> AppResult<()> {
+    let config = AppConfig::new()?;
+    let app = ctx(db_pool.clone())?;
+    let client = reqwest::Client::new();
+    let (base_url, received) = receiver().await?;
+
+    // Arrange: An admin subscribing a working and a broken endpoint
+    let password_hash = hash_password(PASSWORD, config.get_password_hashing())?;
+    let root = User::new(None, "root@example.com", &password_hash, "root", None);
+    services::create_user(&db_pool, &root).await?;
+    let admin = services::get_role_by_name(&db_pool, Role::ADMIN)
+        .await?
+        .expect("admin role should be seeded");
+    services::assign_user_role(&db_pool, root.id, &admin).await?;
+    let token = login(&app, "root", PASSWORD).await?;
+
+    let res = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/webhooks",
+            &token,
+            Body::from(r#"{"url": "http://localhost/hook", "event_types": ["user.renamed"]}"#),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let hook = subscribe(&app, &token, format!("{}/hook", base_url), &[]).await?;
+    subscribe(
+        &app,
+        &token,
+        format!("{}/broken", base_url),
+        &[OutboxEvent::USER_DELETED],
+    )
+    .await?;
+
+    // Act: A user registers, changes their email and is deleted
+    let alice = User::new(None, "alice@example.com", &password_hash, "alice", None);
+    let mut alice = services::create_user(&db_pool, &alice).await?;
+    alice.email = "alice@example.org".to_string();
+    services::update_user(&db_pool, &alice).await?;
+    services::delete_user(&db_pool, alice.id).await?;
+    services::dispatch_webhooks(&db_pool, &client, config.get_webhooks()).await?;
+
+    // Assert: The working endpoint received every event in order, signed with its secret
+    let received = received.lock().unwrap().clone();
+    let event_types: Vec<String> = received
+        .iter()
+        .map(|(headers, _)| headers["X-Webhook-Event"].to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        event_types,
+        vec![
+            OutboxEvent::USER_CREATED,
+            OutboxEvent::USER_CREATED,
+            OutboxEvent::USER_EMAIL_CHANGED,
+            OutboxEvent::USER_DELETED,
+        ]
+    );
+    let (headers, body) = &received[2];
+    let timestamp: i64 = headers["X-Webhook-Timestamp"].to_str()?.parse()?;
+    assert_eq!(
+        headers["X-Webhook-Signature"].to_str()?,
+        services::sign_webhook(&hook.secret, timestamp, body)
+    );
+    let payload: serde_json::Value = serde_json::from_str(body)?;
+    assert_eq!(payload["data"]["email"], "alice@example.org");
+    assert_eq!(payload["data"]["previousEmail"], "alice@example.com");
+
+    // Act: Keep retrying the broken endpoint until it runs out of attempts
+    for _ in 1..*config.get_webhooks().get_max_attempts() {
+        sqlx::query("UPDATE webhook_deliveries SET next_attempt_at = NOW()")
+            .execute(&db_pool)
+            .await?;
+        services::dispatch_webhooks(&db_pool, &client, config.get_webhooks()).await?;
+    }
+
+    // Assert: The failed delivery ends up as a dead letter that can be retried
+    let res = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/webhooks/dead-letters",
+            &token,
+            Body::empty(),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await?;
+    let dead: SuccessResponse<GetAllWebhookDeliveriesResDto> = serde_json::from_slice(&body)?;
+    assert_eq!(dead.body.deliveries.len(), 1);
+    let delivery = &dead.body.deliveries[0];
+    assert_eq!(delivery.attempts, *config.get_webhooks().get_max_attempts());
+    assert!(delivery.last_error.is_some());
+
+    let res = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            &format!("/webhooks/dead-letters/{}/retry", delivery.id),
+            &token,
+            Body::empty(),
+        )?)
+        .await?;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let dead = services::get_dead_webhook_deliveries(&db_pool, 10, 0).await?;
+    assert!(dead.is_empty());
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_webhooks_in_claims(db_pool: PgPool) -> AppResult<()> {
+    let config = AppConfig::new()?;
+    let client = reqwest::Client::new();
+    let (base_url, received) = receiver().await?;
+
+    // Arrange: More events than a single claim holds
+    services::create_webhook_subscription(&db_pool, &format!("{}/hook", base_url), Vec::new())
+        .await?;
+    for i in 0..25 {
+        let user = User::new(
+            None,
+            format!("user{}@example.com", i),
+            "hash",
+            format!("user{}", i),
+            None,
+        );
+        services::create_user(&db_pool, &user).await?;
+    }
+
+    // Act: Dispatch once
+    let attempted = services::dispatch_webhooks(&db_pool, &client, config.get_webhooks()).await?;
+
+    // Assert: Every delivery is attempted in the same pass, claim after claim
+    assert_eq!(attempted, 25);
+    assert_eq!(received.lock().unwrap().len(), 25);
+    let pending: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM webhook_deliveries WHERE delivered_at IS NULL")
+            .fetch_one(&db_pool)
+            .await?;
+    assert_eq!(pending, 0);
+
+    Ok(())
+}